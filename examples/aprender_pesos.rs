@@ -0,0 +1,57 @@
+// Aprende pesos sobre datos propios, sin leerlos de un archivo ARFF
+// Genera datos en los que la clase depende solo de los dos primeros atributos, ejecuta RELIEF, un genético
+//   y una tubería RELIEF + búsqueda local, y muestra los pesos aprendidos y su validación cruzada
+// Uso: cargo run --release --example aprender_pesos
+
+#[macro_use] extern crate aprendizaje_pesos;
+extern crate rand;
+
+use aprendizaje_pesos::aprendiz::{Aprendiz, Tuberia};
+use aprendizaje_pesos::evaluacion_pesos;
+use aprendizaje_pesos::evaluacion_pesos::Validacion;
+use aprendizaje_pesos::funciones_practica1::{busqueda_local_desde, relief};
+use aprendizaje_pesos::funciones_practica2::agg_blx;
+use aprendizaje_pesos::knn::{Dato, Normalizacion};
+use aprendizaje_pesos::parametros::Parametros;
+use aprendizaje_pesos::presupuesto::Presupuesto;
+use rand::{Isaac64Rng, Rng};
+
+
+// Genera n datos con 6 atributos en [0, 10]: la clase es 1 si la suma de los dos primeros supera 10,
+//   y los demás atributos son ruido
+fn generar_datos(n: usize, rng: &mut Isaac64Rng) -> Vec<Dato> {
+    (0..n).map(|_| {
+        let atributos: Vec<f64> = (0..6).map(|_| rng.gen_range(0.0, 10.0)).collect();
+        let clase = if atributos[0] + atributos[1] > 10.0 { 1 } else { 0 };
+        Dato::new(&atributos, &[], clase)
+    }).collect()
+}
+
+fn main() {
+    let mut rng = evaluacion_pesos::rng_semilla(2018);
+    let mut datos = generar_datos(200, &mut rng);
+    // Los algoritmos esperan los atributos numéricos en [0, 1]
+    Normalizacion::ajustar(&datos).aplicar(&mut datos);
+
+    let parametros = Parametros { max_evaluaciones: 2000, ..Parametros::default() };
+    let aprendices: Vec<Box<dyn Aprendiz>> = vec![
+        Box::new(aprendiz!(relief, "RELIEF")),
+        Box::new(aprendiz!(agg_blx, "AGG_BLX").con_parametros(parametros)),
+        Box::new(Tuberia::desde(aprendiz!(relief, "RELIEF"))
+                     .mejora("busqueda_local_desde", busqueda_local_desde)
+                     .parametros(parametros)
+                     .construir()),
+    ];
+
+    for aprendiz in &aprendices {
+        println!("\n{} ({})", aprendiz.nombre(), aprendiz.a_json());
+        let aprendizaje = aprendiz.aprender(&datos, Presupuesto::default(), &mut rng);
+        let pesos: Vec<String> = aprendizaje.pesos.iter().map(|w| format!("{:.3}", w)).collect();
+        println!("Pesos con todos los datos: {} ({} evaluaciones)", pesos.join(" "), aprendizaje.evaluaciones);
+
+        match evaluacion_pesos::ffcv(&|d: &[Dato], r: &mut Isaac64Rng| aprendiz.aprender(d, Presupuesto::default(), r), &datos, 42, Validacion::KFold(5, 1), 1) {
+            Ok(resultados) => evaluacion_pesos::mostrar_resultados(&resultados, Presupuesto::default()),
+            Err(e) => println!("No se puede validar con estos datos: {}", e),
+        }
+    }
+}
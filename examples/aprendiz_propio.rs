@@ -0,0 +1,60 @@
+// Define un algoritmo de aprendizaje de pesos fuera de la biblioteca y lo prueba como los de las prácticas
+// Implementa una búsqueda aleatoria con el rasgo Aprendiz y la compara con RELIEF en un archivo de datos,
+//   usando el planificador para repartir los tests entre los hilos disponibles
+// Uso: cargo run --release --example aprendiz_propio [archivo.arff]
+
+#[macro_use] extern crate aprendizaje_pesos;
+extern crate rand;
+
+use aprendizaje_pesos::aprendiz::Aprendiz;
+use aprendizaje_pesos::evaluacion_pesos;
+use aprendizaje_pesos::funciones_practica1::relief;
+use aprendizaje_pesos::json::Valor;
+use aprendizaje_pesos::knn;
+use aprendizaje_pesos::knn::Dato;
+use aprendizaje_pesos::parametros::Parametros;
+use aprendizaje_pesos::planificador;
+use aprendizaje_pesos::planificador::{Datos, Planificacion};
+use aprendizaje_pesos::presupuesto::{Evaluador, Presupuesto};
+use rand::{Isaac64Rng, Rng};
+use std::env;
+
+
+// Búsqueda aleatoria: evalúa soluciones aleatorias y se queda con la mejor
+struct BusquedaAleatoria {
+    intentos: usize,
+}
+
+impl Aprendiz for BusquedaAleatoria {
+    fn nombre(&self) -> &str { "Búsqueda aleatoria" }
+
+    fn parametros(&self) -> Option<Parametros> { None }
+
+    fn pesos(&self, entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Isaac64Rng) -> Vec<f64> {
+        let n = entrenamiento[0].num_atributos();
+        (0..self.intentos).map(|_| {
+            let w: Vec<f64> = (0..n).map(|_| rng.gen_range(0.0, 1.0)).collect();
+            (evaluacion_pesos::evaluar(evaluador, entrenamiento, &w), w)
+        }).fold((-1.0, Vec::new()), |mejor, s| if s.0 > mejor.0 { s } else { mejor }).1
+    }
+
+    fn a_json(&self) -> Valor {
+        objeto_json!{ "nombre" => self.nombre(), "intentos" => self.intentos }
+    }
+}
+
+fn main() {
+    let archivo = env::args().nth(1).unwrap_or_else(|| "instances/parkinsons.arff".to_string());
+    let datos = knn::leer_archivo(&archivo).unwrap_or_else(|e| panic!("No se pudo abrir el archivo {}: {}", archivo, e));
+
+    let aleatoria = BusquedaAleatoria { intentos: 200 };
+    let relief = aprendiz!(relief, "RELIEF");
+    let aprendices: Vec<&dyn Aprendiz> = vec![&relief, &aleatoria];
+    let conjuntos = vec![(archivo.as_str(), Datos::Archivo(datos))];
+    let plan = Planificacion { semilla: 42, validacion: Default::default(), ejecuciones: 1, repetir: None, hilos: 0, presupuesto: Presupuesto::default() };
+
+    planificador::ejecutar(&aprendices, &conjuntos, &plan, None, None, |_, a, resultados| {
+        println!("\n{} sobre los datos en {}...", aprendices[a].nombre(), archivo);
+        evaluacion_pesos::mostrar_resultados(&resultados, plan.presupuesto);
+    });
+}
@@ -0,0 +1,52 @@
+// Optimiza con las metaheurísticas de las prácticas un problema que no es de aprendizaje de pesos
+// Implementa el rasgo Problema para la función de Rosenbrock en [-2, 2]^n (que se maximiza cambiada de signo)
+//   y la resuelve con evolución diferencial y con enfriamiento simulado, usando los operadores generales
+// Uso: cargo run --release --example problema_propio [dimensión]
+
+extern crate aprendizaje_pesos;
+extern crate rand;
+
+use aprendizaje_pesos::funciones_practica3::{differential_evolution_general, simulated_annealing_general, op_rand_1};
+use aprendizaje_pesos::presupuesto::{Evaluador, Presupuesto};
+use aprendizaje_pesos::problema;
+use aprendizaje_pesos::problema::Problema;
+use rand::{Isaac64Rng, SeedableRng};
+use std::env;
+
+
+// Función de Rosenbrock, con su mínimo (0) en (1, 1, ..., 1)
+struct Rosenbrock {
+    dimension: usize,
+}
+
+impl Problema for Rosenbrock {
+    fn dimension(&self) -> usize { self.dimension }
+
+    fn limites(&self, _i: usize) -> (f64, f64) { (-2.0, 2.0) }
+
+    fn funcion_objetivo(&self, x: &[f64]) -> f64 {
+        -x.windows(2).map(|p| 100.0*(p[1] - p[0]*p[0]).powi(2) + (1.0 - p[0]).powi(2)).sum::<f64>()
+    }
+}
+
+fn main() {
+    let dimension = env::args().nth(1).map_or(5, |d| d.parse().expect("La dimensión debe ser un número natural"));
+    let rosenbrock = Rosenbrock { dimension };
+
+    let mostrar = |nombre: &str, x: &[f64], evaluador: &Evaluador| {
+        println!("{}: f = {:.6} con {} evaluaciones", nombre, -rosenbrock.funcion_objetivo(x), evaluador.usadas());
+        println!("  x = {:?}", x.iter().map(|xi| format!("{:.4}", xi)).collect::<Vec<_>>());
+    };
+
+    let mut rng = Isaac64Rng::from_seed(&[42]);
+    let evaluador = Evaluador::nuevo(Presupuesto::default());
+    let x = differential_evolution_general(&rosenbrock, &problema::solucion_aleatoria, &op_rand_1, &evaluador, &mut rng);
+    mostrar("DE/rand/1", &x, &evaluador);
+
+    let mut rng = Isaac64Rng::from_seed(&[42]);
+    let evaluador = Evaluador::nuevo(Presupuesto::default());
+    let vecino = |x: &[f64], i: usize, rng: &mut Isaac64Rng| problema::mutacion_normal(&rosenbrock, x, i, 0.05, rng);
+    let enfriamiento = |t: f64, t_inicial: f64, t_final: f64, n: usize| t*(t_final/t_inicial).powf(1.0 / n as f64);
+    let x = simulated_annealing_general(&rosenbrock, &problema::solucion_aleatoria, &vecino, &enfriamiento, &evaluador, &mut rng);
+    mostrar("Enfriamiento simulado", &x, &evaluador);
+}
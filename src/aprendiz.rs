@@ -0,0 +1,324 @@
+// Interfaz común de los algoritmos de aprendizaje de pesos
+// Antes cada algoritmo era solo una función (ver Algoritmo), y al combinarlos con combinar se perdía su nombre
+//   y sus parámetros. Un aprendiz conserva ambos, informa de las evaluaciones que ha usado y se describe en JSON
+//   con el mismo formato con el que se leen los algoritmos de los archivos de experimento
+// Las funciones de las prácticas se envuelven con el macro aprendiz!, y con una Tuberia se encadenan con
+//   mejoras (procedimientos que parten de una solución, como la búsqueda local) para formar nuevos aprendices
+
+use evaluacion_pesos;
+use json::Valor;
+use knn::Dato;
+use parametros;
+use parametros::Parametros;
+use presupuesto::{Evaluador, Presupuesto};
+use rand::Isaac64Rng;
+
+
+// Tipo de dato de las funciones que implementan los algoritmos de aprendizaje de pesos
+pub type Algoritmo = fn(&[Dato], &Evaluador, &mut Isaac64Rng) -> Vec<f64>;
+
+// Tipo de dato de los procedimientos que mejoran una solución dada, como busqueda_local_desde
+pub type Mejora = fn(&[Dato], &[f64], &Evaluador, &mut Isaac64Rng) -> Vec<f64>;
+
+// Pesos aprendidos por un aprendiz junto a las evaluaciones de la función objetivo que ha usado
+pub struct Aprendizaje {
+    pub pesos: Vec<f64>,
+    pub evaluaciones: usize,
+}
+
+// Algoritmo de aprendizaje de pesos
+pub trait Aprendiz: Sync {
+    // Nombre con el que se muestran sus resultados
+    fn nombre(&self) -> &str;
+
+    // Parámetros con los que se ejecuta. Si no tiene, usa los del hilo (ver parametros::parametros)
+    fn parametros(&self) -> Option<Parametros>;
+
+    // Obtiene los pesos a partir de unos datos de entrenamiento, evaluando las soluciones con el evaluador indicado
+    fn pesos(&self, entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Isaac64Rng) -> Vec<f64>;
+
+    // Representación en JSON, como las de la lista de algoritmos de los experimentos
+    fn a_json(&self) -> Valor;
+
+    // Comprueba que se puede aplicar a unos datos antes de ejecutarlo. Casi todos se aplican a cualquiera
+    fn comprobar(&self, _datos: &[Dato]) -> Result<(), String> {
+        Ok(())
+    }
+
+    // Obtiene los pesos con un presupuesto y cuenta las evaluaciones de la función objetivo que se hacen para ello
+    fn aprender(&self, entrenamiento: &[Dato], presupuesto: Presupuesto, rng: &mut Isaac64Rng) -> Aprendizaje {
+        let evaluador = Evaluador::nuevo(presupuesto);
+        let pesos = self.pesos(entrenamiento, &evaluador, rng);
+        Aprendizaje { pesos, evaluaciones: evaluador.usadas() }
+    }
+}
+
+// Ejecuta una función con los parámetros indicados, si los hay, y restaura después los que tenía el hilo
+fn con_parametros<T, F: FnOnce() -> T>(parametros: Option<Parametros>, f: F) -> T {
+    match parametros {
+        Some(p) => {
+            let anteriores = parametros::parametros();
+            parametros::fijar_parametros(p);
+            let resultado = f();
+            parametros::fijar_parametros(anteriores);
+            resultado
+        },
+        None => f(),
+    }
+}
+
+// Representación en JSON común a los aprendices con clave
+fn json_aprendiz(clave: &str, nombre: &str, parametros: Option<Parametros>, mejoras: &[(&str, Mejora)]) -> Valor {
+    let mut campos = vec![("algoritmo".to_string(), Valor::from(clave)), ("nombre".to_string(), Valor::from(nombre))];
+    if !mejoras.is_empty() {
+        campos.push(("mejoras".to_string(), Valor::from(mejoras.iter().map(|m| m.0).collect::<Vec<&str>>())));
+    }
+    if let Some(p) = parametros {
+        campos.push(("parametros".to_string(), p.a_json()));
+    }
+    Valor::Objeto(campos)
+}
+
+
+
+// Aprendiz implementado por una de las funciones de las prácticas
+#[derive(Clone)]
+pub struct Funcion {
+    pub clave: &'static str,     // Nombre de la función, con el que se busca en el catálogo
+    pub nombre: String,
+    pub funcion: Algoritmo,
+    pub parametros: Option<Parametros>,
+}
+
+impl Funcion {
+    pub fn nueva(clave: &'static str, nombre: &str, funcion: Algoritmo) -> Funcion {
+        Funcion { clave, nombre: nombre.to_string(), funcion, parametros: None }
+    }
+
+    // Cambia el nombre con el que se muestran sus resultados
+    pub fn con_nombre(mut self, nombre: &str) -> Funcion {
+        self.nombre = nombre.to_string();
+        self
+    }
+
+    // Fija los parámetros con los que se ejecuta
+    pub fn con_parametros(mut self, parametros: Parametros) -> Funcion {
+        self.parametros = Some(parametros);
+        self
+    }
+}
+
+impl Aprendiz for Funcion {
+    fn nombre(&self) -> &str { &self.nombre }
+
+    fn parametros(&self) -> Option<Parametros> { self.parametros }
+
+    fn pesos(&self, entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Isaac64Rng) -> Vec<f64> {
+        con_parametros(self.parametros, || (self.funcion)(entrenamiento, evaluador, rng))
+    }
+
+    fn a_json(&self) -> Valor {
+        json_aprendiz(self.clave, &self.nombre, self.parametros, &[])
+    }
+}
+
+// Envuelve una función de las prácticas en un aprendiz, con la clave del nombre de la función
+// Uso: aprendiz!(funcion, "Nombre")
+#[macro_export]
+macro_rules! aprendiz {
+    ( $funcion:ident, $nombre:expr ) => {
+        $crate::aprendiz::Funcion::nueva(stringify!($funcion), $nombre, $funcion)
+    };
+}
+
+
+
+// Aprendiz que obtiene una solución con un aprendiz inicial y la mejora con uno o varios procedimientos,
+//   uno tras otro, como hace combinar con una búsqueda local
+pub struct Tuberia {
+    nombre: String,
+    inicial: Funcion,
+    mejoras: Vec<(&'static str, Mejora)>,  // Clave y función de cada mejora
+    parametros: Option<Parametros>,
+}
+
+// Constructor de tuberías
+// Uso: Tuberia::desde(aprendiz!(relief, "RELIEF")).mejora("busqueda_local_desde", busqueda_local_desde).construir()
+pub struct ConstructorTuberia {
+    tuberia: Tuberia,
+}
+
+impl Tuberia {
+    // Empieza una tubería con el aprendiz que da la solución inicial
+    pub fn desde(inicial: Funcion) -> ConstructorTuberia {
+        let nombre = inicial.nombre.clone();
+        ConstructorTuberia { tuberia: Tuberia { nombre, inicial, mejoras: Vec::new(), parametros: None } }
+    }
+}
+
+impl ConstructorTuberia {
+    // Añade una mejora que se aplica a la solución de los pasos anteriores
+    pub fn mejora(mut self, clave: &'static str, mejora: Mejora) -> ConstructorTuberia {
+        self.tuberia.mejoras.push((clave, mejora));
+        self
+    }
+
+    // Cambia el nombre con el que se muestran sus resultados, que por defecto es el del aprendiz inicial
+    //   seguido de las claves de las mejoras
+    pub fn nombre(mut self, nombre: &str) -> ConstructorTuberia {
+        self.tuberia.nombre = nombre.to_string();
+        self
+    }
+
+    // Fija los parámetros con los que se ejecutan todos los pasos
+    pub fn parametros(mut self, parametros: Parametros) -> ConstructorTuberia {
+        self.tuberia.parametros = Some(parametros);
+        self
+    }
+
+    pub fn construir(mut self) -> Tuberia {
+        if self.tuberia.nombre == self.tuberia.inicial.nombre {
+            for m in &self.tuberia.mejoras {
+                self.tuberia.nombre.push_str(&format!(" + {}", m.0));
+            }
+        }
+        self.tuberia
+    }
+}
+
+impl Aprendiz for Tuberia {
+    fn nombre(&self) -> &str { &self.nombre }
+
+    fn parametros(&self) -> Option<Parametros> { self.parametros }
+
+    fn pesos(&self, entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Isaac64Rng) -> Vec<f64> {
+        con_parametros(self.parametros, || {
+            // Todos los pasos comparten el presupuesto de la tubería: cada uno puede usar su parte de las evaluaciones
+            //   que dejan los anteriores, de forma que las que no use pasan a los siguientes, y el tiempo corre desde el primero
+            let pasos = 1 + self.mejoras.len();
+            let parte = |paso: usize| evaluador.restantes() / (pasos - paso);
+            let inicial = evaluador.con_limite(parte(0), || self.inicial.pesos(entrenamiento, evaluador, rng));
+            self.mejoras.iter().enumerate().fold(inicial, |w, (i, m)| {
+                evaluador.con_limite(parte(i + 1), || (m.1)(entrenamiento, &w, evaluador, rng))
+            })
+        })
+    }
+
+    fn a_json(&self) -> Valor {
+        json_aprendiz(self.inicial.clave, &self.nombre, self.parametros, &self.mejoras)
+    }
+}
+
+
+
+// Aprendiz que devuelve siempre unos pesos indicados por el usuario (ver evaluacion_pesos::pesos_fijos)
+// Solo se aplica a datos con un atributo por peso, lo que se comprueba antes de ejecutarlo
+pub struct PesosFijos {
+    nombre: String,
+    pesos: Vec<f64>,
+}
+
+impl PesosFijos {
+    // Devuelve un error si no hay pesos o alguno no es finito
+    pub fn nuevos(nombre: &str, pesos: Vec<f64>) -> Result<PesosFijos, String> {
+        if pesos.is_empty() {
+            return Err("No se han indicado pesos".to_string());
+        }
+        if pesos.iter().any(|w| !w.is_finite()) {
+            return Err("Los pesos deben ser números finitos".to_string());
+        }
+        Ok(PesosFijos { nombre: nombre.to_string(), pesos })
+    }
+}
+
+impl Aprendiz for PesosFijos {
+    fn nombre(&self) -> &str { &self.nombre }
+
+    fn parametros(&self) -> Option<Parametros> { None }
+
+    fn pesos(&self, _entrenamiento: &[Dato], _evaluador: &Evaluador, _rng: &mut Isaac64Rng) -> Vec<f64> {
+        self.pesos.clone()
+    }
+
+    fn a_json(&self) -> Valor {
+        objeto_json!{
+            "nombre" => self.nombre.as_str(),
+            "pesos" => self.pesos.as_slice(),
+        }
+    }
+
+    fn comprobar(&self, datos: &[Dato]) -> Result<(), String> {
+        evaluacion_pesos::comprobar_pesos(&self.pesos, datos)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Duration;
+
+    // Pasos de tubería que agotan el presupuesto que se les da y añaden a la solución las evaluaciones que han hecho
+    fn agotar(_: &[Dato], evaluador: &Evaluador, _: &mut Isaac64Rng) -> Vec<f64> {
+        agotar_desde(&[], &[], evaluador, &mut evaluacion_pesos::rng_semilla(0))
+    }
+
+    fn agotar_desde(_: &[Dato], w: &[f64], evaluador: &Evaluador, _: &mut Isaac64Rng) -> Vec<f64> {
+        let antes = evaluador.usadas();
+        while !evaluador.agotado() {
+            evaluador.evaluar(|| 0.0);
+        }
+        let mut w = w.to_vec();
+        w.push((evaluador.usadas() - antes) as f64);
+        w
+    }
+
+    // Paso inicial que no evalúa nada pero tarda un poco
+    fn esperar(_: &[Dato], _: &Evaluador, _: &mut Isaac64Rng) -> Vec<f64> {
+        thread::sleep(Duration::from_millis(20));
+        Vec::new()
+    }
+
+    // Mejora que añade a la solución el tiempo transcurrido desde el inicio del presupuesto
+    fn cronometrar(_: &[Dato], w: &[f64], evaluador: &Evaluador, _: &mut Isaac64Rng) -> Vec<f64> {
+        let mut w = w.to_vec();
+        w.push(evaluador.transcurrido().as_millis() as f64);
+        w
+    }
+
+    #[test]
+    fn las_tuberias_reparten_el_presupuesto() {
+        let presupuesto = Presupuesto { evaluaciones: Some(100), ..Default::default() };
+        let datos = [Dato::new(&[0.0], &[], 0)];
+        let rng = &mut evaluacion_pesos::rng_semilla(0);
+
+        let tuberia = Tuberia::desde(Funcion::nueva("agotar", "Agotar", agotar))
+            .mejora("a", agotar_desde).mejora("b", agotar_desde).construir();
+        let aprendizaje = tuberia.aprender(&datos, presupuesto, rng);
+        assert_eq!(aprendizaje.pesos, vec![33.0, 33.0, 34.0]);
+        assert_eq!(aprendizaje.evaluaciones, 100);
+
+        // Las evaluaciones que no usa un paso pasan a los siguientes (aquí, 100/3 en lugar de 100/4 para
+        //   el primero que evalúa), y el reloj no se reinicia entre pasos
+        let tuberia = Tuberia::desde(Funcion::nueva("esperar", "Esperar", esperar))
+            .mejora("a", agotar_desde).mejora("b", agotar_desde).mejora("c", cronometrar).construir();
+        let evaluador = Evaluador::nuevo(presupuesto);
+        let pesos = tuberia.pesos(&datos, &evaluador, rng);
+        assert_eq!(pesos[..2], [33.0, 33.0]);
+        assert!(pesos[2] >= 20.0);
+        assert_eq!(evaluador.usadas(), 66);
+    }
+
+    #[test]
+    fn pesos_fijos_validos() {
+        assert!(PesosFijos::nuevos("vacíos", Vec::new()).is_err());
+        assert!(PesosFijos::nuevos("infinitos", vec![1.0, f64::INFINITY]).is_err());
+        let fijos = PesosFijos::nuevos("fijos", vec![0.2, 0.8]).unwrap();
+        let datos = |n: usize| vec![Dato::new(&vec![0.0; n], &[], 0)];
+        assert!(fijos.comprobar(&datos(2)).is_ok());
+        assert!(fijos.comprobar(&datos(3)).is_err());
+        assert_eq!(fijos.pesos(&datos(2), &Evaluador::nuevo(Presupuesto::default()), &mut evaluacion_pesos::rng_semilla(0)), vec![0.2, 0.8]);
+        assert_eq!(fijos.a_json().to_string(), r#"{"nombre":"fijos","pesos":[0.2,0.8]}"#);
+    }
+}
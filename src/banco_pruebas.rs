@@ -0,0 +1,443 @@
+// Banco de pruebas de las metaheurísticas con funciones continuas clásicas
+// Cada función de prueba es un problema (ver problema.rs) de mínimo conocido, de forma que se puede medir
+//   lo lejos que se queda de él cada algoritmo con un presupuesto de evaluaciones fijo
+// Como en las competiciones del CEC, las funciones se pueden desplazar (el óptimo se mueve a un punto aleatorio)
+//   y rotar (las variables dejan de ser separables), para que los algoritmos no se beneficien de que el óptimo
+//   esté en el centro del dominio o de optimizar cada componente por separado
+
+use evaluacion_pesos;
+use funciones_practica1::busqueda_local_generica_desde;
+use funciones_practica2::{genetico_estacionario, genetico_generacional};
+use funciones_practica3::{differential_evolution_general, enfriamiento_cauchy, iterated_local_search_general,
+                          op_ctb_1, op_rand_1, simulated_annealing_general};
+use funciones_tabu::tabu_search_general;
+use parametros::parametros;
+use presupuesto::{Evaluador, Presupuesto};
+use problema;
+use problema::Problema;
+use rand::{Isaac64Rng, Rng};
+use rand::distributions::{Normal, Sample};
+use std;
+use std::f64::consts::PI;
+use std::str::FromStr;
+use std::time::Instant;
+
+
+
+// Funciones de prueba
+// Todas tienen su mínimo, de valor 0, en el punto (c, c, ..., c), siendo c el centro de la función
+
+
+// Función de prueba
+#[derive(Clone, Copy)]
+pub struct FuncionPrueba {
+    pub clave: &'static str,     // Nombre con el que se selecciona
+    pub nombre: &'static str,
+    pub limites: (f64, f64),     // Límites de cada componente
+    pub centro: f64,             // Valor de las componentes del mínimo
+    pub funcion: fn(&[f64]) -> f64,
+}
+
+fn esfera(z: &[f64]) -> f64 {
+    z.iter().map(|zi| zi*zi).sum()
+}
+
+fn rastrigin(z: &[f64]) -> f64 {
+    z.iter().map(|zi| zi*zi - 10.0*(2.0*PI*zi).cos() + 10.0).sum()
+}
+
+fn rosenbrock(z: &[f64]) -> f64 {
+    z.windows(2).map(|p| 100.0*(p[1] - p[0]*p[0]).powi(2) + (1.0 - p[0]).powi(2)).sum()
+}
+
+fn ackley(z: &[f64]) -> f64 {
+    let n = z.len() as f64;
+    let cuadrados = z.iter().map(|zi| zi*zi).sum::<f64>() / n;
+    let cosenos = z.iter().map(|zi| (2.0*PI*zi).cos()).sum::<f64>() / n;
+    -20.0*(-0.2*cuadrados.sqrt()).exp() - cosenos.exp() + 20.0 + std::f64::consts::E
+}
+
+fn griewank(z: &[f64]) -> f64 {
+    let suma = z.iter().map(|zi| zi*zi).sum::<f64>() / 4000.0;
+    let producto = z.iter().enumerate().map(|(i, zi)| (zi / ((i + 1) as f64).sqrt()).cos()).product::<f64>();
+    suma - producto + 1.0
+}
+
+// Versión de la función de Schwefel del CEC 2014: fuera de [-500, 500] la función se refleja y se penaliza,
+//   porque al desplazarla o rotarla podría tomar valores menores que el del mínimo
+fn schwefel(z: &[f64]) -> f64 {
+    let n = z.len() as f64;
+    let termino = |zi: f64| if zi > 500.0 {
+        let r = 500.0 - zi % 500.0;
+        r*r.abs().sqrt().sin() - (zi - 500.0).powi(2)/(10000.0*n)
+    } else if zi < -500.0 {
+        let r = zi.abs() % 500.0 - 500.0;
+        r*r.abs().sqrt().sin() - (zi + 500.0).powi(2)/(10000.0*n)
+    } else {
+        zi*zi.abs().sqrt().sin()
+    };
+    418.982_887_272_433_9*n - z.iter().map(|zi| termino(*zi)).sum::<f64>()
+}
+
+// Todas las funciones de prueba
+pub fn funciones() -> Vec<FuncionPrueba> {
+    let funcion = |clave, nombre, limites, centro, funcion| FuncionPrueba { clave, nombre, limites, centro, funcion };
+    vec![
+        funcion("esfera", "Esfera", (-100.0, 100.0), 0.0, esfera as fn(&[f64]) -> f64),
+        funcion("rastrigin", "Rastrigin", (-5.12, 5.12), 0.0, rastrigin),
+        funcion("rosenbrock", "Rosenbrock", (-30.0, 30.0), 1.0, rosenbrock),
+        funcion("ackley", "Ackley", (-32.768, 32.768), 0.0, ackley),
+        funcion("griewank", "Griewank", (-600.0, 600.0), 0.0, griewank),
+        funcion("schwefel", "Schwefel", (-500.0, 500.0), 420.968_746_359_982, schwefel),
+    ]
+}
+
+// Selecciona las funciones de una lista separada por comas por su clave o su nombre, sin distinguir mayúsculas
+// "todas" las selecciona todas
+pub fn seleccionar_funciones(lista: &str) -> Result<Vec<FuncionPrueba>, String> {
+    let mut seleccion: Vec<FuncionPrueba> = Vec::new();
+    for nombre in lista.split(',').map(|n| n.trim().to_lowercase()).filter(|n| !n.is_empty()) {
+        let encontradas = match nombre.as_str() {
+            "todas" | "all" => funciones(),
+            _ => vec![funciones().into_iter().find(|f| f.clave == nombre || f.nombre.to_lowercase() == nombre)
+                      .ok_or_else(|| format!("función de prueba desconocida: {} (se admiten {})", nombre,
+                                             funciones().iter().map(|f| f.clave).collect::<Vec<_>>().join(", ")))?],
+        };
+        for f in encontradas {
+            if !seleccion.iter().any(|s| s.clave == f.clave) {
+                seleccion.push(f);
+            }
+        }
+    }
+    if seleccion.is_empty() {
+        return Err("no se ha indicado ninguna función de prueba".to_string());
+    }
+    Ok(seleccion)
+}
+
+
+// Variante de una función de prueba
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Variante {
+    Normal,
+    Desplazada,             // El mínimo se mueve a un punto aleatorio del dominio
+    DesplazadaRotada,       // Además, las variables se rotan con una matriz ortogonal aleatoria
+}
+
+impl Variante {
+    pub fn nombre(&self) -> &'static str {
+        match *self {
+            Variante::Normal => "",
+            Variante::Desplazada => " desplazada",
+            Variante::DesplazadaRotada => " desplazada y rotada",
+        }
+    }
+}
+
+impl FromStr for Variante {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Variante, String> {
+        match s.trim().to_lowercase().as_str() {
+            "normal" => Ok(Variante::Normal),
+            "desplazada" | "shifted" => Ok(Variante::Desplazada),
+            "rotada" | "rotated" => Ok(Variante::DesplazadaRotada),
+            _ => Err(format!("variante desconocida: {} (se admiten normal, desplazada y rotada)", s)),
+        }
+    }
+}
+
+
+// Problema de minimizar una función de prueba en una dimensión, como maximización de su opuesto
+// Se evalúa en z = M(x - o) + c, siendo o el punto al que se desplaza el mínimo, M la matriz de rotación y c el centro
+pub struct ProblemaPrueba {
+    pub funcion: FuncionPrueba,
+    pub variante: Variante,
+    dimension: usize,
+    desplazamiento: Vec<f64>,           // Punto o en el que está el mínimo
+    rotacion: Option<Vec<Vec<f64>>>,    // Matriz M, por filas
+}
+
+impl ProblemaPrueba {
+    // Construye el problema. El desplazamiento y la rotación de las variantes se obtienen de la semilla,
+    //   de forma que todos los algoritmos resuelven la misma instancia
+    pub fn nuevo(funcion: FuncionPrueba, dimension: usize, variante: Variante, semilla: u64) -> ProblemaPrueba {
+        let mut rng = evaluacion_pesos::rng_semilla(evaluacion_pesos::derivar_semilla(semilla,
+                          &[evaluacion_pesos::hash_texto(funcion.clave), dimension as u64]));
+        let (inferior, superior) = funcion.limites;
+        // El mínimo desplazado se queda en el 80% central del dominio, para que no coincida con sus límites
+        let margen = 0.1*(superior - inferior);
+        let desplazamiento = match variante {
+            Variante::Normal => vec![funcion.centro; dimension],
+            _ => (0..dimension).map(|_| rng.gen_range(inferior + margen, superior - margen)).collect(),
+        };
+        let rotacion = match variante {
+            Variante::DesplazadaRotada => Some(matriz_ortogonal(dimension, &mut rng)),
+            _ => None,
+        };
+        ProblemaPrueba { funcion, variante, dimension, desplazamiento, rotacion }
+    }
+
+    // Nombre con el que se muestran los resultados, por ejemplo "Rastrigin desplazada (D = 10)"
+    pub fn nombre(&self) -> String {
+        format!("{}{} (D = {})", self.funcion.nombre, self.variante.nombre(), self.dimension)
+    }
+
+    // Valor de la función de prueba en un punto, que es su distancia al mínimo porque este vale 0
+    pub fn error(&self, x: &[f64]) -> f64 {
+        let d: Vec<f64> = x.iter().zip(&self.desplazamiento).map(|(xi, oi)| xi - oi).collect();
+        let z: Vec<f64> = match self.rotacion {
+            Some(ref m) => m.iter().map(|fila| fila.iter().zip(&d).map(|(a, b)| a*b).sum::<f64>() + self.funcion.centro).collect(),
+            None => d.iter().map(|di| di + self.funcion.centro).collect(),
+        };
+        (self.funcion.funcion)(&z)
+    }
+}
+
+impl Problema for ProblemaPrueba {
+    fn dimension(&self) -> usize { self.dimension }
+
+    fn limites(&self, _i: usize) -> (f64, f64) { self.funcion.limites }
+
+    fn funcion_objetivo(&self, x: &[f64]) -> f64 {
+        -self.error(x)
+    }
+}
+
+// Genera una matriz ortogonal aleatoria ortonormalizando con Gram-Schmidt vectores de normales independientes
+fn matriz_ortogonal<Trng: Rng>(n: usize, rng: &mut Trng) -> Vec<Vec<f64>> {
+    let mut normal = Normal::new(0.0, 1.0);
+    let mut filas: Vec<Vec<f64>> = Vec::with_capacity(n);
+    while filas.len() < n {
+        let mut v: Vec<f64> = (0..n).map(|_| normal.sample(rng)).collect();
+        for f in &filas {
+            let proyeccion: f64 = v.iter().zip(f).map(|(a, b)| a*b).sum();
+            for (vi, fi) in v.iter_mut().zip(f) {
+                *vi -= proyeccion*fi;
+            }
+        }
+        let norma = v.iter().map(|vi| vi*vi).sum::<f64>().sqrt();
+        if norma > 1e-8 {   // Si el vector es casi dependiente de los anteriores, se descarta
+            filas.push(v.into_iter().map(|vi| vi/norma).collect());
+        }
+    }
+    filas
+}
+
+
+
+// Algoritmos generales de las prácticas con los operadores de problema.rs, que sirven para cualquier problema
+// Usan los mismos parámetros que en el aprendizaje de pesos (ver parametros.rs), y las desviaciones típicas de
+//   las mutaciones son proporcionales a la amplitud de los límites de cada componente
+// Los meméticos no aparecen porque su búsqueda local usa el operador de vecino del aprendizaje de pesos
+
+
+// Tipo de dato de las funciones que optimizan un problema cualquiera
+pub type Optimizacion = fn(&dyn Problema, &Evaluador, &mut Isaac64Rng) -> Vec<f64>;
+
+// Algoritmo general del banco de pruebas
+#[derive(Clone, Copy)]
+pub struct Optimizador {
+    pub clave: &'static str,     // Nombre con el que se selecciona, el mismo que el del algoritmo del catálogo
+    pub nombre: &'static str,
+    pub funcion: Optimizacion,
+}
+
+fn busqueda_local(problema: &dyn Problema, evaluador: &Evaluador, rng: &mut Isaac64Rng) -> Vec<f64> {
+    let param = parametros();
+    let vecino = |x: &[f64], i: usize, rng: &mut Isaac64Rng| problema::mutacion_normal(problema, x, i, param.sigma_vecino, rng);
+    let inicial = problema::solucion_aleatoria(problema, rng);
+    busqueda_local_generica_desde(problema, &inicial, &vecino, evaluador.presupuesto().max_evaluaciones(), param.max_ciclos_bl, evaluador, rng)
+}
+
+fn es(problema: &dyn Problema, evaluador: &Evaluador, rng: &mut Isaac64Rng) -> Vec<f64> {
+    let sigma = parametros().sigma_vecino;
+    let vecino = |x: &[f64], i: usize, rng: &mut Isaac64Rng| problema::mutacion_normal(problema, x, i, sigma, rng);
+    simulated_annealing_general(problema, &problema::solucion_aleatoria, &vecino, &enfriamiento_cauchy, evaluador, rng)
+}
+
+fn ils(problema: &dyn Problema, evaluador: &Evaluador, rng: &mut Isaac64Rng) -> Vec<f64> {
+    let param = parametros();
+    let vecino = |x: &[f64], i: usize, rng: &mut Isaac64Rng| problema::mutacion_normal(problema, x, i, param.sigma_vecino, rng);
+    let mutacion = |x: &[f64], rng: &mut Isaac64Rng| problema::perturbacion(problema, x, 0.1, param.sigma_ils, rng);
+    let bl = |p: &dyn Problema, x: &[f64], evaluador: &Evaluador, rng: &mut Isaac64Rng| {
+        busqueda_local_generica_desde(p, x, &vecino, param.max_evaluaciones_bl_ils, usize::MAX, evaluador, rng)
+    };
+    iterated_local_search_general(problema, &problema::solucion_aleatoria, &mutacion, &bl, evaluador, rng)
+}
+
+fn de_rand_1(problema: &dyn Problema, evaluador: &Evaluador, rng: &mut Isaac64Rng) -> Vec<f64> {
+    differential_evolution_general(problema, &problema::solucion_aleatoria, &op_rand_1, evaluador, rng)
+}
+
+fn de_ctb_1(problema: &dyn Problema, evaluador: &Evaluador, rng: &mut Isaac64Rng) -> Vec<f64> {
+    differential_evolution_general(problema, &problema::solucion_aleatoria, &op_ctb_1, evaluador, rng)
+}
+
+fn agg_blx(problema: &dyn Problema, evaluador: &Evaluador, rng: &mut Isaac64Rng) -> Vec<f64> {
+    let param = parametros();
+    let cruce = |p1: &[f64], p2: &[f64], rng: &mut Isaac64Rng| problema::cruce_blx(problema, p1, p2, param.alfa_blx, rng);
+    let vecino = |x: &[f64], i: usize, rng: &mut Isaac64Rng| problema::mutacion_normal(problema, x, i, param.sigma_vecino, rng);
+    genetico_generacional(problema, &problema::solucion_aleatoria, &cruce, &vecino, evaluador, rng)
+}
+
+fn age_blx(problema: &dyn Problema, evaluador: &Evaluador, rng: &mut Isaac64Rng) -> Vec<f64> {
+    let param = parametros();
+    let cruce = |p1: &[f64], p2: &[f64], rng: &mut Isaac64Rng| problema::cruce_blx(problema, p1, p2, param.alfa_blx, rng);
+    let vecino = |x: &[f64], i: usize, rng: &mut Isaac64Rng| problema::mutacion_normal(problema, x, i, param.sigma_vecino, rng);
+    genetico_estacionario(problema, &problema::solucion_aleatoria, &cruce, &vecino, evaluador, rng)
+}
+
+fn busqueda_tabu(problema: &dyn Problema, evaluador: &Evaluador, rng: &mut Isaac64Rng) -> Vec<f64> {
+    let sigma = parametros().sigma_vecino;
+    let vecino = |x: &[f64], i: usize, rng: &mut Isaac64Rng| problema::mutacion_normal(problema, x, i, sigma, rng);
+    tabu_search_general(problema, &problema::solucion_aleatoria, &vecino, evaluador, rng)
+}
+
+// Todos los algoritmos del banco de pruebas
+pub fn optimizadores() -> Vec<Optimizador> {
+    let optimizador = |clave, nombre, funcion| Optimizador { clave, nombre, funcion };
+    vec![
+        optimizador("busqueda_local", "Búsqueda local", busqueda_local as Optimizacion),
+        optimizador("es", "Enfriamiento simulado", es),
+        optimizador("ils", "ILS", ils),
+        optimizador("de_rand_1", "DE/rand/1", de_rand_1),
+        optimizador("de_ctb_1", "DE/current-to-best/1", de_ctb_1),
+        optimizador("agg_blx", "AGG_BLX", agg_blx),
+        optimizador("age_blx", "AGE_BLX", age_blx),
+        optimizador("busqueda_tabu", "Búsqueda tabú", busqueda_tabu),
+    ]
+}
+
+// Selecciona los algoritmos de una lista separada por comas por su clave o su nombre, sin distinguir mayúsculas
+// "todos" los selecciona todos
+pub fn seleccionar_optimizadores(lista: &str) -> Result<Vec<Optimizador>, String> {
+    let mut seleccion: Vec<Optimizador> = Vec::new();
+    for nombre in lista.split(',').map(|n| n.trim().to_lowercase()).filter(|n| !n.is_empty()) {
+        let encontrados = match nombre.as_str() {
+            "todos" | "all" => optimizadores(),
+            _ => vec![optimizadores().into_iter().find(|o| o.clave == nombre || o.nombre.to_lowercase() == nombre)
+                      .ok_or_else(|| format!("algoritmo desconocido en el banco de pruebas: {} (se admiten {})", nombre,
+                                             optimizadores().iter().map(|o| o.clave).collect::<Vec<_>>().join(", ")))?],
+        };
+        for o in encontrados {
+            if !seleccion.iter().any(|s| s.clave == o.clave) {
+                seleccion.push(o);
+            }
+        }
+    }
+    if seleccion.is_empty() {
+        return Err("no se ha indicado ningún algoritmo".to_string());
+    }
+    Ok(seleccion)
+}
+
+
+
+// Ejecución de las pruebas
+
+
+// Resultado de una ejecución de un algoritmo sobre una función de prueba
+#[derive(Clone, Copy, Debug)]
+pub struct ResultadoPrueba {
+    pub error: f64,           // Diferencia entre el valor de la mejor solución y el del mínimo
+    pub evaluaciones: usize,
+    pub tiempo_ms: i64,
+}
+
+// Ejecuta un algoritmo sobre una función de prueba con un presupuesto y un RNG de la semilla indicada
+pub fn ejecutar(optimizador: &Optimizador, problema: &ProblemaPrueba, presupuesto: Presupuesto, semilla: u64) -> ResultadoPrueba {
+    let mut rng = evaluacion_pesos::rng_semilla(semilla);
+    let evaluador = Evaluador::nuevo(presupuesto);
+    let inicio = Instant::now();
+    let x = (optimizador.funcion)(problema, &evaluador, &mut rng);
+    let tiempo_ms = inicio.elapsed().as_millis() as i64;
+    ResultadoPrueba { error: problema.error(&x), evaluaciones: evaluador.usadas(), tiempo_ms }
+}
+
+// Ejecuta varias veces cada algoritmo sobre cada función de prueba y muestra los estadísticos del error alcanzado
+// Las semillas de cada ejecución se derivan de la maestra y no dependen del algoritmo, como en la validación
+pub fn ejecutar_pruebas(optimizadores: &[Optimizador], problemas: &[ProblemaPrueba], presupuesto: Presupuesto, ejecuciones: usize, semilla: u64) {
+    for problema in problemas {
+        println!("\n{}", problema.nombre());
+        println!("{:<24} {:>12} {:>12} {:>12} {:>12} {:>12} {:>9}", "Algoritmo", "Error medio", "Desviación", "Mejor", "Peor", "Evaluaciones", "Tiempo");
+        for optimizador in optimizadores {
+            let resultados: Vec<ResultadoPrueba> = (0..ejecuciones).map(|e| {
+                ejecutar(optimizador, problema, presupuesto, evaluacion_pesos::semilla_test(semilla, 0, 0, e))
+            }).collect();
+            let errores: Vec<f64> = resultados.iter().map(|r| r.error).collect();
+            let d = evaluacion_pesos::Dispersion::de(&errores, false);
+            let evaluaciones = resultados.iter().map(|r| r.evaluaciones).sum::<usize>() / ejecuciones;
+            let tiempo_ms = resultados.iter().map(|r| r.tiempo_ms).sum::<i64>() / ejecuciones as i64;
+            println!("{:<24} {:>12.4e} {:>12.4e} {:>12.4e} {:>12.4e} {:>12} {:>6} ms", optimizador.nombre, d.media, d.desviacion, d.mejor, d.peor, evaluaciones, tiempo_ms);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VARIANTES: [Variante; 3] = [Variante::Normal, Variante::Desplazada, Variante::DesplazadaRotada];
+
+    #[test]
+    fn error_nulo_en_el_optimo() {
+        let mut rng = evaluacion_pesos::rng_semilla(3);
+        for f in funciones() {
+            assert!((f.centro >= f.limites.0) && (f.centro <= f.limites.1), "el centro de {} está fuera de sus límites", f.clave);
+            for dimension in &[2, 10, 30] {
+                for variante in &VARIANTES {
+                    let problema = ProblemaPrueba::nuevo(f, *dimension, *variante, 11);
+                    let optimo = problema.desplazamiento.clone();
+                    assert!(optimo.iter().all(|x| *x >= f.limites.0 && *x <= f.limites.1));
+                    // En Schwefel, esto comprueba también que la constante de la función (418.98...) es el valor
+                    //   del término de cada componente en su centro (420.97...)
+                    let error = problema.error(&optimo);
+                    assert!(error.abs() < 1e-6, "{}: el error en el óptimo es {}", problema.nombre(), error);
+                    for _ in 0..20 {
+                        let x: Vec<f64> = (0..*dimension).map(|_| rng.gen_range(f.limites.0, f.limites.1)).collect();
+                        assert!(problema.error(&x) > error, "{} tiene un punto mejor que el óptimo", problema.nombre());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn variantes_de_una_misma_instancia() {
+        let rastrigin = seleccionar_funciones("rastrigin").unwrap()[0];
+        let normal = ProblemaPrueba::nuevo(rastrigin, 5, Variante::Normal, 1);
+        assert_eq!(normal.desplazamiento, vec![0.0; 5]);
+        let desplazada = ProblemaPrueba::nuevo(rastrigin, 5, Variante::Desplazada, 1);
+        assert_eq!(desplazada.desplazamiento, ProblemaPrueba::nuevo(rastrigin, 5, Variante::Desplazada, 1).desplazamiento);
+        assert_ne!(desplazada.desplazamiento, ProblemaPrueba::nuevo(rastrigin, 5, Variante::Desplazada, 2).desplazamiento);
+        assert_eq!(ProblemaPrueba::nuevo(rastrigin, 5, Variante::DesplazadaRotada, 1).nombre(), "Rastrigin desplazada y rotada (D = 5)");
+    }
+
+    #[test]
+    fn rotaciones_ortogonales() {
+        let mut rng = evaluacion_pesos::rng_semilla(5);
+        for n in &[1, 2, 7] {
+            let m = matriz_ortogonal(*n, &mut rng);
+            for i in 0..*n {
+                for j in 0..*n {
+                    let producto: f64 = m[i].iter().zip(&m[j]).map(|(a, b)| a*b).sum();
+                    assert!((producto - if i == j { 1.0 } else { 0.0 }).abs() < 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn seleccion_de_funciones_y_variantes() {
+        let claves = |s: &str| seleccionar_funciones(s).map(|v| v.iter().map(|f| f.clave).collect::<Vec<_>>());
+        assert_eq!(claves(" Ackley ,esfera,ackley"), Ok(vec!["ackley", "esfera"]));
+        assert_eq!(claves("todas").unwrap().len(), funciones().len());
+        assert!(claves("esfera,michalewicz").is_err());
+        assert!(claves("").is_err());
+        assert_eq!("Rotated".parse(), Ok(Variante::DesplazadaRotada));
+        assert_eq!("desplazada".parse(), Ok(Variante::Desplazada));
+        assert!("girada".parse::<Variante>().is_err());
+    }
+}
@@ -0,0 +1,236 @@
+// Caché de evaluaciones de la función objetivo del aprendizaje de pesos
+// Los algoritmos de población vuelven a evaluar a menudo pesos idénticos (el cruce aritmético de dos padres
+//   iguales, los vectores de prueba de DE en los que no cruza ningún gen...). Con la caché activa, la puntuación
+//   de unos pesos ya evaluados se recupera sin calcularla de nuevo y sin gastar presupuesto
+// Como los aciertos no cuentan como evaluaciones, los algoritmos que la usan no se comparan en igualdad
+//   de condiciones con los que no, así que está desactivada por defecto
+// En modo umbral los pesos menores que 0.2 se tratan como 0 antes de buscarlos: la distancia no los tiene en
+//   cuenta y la tasa de reducción solo depende de cuántos hay, así que la puntuación es la misma
+// Las puntuaciones solo valen para los datos con los que se han calculado, así que se guardan junto a un hash de
+//   esos datos y unos pesos solo se recuperan al evaluarlos sobre los mismos. La caché se vacía al empezar cada test
+
+use parametros::parametros;
+use presupuesto::Evaluador;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::f64;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+
+// Modo de funcionamiento de la caché
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ModoCache {
+    Desactivada,
+    Exacta,    // Solo se recuperan los pesos exactamente iguales
+    Umbral,    // Se recuperan también los pesos que solo difieren en los menores que 0.2
+}
+
+impl FromStr for ModoCache {
+    type Err = String;
+
+    fn from_str(texto: &str) -> Result<ModoCache, String> {
+        match texto.trim() {
+            "no" => Ok(ModoCache::Desactivada),
+            "exacta" => Ok(ModoCache::Exacta),
+            "umbral" => Ok(ModoCache::Umbral),
+            _ => Err(format!("Modo de caché no válido: {} (debe ser no, exacta o umbral)", texto)),
+        }
+    }
+}
+
+// Puntuaciones guardadas en un hilo, de los pesos evaluados desde que se vació, por el hash de sus datos y sus pesos
+struct Cache {
+    valores: HashMap<(u64, Vec<u64>), f64>,
+    aciertos: usize,
+    consultas: usize,
+}
+
+thread_local! {
+    static MODO: Cell<ModoCache> = const { Cell::new(ModoCache::Desactivada) };
+    static CACHE: RefCell<Cache> = RefCell::new(Cache { valores: HashMap::new(), aciertos: 0, consultas: 0 });
+}
+
+// Obtiene el modo de la caché en el hilo actual
+pub fn modo_cache() -> ModoCache {
+    MODO.with(|m| m.get())
+}
+
+// Cambia el modo de la caché en el hilo actual
+pub fn fijar_modo_cache(modo: ModoCache) {
+    MODO.with(|m| m.set(modo));
+}
+
+// Vacía la caché del hilo actual y pone a cero sus estadísticas
+// Se llama antes de ejecutar cada algoritmo (ver evaluacion_pesos::evaluar_test)
+pub fn reiniciar() {
+    CACHE.with(|c| {
+        let mut c = c.borrow_mut();
+        c.valores.clear();
+        c.aciertos = 0;
+        c.consultas = 0;
+    });
+}
+
+// Número de evaluaciones resueltas por la caché desde el último reinicio
+pub fn aciertos() -> usize {
+    CACHE.with(|c| c.borrow().aciertos)
+}
+
+// Número de evaluaciones consultadas en la caché desde el último reinicio, resueltas o no
+pub fn consultas() -> usize {
+    CACHE.with(|c| c.borrow().consultas)
+}
+
+// Clave con la que se guardan unos pesos evaluados sobre unos datos
+fn clave<D: Hash + ?Sized>(datos: &D, w: &[f64], modo: ModoCache) -> (u64, Vec<u64>) {
+    let mut hash = DefaultHasher::new();
+    datos.hash(&mut hash);
+    let pesos = match modo {
+        ModoCache::Umbral => w.iter().map(|p| if *p < 0.2 { 0 } else { p.to_bits() }).collect(),
+        _ => w.iter().map(|p| p.to_bits()).collect(),
+    };
+    (hash.finish(), pesos)
+}
+
+// Puntúa unos pesos sobre unos datos con la función indicada, salvo que ya se hayan puntuado sobre los mismos
+//   datos desde que se vació la caché
+// Las evaluaciones que hace la función se cuentan en el presupuesto del evaluador, y las que se resuelven en la caché no
+// Tras el máximo de aciertos (el parámetro aciertos_cache por cada evaluación del presupuesto) ya no se resuelve
+//   ninguna, de forma que termina un algoritmo cuya población ha convergido y solo genera pesos ya evaluados
+pub fn evaluar<D: Hash + ?Sized, F: FnOnce() -> f64>(evaluador: &Evaluador, datos: &D, w: &[f64], funcion: F) -> f64 {
+    let modo = modo_cache();
+    if modo == ModoCache::Desactivada {
+        return funcion();
+    }
+
+    let clave = clave(datos, w, modo);
+    let max_aciertos = evaluador.presupuesto().max_evaluaciones().saturating_mul(parametros().aciertos_cache);
+    let guardado = CACHE.with(|c| {
+        let mut c = c.borrow_mut();
+        c.consultas += 1;
+        let guardado = if c.aciertos < max_aciertos { c.valores.get(&clave).cloned() } else { None };
+        if guardado.is_some() {
+            c.aciertos += 1;
+        }
+        guardado
+    });
+
+    guardado.unwrap_or_else(|| {
+        let valor = funcion();
+        // Las evaluaciones con el presupuesto agotado no son puntuaciones reales, así que no se guardan
+        if valor != f64::NEG_INFINITY {
+            CACHE.with(|c| c.borrow_mut().valores.insert(clave, valor));
+        }
+        valor
+    })
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parametros::{fijar_parametros, Parametros};
+    use evaluacion_pesos;
+    use knn::Dato;
+    use presupuesto::pruebas::evaluador;
+    use problema::{PonderacionCaracteristicas, Problema};
+
+    // Evalúa unos pesos con una puntuación que depende solo del primero, con cargo al presupuesto del evaluador
+    fn puntuar(evaluador: &Evaluador, w: &[f64]) -> f64 {
+        evaluar(evaluador, "datos", w, || evaluador.evaluar(|| w[0]))
+    }
+
+    #[test]
+    fn los_aciertos_no_gastan_presupuesto() {
+        fijar_modo_cache(ModoCache::Exacta);
+        reiniciar();
+        let e = evaluador(10);
+        assert_eq!(puntuar(&e, &[0.5, 0.1]), 0.5);
+        assert_eq!(puntuar(&e, &[0.5, 0.1]), 0.5);
+        assert_eq!(puntuar(&e, &[0.5, 0.15]), 0.5);
+        assert_eq!((e.usadas(), aciertos(), consultas()), (2, 1, 3));
+    }
+
+    #[test]
+    fn en_modo_umbral_se_ignoran_los_pesos_pequenos() {
+        fijar_modo_cache(ModoCache::Umbral);
+        reiniciar();
+        let e = evaluador(10);
+        puntuar(&e, &[0.5, 0.1]);
+        puntuar(&e, &[0.5, 0.15]);
+        puntuar(&e, &[0.5, 0.25]);
+        assert_eq!((e.usadas(), aciertos()), (2, 1));
+    }
+
+    #[test]
+    fn desactivada_evalua_siempre() {
+        fijar_modo_cache(ModoCache::Desactivada);
+        reiniciar();
+        let e = evaluador(10);
+        puntuar(&e, &[0.5]);
+        puntuar(&e, &[0.5]);
+        assert_eq!((e.usadas(), aciertos(), consultas()), (2, 0, 0));
+    }
+
+    #[test]
+    fn al_reiniciarse_se_vacia() {
+        fijar_modo_cache(ModoCache::Exacta);
+        reiniciar();
+        let e = evaluador(10);
+        puntuar(&e, &[0.5]);
+        puntuar(&e, &[0.5]);
+        reiniciar();
+        assert_eq!((aciertos(), consultas()), (0, 0));
+        assert_eq!(evaluar(&e, "datos", &[0.5], || e.evaluar(|| 0.7)), 0.7);
+        assert_eq!((e.usadas(), aciertos(), consultas()), (2, 0, 1));
+    }
+
+    #[test]
+    fn no_comparte_puntuaciones_entre_datos_distintos() {
+        fijar_modo_cache(ModoCache::Exacta);
+        reiniciar();
+        let datos_1: Vec<Dato> = (0..6).map(|i| Dato::new(&[i as f64 / 5.0], &[], i % 2)).collect();
+        let datos_2: Vec<Dato> = (0..6).map(|i| Dato::new(&[i as f64 / 5.0], &[], i / 3)).collect();
+        let e = evaluador(10);
+        let w = [1.0];
+        let p1 = PonderacionCaracteristicas::nueva(&datos_1).evaluar(&e, &w);
+        let p2 = PonderacionCaracteristicas::nueva(&datos_2).evaluar(&e, &w);
+        assert_eq!((p1, p2), (evaluacion_pesos::puntuar(&datos_1, &w), evaluacion_pesos::puntuar(&datos_2, &w)));
+        assert!(p1 != p2);
+        assert_eq!((e.usadas(), aciertos()), (2, 0));
+        // Otro problema con los mismos datos sí recupera su puntuación
+        assert_eq!(PonderacionCaracteristicas::nueva(&datos_1.clone()).evaluar(&e, &w), p1);
+        assert_eq!((e.usadas(), aciertos()), (2, 1));
+    }
+
+    #[test]
+    fn deja_de_responder_tras_el_maximo_de_aciertos() {
+        fijar_modo_cache(ModoCache::Exacta);
+        fijar_parametros(Parametros { aciertos_cache: 2, ..Parametros::default() });
+        reiniciar();
+        let e = evaluador(2);
+        puntuar(&e, &[0.5]);
+        // Con 2 evaluaciones de presupuesto caben 4 aciertos, y después la evaluación se cobra y lo agota
+        for _ in 0..4 {
+            assert_eq!(puntuar(&e, &[0.5]), 0.5);
+        }
+        assert_eq!((e.usadas(), aciertos()), (1, 4));
+        assert_eq!(puntuar(&e, &[0.5]), 0.5);
+        assert_eq!(puntuar(&e, &[0.5]), f64::NEG_INFINITY);
+        assert!(e.agotado());
+    }
+
+    #[test]
+    fn no_guarda_evaluaciones_sin_presupuesto() {
+        fijar_modo_cache(ModoCache::Exacta);
+        reiniciar();
+        let e = evaluador(1);
+        puntuar(&e, &[0.1]);
+        assert_eq!(puntuar(&e, &[0.5]), f64::NEG_INFINITY);
+        assert_eq!(consultas(), 2);
+        assert!(CACHE.with(|c| !c.borrow().valores.contains_key(&clave("datos", &[0.5], ModoCache::Exacta))));
+    }
+}
@@ -0,0 +1,182 @@
+// Catálogo de todos los algoritmos de aprendizaje de pesos implementados en las prácticas
+// Permite elegir por nombre los algoritmos que se ejecutan sin tener que recompilar
+
+use aprendiz::{Algoritmo, Funcion, Mejora};
+use funciones_practica1::*;
+use funciones_practica2::*;
+use funciones_practica3::*;
+use funciones_tabu::*;
+
+
+// Algoritmo del catálogo
+#[derive(Clone, Copy)]
+pub struct Entrada {
+    pub clave: &'static str,     // Nombre con el que se selecciona (el de la función que lo implementa)
+    pub nombre: &'static str,    // Nombre con el que se muestran sus resultados
+    pub practica: usize,         // Práctica en la que se implementó
+    pub funcion: Algoritmo,
+}
+
+impl Entrada {
+    // Aprendiz que ejecuta el algoritmo, con su clave y su nombre
+    pub fn aprendiz(&self) -> Funcion {
+        Funcion::nueva(self.clave, self.nombre, self.funcion)
+    }
+}
+
+// Todos los algoritmos, en el orden en que se implementaron
+pub fn algoritmos() -> Vec<Entrada> {
+    let entrada = |clave, nombre, practica, funcion| Entrada { clave, nombre, practica, funcion };
+    vec![
+        entrada("uno_nn", "1NN", 1, uno_nn as Algoritmo),
+        entrada("relief", "RELIEF", 1, relief),
+        entrada("busqueda_local", "Búsqueda local", 1, busqueda_local),
+        entrada("relief_truncado", "RELIEF + truncado", 1, relief_truncado),
+        entrada("relief_potencia", "RELIEF + potencia", 1, relief_potencia),
+        entrada("relief_afinidad", "RELIEF + afinidad", 1, relief_afinidad),
+        entrada("busqueda_local_mut2", "BL con otra mutación", 1, busqueda_local_mut2),
+        entrada("busqueda_local_orden", "BL con orden", 1, busqueda_local_orden),
+        entrada("busqueda_local_orden_mut2", "BL con orden y otra mutación", 1, busqueda_local_orden_mut2),
+        entrada("agg_blx", "AGG_BLX", 2, agg_blx),
+        entrada("agg_ca", "AGG_CA", 2, agg_ca),
+        entrada("age_blx", "AGE_BLX", 2, age_blx),
+        entrada("age_ca", "AGE_CA", 2, age_ca),
+        entrada("am_a", "AM-(10,1.0)", 2, am_a),
+        entrada("am_b", "AM-(10,0.1)", 2, am_b),
+        entrada("am_c", "AM-(10,0.1mej)", 2, am_c),
+        entrada("agg_blx_mut2", "AGG_BLX_MUT2", 2, agg_blx_mut2),
+        entrada("age_blx_mut2", "AGE_BLX_MUT2", 2, age_blx_mut2),
+        entrada("age_ca_alt", "AGE_CA_ALT", 2, age_ca_alt),
+        entrada("am_afinidad_01", "AM-(10,0.1,af)", 2, am_afinidad_01),
+        entrada("es", "Enfriamiento simulado", 3, es),
+        entrada("ils", "ILS", 3, ils),
+        entrada("de_rand_1", "DE/rand/1", 3, de_rand_1),
+        entrada("de_ctb_1", "DE/current-to-best/1", 3, de_ctb_1),
+        entrada("es_mut2", "ES_mut2", 3, es_mut2),
+        entrada("es_prop", "ES_prop", 3, es_prop),
+        entrada("es_prop_mut2", "ES_prop_mut2", 3, es_prop_mut2),
+        entrada("ils_mut2", "ILS_mut2", 3, ils_mut2),
+        entrada("ils_afinidad", "ILS_afinidad", 3, ils_afinidad),
+        entrada("ils_afinidad_relief", "ILS_afinidad_relief", 3, ils_afinidad_relief),
+        entrada("busqueda_tabu", "Búsqueda tabú", 3, busqueda_tabu),
+        entrada("busqueda_tabu_mut2", "BT con otra mutación", 3, busqueda_tabu_mut2),
+    ]
+}
+
+// Busca un algoritmo por su clave o por el nombre con el que se muestra, sin distinguir mayúsculas
+pub fn buscar(nombre: &str) -> Option<Entrada> {
+    let nombre = nombre.trim().to_lowercase();
+    algoritmos().into_iter().find(|a| a.clave == nombre || a.nombre.to_lowercase() == nombre)
+}
+
+// Selecciona los algoritmos de una lista separada por comas
+// También se admiten "todos" y "p1", "p2" o "p3" para elegir todos los de una práctica
+pub fn seleccionar(lista: &str) -> Result<Vec<Entrada>, String> {
+    let mut seleccion: Vec<Entrada> = Vec::new();
+    for nombre in lista.split(',').map(|n| n.trim()).filter(|n| !n.is_empty()) {
+        let encontrados = match nombre.to_lowercase().as_str() {
+            "todos" | "all" => algoritmos(),
+            "p1" | "p2" | "p3" => {
+                let practica = nombre[1..].parse::<usize>().unwrap();
+                algoritmos().into_iter().filter(|a| a.practica == practica).collect()
+            },
+            _ => vec![buscar(nombre).ok_or_else(|| format!("algoritmo desconocido: {} (list-algorithms muestra los disponibles)", nombre))?],
+        };
+        for a in encontrados {
+            if !seleccion.iter().any(|s| s.clave == a.clave) {
+                seleccion.push(a);
+            }
+        }
+    }
+    if seleccion.is_empty() {
+        return Err("no se ha indicado ningún algoritmo".to_string());
+    }
+    Ok(seleccion)
+}
+
+
+// Procedimiento del catálogo que mejora una solución, con el que se forman tuberías de aprendices
+#[derive(Clone, Copy)]
+pub struct EntradaMejora {
+    pub clave: &'static str,     // Nombre con el que se selecciona (el de la función que lo implementa)
+    pub nombre: &'static str,
+    pub funcion: Mejora,
+}
+
+// Todos los procedimientos de mejora
+pub fn mejoras() -> Vec<EntradaMejora> {
+    let entrada = |clave, nombre, funcion| EntradaMejora { clave, nombre, funcion };
+    vec![
+        entrada("busqueda_local_desde", "Búsqueda local", busqueda_local_desde as Mejora),
+        entrada("busqueda_local_mut2_desde", "BL con otra mutación", busqueda_local_mut2_desde),
+        entrada("busqueda_local_orden_desde", "BL con orden", busqueda_local_orden_desde),
+        entrada("busqueda_local_orden_mut2_desde", "BL con orden y otra mutación", busqueda_local_orden_mut2_desde),
+        entrada("truncado_optimo", "Truncado", truncado_optimo),
+        entrada("potencia_optima", "Potencia", potencia_optima),
+        entrada("afinidad_optima", "Afinidad", afinidad_optima),
+    ]
+}
+
+// Busca un procedimiento de mejora por su clave, sin distinguir mayúsculas
+pub fn buscar_mejora(clave: &str) -> Option<EntradaMejora> {
+    let clave = clave.trim().to_lowercase();
+    mejoras().into_iter().find(|m| m.clave == clave)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aprendiz::Aprendiz;
+
+    fn claves(entradas: &[Entrada]) -> Vec<&'static str> {
+        entradas.iter().map(|e| e.clave).collect()
+    }
+
+    #[test]
+    fn claves_y_nombres_unicos() {
+        let todos = algoritmos();
+        for (i, a) in todos.iter().enumerate() {
+            assert!(todos[i+1..].iter().all(|b| b.clave != a.clave && b.nombre.to_lowercase() != a.nombre.to_lowercase()),
+                    "{} está repetido", a.clave);
+            assert_eq!(a.clave, a.clave.to_lowercase());
+            assert!((1..4).contains(&a.practica));
+        }
+        let mejoras = mejoras();
+        for (i, m) in mejoras.iter().enumerate() {
+            assert!(mejoras[i+1..].iter().all(|n| n.clave != m.clave), "{} está repetido", m.clave);
+        }
+    }
+
+    #[test]
+    fn busqueda_por_clave_o_nombre() {
+        assert_eq!(buscar("relief").map(|a| a.nombre), Some("RELIEF"));
+        assert_eq!(buscar(" DE/Current-to-Best/1 ").map(|a| a.clave), Some("de_ctb_1"));
+        assert_eq!(buscar("Búsqueda tabú").map(|a| a.clave), Some("busqueda_tabu"));
+        assert!(buscar("relif").is_none());
+        assert_eq!(buscar_mejora("TRUNCADO_OPTIMO").map(|m| m.nombre), Some("Truncado"));
+        assert!(buscar_mejora("Truncado").is_none());
+    }
+
+    #[test]
+    fn seleccion_de_listas() {
+        assert_eq!(claves(&seleccionar("relief, ils,de_ctb_1").unwrap()), vec!["relief", "ils", "de_ctb_1"]);
+        // Los repetidos se seleccionan una sola vez, en el orden en que aparecen por primera vez
+        assert_eq!(claves(&seleccionar("ils,relief,ILS,,").unwrap()), vec!["ils", "relief"]);
+        let p2 = seleccionar("p2").unwrap();
+        assert!(!p2.is_empty() && p2.iter().all(|a| a.practica == 2));
+        assert_eq!(claves(&seleccionar("es,p3").unwrap())[0], "es");
+        assert_eq!(seleccionar("p3,p1,p2").unwrap().len(), algoritmos().len());
+        assert_eq!(seleccionar("todos").unwrap().len(), algoritmos().len());
+        assert!(seleccionar("relief,desconocido").err().is_some_and(|e| e.contains("desconocido")));
+        assert!(seleccionar(" , ").is_err());
+        assert!(seleccionar("p4").is_err());
+    }
+
+    #[test]
+    fn aprendiz_de_una_entrada() {
+        let a = buscar("agg_blx").unwrap().aprendiz();
+        assert_eq!(a.nombre(), "AGG_BLX");
+        assert_eq!(a.a_json().campo("algoritmo").and_then(|c| c.como_texto()), Some("agg_blx"));
+    }
+}
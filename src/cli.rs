@@ -0,0 +1,485 @@
+// Argumentos y procedimientos comunes a los programas
+// Reúne lo que antes repetía cada práctica: la lectura de la semilla, la medida de clasificación
+//   y el esquema de validación, y la prueba de una lista de algoritmos con uno o varios conjuntos de datos
+
+use aprendiz::Aprendiz;
+use cache;
+use clap::{Arg, ArgMatches};
+use evaluacion_pesos;
+use evaluacion_pesos::{ResultadosCV, Validacion};
+use json;
+use knn;
+use parametros;
+use planificador;
+use planificador::{Datos, Planificacion};
+use presupuesto::Presupuesto;
+use punto_control;
+use punto_control::PuntoControl;
+use resultados::Salida;
+use std;
+use std::fs::File;
+use std::io::Read;
+use std::time::Duration;
+use traza::ArchivoTraza;
+
+
+// Archivos de datos ofrecidos en las prácticas, que se usan si no se indica ninguno
+pub const INSTANCIAS: [&str; 3] = ["instances/ozone-320.arff", "instances/parkinsons.arff", "instances/spectf-heart.arff"];
+
+// Semilla por defecto, en caso de que el usuario decida no introducir ninguna
+pub const SEMILLA_POR_DEFECTO: &str = "Es el usuario el que elige a la semilla y es la semilla la que quiere que sean los usuarios la semilla.";
+
+
+// Argumento de la semilla, común a todos los programas que ejecutan algoritmos
+pub fn argumento_semilla<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("semilla").short("s").long("seed").takes_value(true)
+        .help("Semilla maestra para el PRNG: un entero de 64 bits (en decimal o en hexadecimal con 0x) o un texto del que obtenerlo. De ella se derivan las semillas de cada repetición y cada test, que se guardan con los resultados")
+}
+
+// Argumento para repetir solo uno de los tests de la validación
+pub fn argumento_repetir<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("repetir").long("repetir").takes_value(true)
+        .help("Ejecuta solo uno de los tests de la validación, con la misma partición y las mismas semillas derivadas que en la validación completa: \"p\" para la partición p de la primera repetición, \"r.p\" para la partición p de la repetición r o \"r.p.e\" para solo la ejecución e de ese test, empezando a contar en 1")
+}
+
+// Argumento del número de ejecuciones independientes de cada algoritmo en cada test
+pub fn argumento_ejecuciones<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("ejecuciones").short("n").long("ejecuciones").takes_value(true)
+        .help("Número de ejecuciones independientes de cada algoritmo en cada test, con las mismas particiones y distintas semillas. Con más de una se muestran la media, la desviación típica, el mejor, el peor y la mediana de las ejecuciones. Por defecto, 1")
+}
+
+// Argumento de la medida de clasificación de la función objetivo
+pub fn argumento_medida<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("medida").long("medida").takes_value(true)
+        .help("Medida con la que se puntúa la clasificación en la función objetivo: acierto (por defecto), balanceado (acierto balanceado) o gmedia (media geométrica de las sensibilidades)")
+}
+
+// Argumento del esquema de validación
+pub fn argumento_validacion<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("validacion").short("v").long("validacion").takes_value(true)
+        .help("Esquema de validación: k-fold cross validation repetida r veces (\"k\" o \"kxr\", por ejemplo \"5x2\"), hold-out con proporción de test p (\"holdout:p\" o \"holdout:pxr\") o leave-one-out (\"loo\"). Por defecto, \"5\"")
+}
+
+// Argumento para mostrar las medidas de clasificación detalladas
+pub fn argumento_metricas<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("metricas").short("m").long("metricas")
+        .help("Muestra también la matriz de confusión y las medidas de clasificación detalladas de cada test")
+}
+
+// Argumento del número de hilos con los que se ejecutan los tests
+pub fn argumento_hilos<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("hilos").short("j").long("jobs").takes_value(true)
+        .help("Número de hilos entre los que se reparten los tests de todos los algoritmos y conjuntos de datos (0 para usar todos los núcleos). Los resultados son los mismos con cualquier número de hilos, aunque los tiempos pueden aumentar si se usan más que núcleos. Por defecto, 1")
+}
+
+// Argumento del archivo en el que se escriben los resultados
+pub fn argumento_salida<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("salida").short("o").long("output").takes_value(true)
+        .help("Archivo en el que escribir los resultados de cada test, incluidos los pesos aprendidos y las evaluaciones hechas. Se escribe en CSV si su extensión es .csv y en JSON Lines en otro caso")
+}
+
+// Argumentos del punto de control con el que se reanudan los lotes interrumpidos
+pub fn argumentos_control<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("control").long("checkpoint").takes_value(true)
+            .help("Archivo JSON Lines en el que se guarda cada test según termina. Si ya existe, los tests que contiene no se vuelven a ejecutar, de forma que un lote interrumpido se reanuda repitiendo la misma orden. No se reanuda un lote con otra validación, medida, parámetros, presupuesto o caché"),
+        Arg::with_name("control_poblacion").long("checkpoint-poblacion").requires("control")
+            .help("Los algoritmos genéticos guardan también su población junto al punto de control cada cierto número de generaciones (el parámetro generaciones_control), y un test interrumpido continúa desde ella. Al no guardarse el estado del RNG, el test reanudado no es idéntico al que no se interrumpe"),
+    ]
+}
+
+// Argumento del archivo en el que se escriben las trazas de convergencia
+pub fn argumento_traza<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("traza").long("trace").takes_value(true)
+        .help("Archivo CSV en el que escribir la traza de convergencia de cada test: las evaluaciones hechas, la puntuación de la solución actual (o la media de la población) y la de la mejor, y la temperatura o la diversidad de la población (distancia media entre cromosomas, entropía media de los genes y varianza de las puntuaciones) si las hay")
+}
+
+// Argumento del archivo de experimento del que se leen los parámetros de los algoritmos
+pub fn argumento_config<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("config").short("c").long("config").takes_value(true)
+        .help("Archivo de experimento JSON de cuya sección \"parametros\" se leen los parámetros de los algoritmos (tamaños de población, tasas, máximo de evaluaciones...). Los que no aparecen toman su valor por defecto")
+}
+
+// Argumentos del tiempo límite de cada ejecución de un algoritmo
+pub fn argumentos_limite_tiempo<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("limite_tiempo").long("time-limit").takes_value(true)
+            .help("Tiempo máximo de cada ejecución de un algoritmo (por ejemplo, 30s, 500ms, 2m o 1h; sin unidad, en segundos), además del máximo de evaluaciones. Al agotarse, el algoritmo termina con la mejor solución que haya encontrado, y se muestran las evaluaciones que ha hecho"),
+        Arg::with_name("solo_tiempo").long("time-only").requires("limite_tiempo")
+            .help("Ignora el máximo de evaluaciones, de forma que los algoritmos solo se detienen por el tiempo límite (o por sus propios criterios de parada)"),
+    ]
+}
+
+// Argumento de la caché de evaluaciones
+pub fn argumento_cache<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("cache").long("cache").takes_value(true)
+        .help("Caché de evaluaciones: no (por defecto), exacta (los pesos ya evaluados no se vuelven a evaluar) o umbral (tampoco los que solo difieren en pesos menores que 0.2). Las evaluaciones que resuelve no cuentan en el presupuesto, así que los resultados no son comparables con los obtenidos sin ella. Deja de responder tras un número de aciertos igual al máximo de evaluaciones por el parámetro aciertos_cache (10 por defecto), para que terminen los algoritmos que solo generan pesos ya evaluados")
+}
+
+// Argumentos de los programas que validan algoritmos: los anteriores, las métricas detalladas y las particiones predefinidas
+pub fn argumentos_validacion<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        argumento_semilla(),
+        argumento_repetir(),
+        argumento_ejecuciones(),
+        argumento_hilos(),
+        argumento_validacion(),
+        argumento_medida(),
+        argumento_metricas(),
+        argumento_salida(),
+        argumento_traza(),
+        argumento_config(),
+        argumento_cache(),
+    ].into_iter().chain(argumentos_limite_tiempo()).chain(argumentos_control()).chain(vec![
+        Arg::with_name("entrenamiento").short("e").long("entrenamiento").takes_value(true).requires("test")
+            .help("Archivo de entrenamiento de una partición predefinida. Se evalúa con el archivo de test indicado en lugar de hacer validación"),
+        Arg::with_name("test").short("t").long("test").takes_value(true).requires("entrenamiento")
+            .help("Archivo de test de una partición predefinida"),
+        Arg::with_name("particiones").short("p").long("particiones").takes_value(true).conflicts_with("entrenamiento")
+            .help("Directorio con archivos de entrenamiento y test de particiones predefinidas, con nombres terminados en tra y tst como en KEEL (por ejemplo, iris-5-1tra.dat e iris-5-1tst.dat)"),
+    ]).collect()
+}
+
+
+// Termina el programa tras mostrar un mensaje de error
+pub fn salir(mensaje: &str) -> ! {
+    println!("{}", mensaje);
+    std::process::exit(1)
+}
+
+// Leemos la semilla maestra, que por defecto se obtiene del texto de SEMILLA_POR_DEFECTO
+pub fn leer_semilla(matches: &ArgMatches) -> u64 {
+    interpretar_semilla(matches.value_of("semilla").unwrap_or(SEMILLA_POR_DEFECTO))
+}
+
+// Interpreta una semilla: un entero en decimal o en hexadecimal (con 0x) se usa tal cual, y cualquier
+//   otro texto se transforma en un entero con el hash FNV-1a de sus bytes
+pub fn interpretar_semilla(texto: &str) -> u64 {
+    let texto = texto.trim();
+    let numero = match texto.get(..2) {
+        Some("0x") | Some("0X") => u64::from_str_radix(&texto[2..], 16).ok(),
+        _ => texto.parse().ok(),
+    };
+    numero.unwrap_or_else(|| evaluacion_pesos::hash_texto(texto))
+}
+
+// Leemos el test que se quiere repetir, como (repetición, partición, ejecución) contando desde 0
+// Si no se indica la ejecución, se repiten todas
+pub fn leer_repetir(matches: &ArgMatches) -> Option<(usize, usize, Option<usize>)> {
+    matches.value_of("repetir").map(|texto| {
+        let numeros: Option<Vec<usize>> = texto.split('.').map(|x| x.trim().parse().ok().filter(|n| *n > 0)).collect();
+        match numeros.as_ref().map(|n| &n[..]) {
+            Some([p]) => (0, p - 1, None),
+            Some([r, p]) => (r - 1, p - 1, None),
+            Some([r, p, e]) => (r - 1, p - 1, Some(e - 1)),
+            _ => salir(&format!("Test no válido: {}. Debe ser \"p\", \"r.p\" o \"r.p.e\", empezando a contar en 1", texto)),
+        }
+    })
+}
+
+// Leemos el número de ejecuciones de cada algoritmo en cada test, que por defecto es 1
+pub fn leer_ejecuciones(matches: &ArgMatches) -> usize {
+    match matches.value_of("ejecuciones") {
+        Some(n) => n.parse().ok().filter(|n| *n > 0)
+                    .unwrap_or_else(|| salir(&format!("Número de ejecuciones no válido: {}", n))),
+        None => 1,
+    }
+}
+
+// Leemos el número de hilos con los que se ejecutan los tests, que por defecto es 1
+pub fn leer_hilos(matches: &ArgMatches) -> usize {
+    match matches.value_of("hilos") {
+        Some(n) => n.parse().unwrap_or_else(|_| salir(&format!("Número de hilos no válido: {}", n))),
+        None => 1,
+    }
+}
+
+// Leemos la medida de clasificación de la función objetivo, que por defecto es la tasa de aciertos
+pub fn leer_medida(matches: &ArgMatches) {
+    if let Some(m) = matches.value_of("medida") {
+        match m.parse() {
+            Ok(medida) => evaluacion_pesos::fijar_medida_clasificacion(medida),
+            Err(e) => salir(&e),
+        }
+    }
+}
+
+// Leemos el esquema de validación, que por defecto es 5-fold cross validation
+pub fn leer_validacion(matches: &ArgMatches) -> Validacion {
+    match matches.value_of("validacion") {
+        Some(v) => v.parse().unwrap_or_else(|e| salir(&format!("Esquema de validación no válido: {}", e))),
+        None => Validacion::default(),
+    }
+}
+
+// Leemos los parámetros de los algoritmos del archivo de experimento, si se indica, y los fijamos
+pub fn leer_config(matches: &ArgMatches) {
+    if let Some(ruta) = matches.value_of("config") {
+        match parametros::leer_parametros(ruta) {
+            Ok(p) => parametros::fijar_parametros(p),
+            Err(e) => salir(&format!("No se pudo leer el archivo de experimento {}", e)),
+        }
+    }
+}
+
+// Leemos el modo de la caché de evaluaciones, que por defecto está desactivada, y lo fijamos
+pub fn leer_cache(matches: &ArgMatches) {
+    if let Some(m) = matches.value_of("cache") {
+        match m.parse() {
+            Ok(modo) => cache::fijar_modo_cache(modo),
+            Err(e) => salir(&e),
+        }
+    }
+}
+
+// Interpreta un máximo de evaluaciones indicado en la línea de órdenes y devuelve los parámetros actuales con él,
+//   comprobándolo igual que el parámetro max_evaluaciones de un archivo de experimento
+pub fn interpretar_evaluaciones(texto: &str) -> Result<parametros::Parametros, String> {
+    let mut p = parametros::parametros();
+    let n: usize = texto.trim().parse().map_err(|_| format!("Número de evaluaciones no válido: {}", texto))?;
+    p.actualizar(&objeto_json!{ "max_evaluaciones" => n }).map_err(|e| format!("Número de evaluaciones no válido: {}", e))?;
+    Ok(p)
+}
+
+// Interpreta una duración: un número seguido de su unidad (ms, s, m o h), o solo un número de segundos
+pub fn interpretar_duracion(texto: &str) -> Result<Duration, String> {
+    let texto = texto.trim();
+    let fin = texto.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(texto.len());
+    let (numero, unidad) = texto.split_at(fin);
+    let segundos_unidad = match unidad.trim() {
+        "" | "s" => 1.0,
+        "ms" => 0.001,
+        "m" | "min" => 60.0,
+        "h" => 3600.0,
+        u => return Err(format!("Unidad de tiempo no válida: {} (debe ser ms, s, m o h)", u)),
+    };
+    // Rechazamos también los tiempos que no caben en un Duration (números enormes o infinitos)
+    match numero.parse::<f64>().ok().filter(|n| *n > 0.0).and_then(|n| Duration::try_from_secs_f64(n*segundos_unidad).ok()) {
+        Some(d) => Ok(d),
+        None => Err(format!("Tiempo no válido: {}", texto)),
+    }
+}
+
+// Leemos el presupuesto de cada ejecución de los algoritmos: el tiempo límite, si se indica, y el máximo de
+//   evaluaciones, que es el parámetro max_evaluaciones salvo que con --time-only no haya máximo
+pub fn leer_limite_tiempo(matches: &ArgMatches) -> Presupuesto {
+    let mut p = Presupuesto::default();
+    if let Some(t) = matches.value_of("limite_tiempo") {
+        p.tiempo = Some(interpretar_duracion(t).unwrap_or_else(|e| salir(&e)));
+        if matches.is_present("solo_tiempo") {
+            p.evaluaciones = Some(usize::MAX);
+        }
+    }
+    p
+}
+
+// Interpreta una lista de pesos: números separados por comas, punto y coma o espacios, una lista JSON
+//   o un objeto JSON con un campo "pesos" (como los modelos guardados por mh train o los registros de --output)
+pub fn interpretar_pesos(texto: &str) -> Result<Vec<f64>, String> {
+    let texto = texto.trim();
+    if texto.starts_with('[') || texto.starts_with('{') {
+        let valor = json::analizar(texto)?;
+        let lista = valor.campo("pesos").unwrap_or(&valor).como_lista().ok_or("No hay una lista de pesos")?;
+        lista.iter().map(|w| w.como_numero().ok_or_else(|| "Los pesos deben ser números".to_string())).collect()
+    } else {
+        texto.split(|c: char| c == ',' || c == ';' || c.is_whitespace()).filter(|w| !w.is_empty())
+             .map(|w| w.parse().map_err(|_| format!("Peso no válido: {}", w))).collect()
+    }
+}
+
+// Leemos los pesos indicados en la línea de órdenes o en un archivo
+pub fn leer_pesos(matches: &ArgMatches) -> Vec<f64> {
+    let texto = match (matches.value_of("pesos"), matches.value_of("archivo_pesos")) {
+        (Some(pesos), _) => pesos.to_string(),
+        (None, Some(ruta)) => {
+            let mut texto = String::new();
+            if let Err(e) = File::open(ruta).and_then(|mut f| f.read_to_string(&mut texto)) {
+                salir(&format!("No se pudo leer el archivo {}: {}", ruta, e));
+            }
+            texto
+        },
+        (None, None) => salir("No se han indicado pesos"),
+    };
+    match interpretar_pesos(&texto) {
+        Ok(pesos) => if pesos.is_empty() { salir("No se han indicado pesos") } else { pesos },
+        Err(e) => salir(&e),
+    }
+}
+
+// Abrimos el archivo de salida de resultados, si se indica
+pub fn leer_salida(matches: &ArgMatches) -> Option<Salida> {
+    matches.value_of("salida").map(|ruta| Salida::crear(ruta).unwrap_or_else(|e| {
+        salir(&format!("No se pudo crear el archivo {}: {}", ruta, e))
+    }))
+}
+
+
+// Creamos el archivo de trazas de convergencia, si se indica
+pub fn leer_traza(matches: &ArgMatches) -> Option<ArchivoTraza> {
+    matches.value_of("traza").map(crear_traza)
+}
+
+// Crea un archivo de trazas, terminando el programa si no se puede
+pub fn crear_traza(ruta: &str) -> ArchivoTraza {
+    ArchivoTraza::crear(ruta).unwrap_or_else(|e| salir(&format!("No se pudo crear el archivo {}: {}", ruta, e)))
+}
+
+// Abrimos el punto de control, si se indica
+// Se abre con la configuración del hilo actual, así que deben haberse leído antes el resto de opciones
+pub fn leer_control(matches: &ArgMatches, validacion: Validacion, presupuesto: Presupuesto) -> Option<PuntoControl> {
+    matches.value_of("control").map(|ruta| abrir_control(ruta, matches.is_present("control_poblacion"), validacion, presupuesto))
+}
+
+// Abre un punto de control con la configuración del hilo actual, terminando el programa si no se puede
+pub fn abrir_control(ruta: &str, poblaciones: bool, validacion: Validacion, presupuesto: Presupuesto) -> PuntoControl {
+    let control = PuntoControl::abrir(ruta, poblaciones, &punto_control::configuracion(validacion, presupuesto)).unwrap_or_else(|e| {
+        salir(&format!("No se pudo abrir el punto de control {}: {}", ruta, e))
+    });
+    if control.num_hechos() > 0 {
+        println!("El punto de control {} contiene {} tests", ruta, control.num_hechos());
+    }
+    control
+}
+
+// Opciones con las que se prueban los algoritmos
+pub struct OpcionesValidacion {
+    pub semilla: u64,                     // Semilla maestra
+    pub repetir: Option<(usize, usize, Option<usize>)>,  // Test que se ejecuta en lugar de la validación completa, si se indica
+    pub ejecuciones: usize,               // Ejecuciones independientes de cada algoritmo en cada test
+    pub hilos: usize,                     // Hilos entre los que se reparten los tests (0 para usar todos los núcleos)
+    pub validacion: Validacion,
+    pub presupuesto: Presupuesto,  // Presupuesto de cada ejecución de los algoritmos
+    pub metricas: bool,            // Si se imprimen las medidas de clasificación detalladas
+    pub salida: Option<Salida>,    // Archivo en el que se escriben los resultados
+    pub control: Option<PuntoControl>,  // Punto de control en el que se guardan los tests terminados
+    pub traza: Option<ArchivoTraza>,    // Archivo en el que se escriben las trazas de convergencia
+}
+
+impl OpcionesValidacion {
+    // Lee las opciones de los argumentos de argumentos_validacion. Fija también la medida de clasificación,
+    //   los parámetros de los algoritmos y la caché de evaluaciones
+    pub fn leer(matches: &ArgMatches) -> OpcionesValidacion {
+        leer_medida(matches);
+        leer_config(matches);
+        leer_cache(matches);
+        let validacion = leer_validacion(matches);
+        let presupuesto = leer_limite_tiempo(matches);
+        OpcionesValidacion {
+            semilla: leer_semilla(matches),
+            repetir: leer_repetir(matches),
+            ejecuciones: leer_ejecuciones(matches),
+            hilos: leer_hilos(matches),
+            validacion,
+            presupuesto,
+            metricas: matches.is_present("metricas"),
+            salida: leer_salida(matches),
+            control: leer_control(matches, validacion, presupuesto),
+            traza: leer_traza(matches),
+        }
+    }
+
+    // Opciones con las que el planificador genera y reparte los tests
+    pub fn planificacion(&self) -> Planificacion {
+        Planificacion {
+            semilla: self.semilla,
+            validacion: self.validacion,
+            ejecuciones: self.ejecuciones,
+            repetir: self.repetir,
+            hilos: self.hilos,
+            presupuesto: self.presupuesto,
+        }
+    }
+}
+
+// Prueba los algoritmos según los argumentos de argumentos_validacion: con las particiones predefinidas si
+//   se indican y, si no, validando con cada uno de los archivos de datos
+pub fn validar(algoritmos: &[Box<dyn Aprendiz>], archivos: &[&str], matches: &ArgMatches) {
+    let mut opciones = OpcionesValidacion::leer(matches);
+
+    let mut conjuntos: Vec<(&str, Datos)> = Vec::new();
+    if let Some(directorio) = matches.value_of("particiones") {
+        // Si se indican particiones predefinidas, se usan en lugar de la validación
+        match knn::leer_directorio_particiones(directorio) {
+            Ok(particiones) => conjuntos.push((directorio, Datos::Particiones(particiones))),
+            Err(e) => println!("No se pudieron leer las particiones de {}: {}", directorio, e),
+        }
+    } else if let (Some(entrenamiento), Some(test)) = (matches.value_of("entrenamiento"), matches.value_of("test")) {
+        match knn::leer_entrenamiento_test(entrenamiento, test) {
+            Ok(particion) => conjuntos.push((test, Datos::Particiones(vec![particion]))),
+            Err(e) => println!("No se pudo leer la partición {} - {}: {}", entrenamiento, test, e),
+        }
+    } else {
+        for archivo in archivos {
+            match knn::leer_archivo(archivo) {
+                Ok(datos) => if !datos.is_empty() { conjuntos.push((archivo, Datos::Archivo(datos))) },
+                Err(e) => println!("No se pudo abrir el archivo {}: {}", archivo, e),
+            }
+        }
+    }
+
+    let aprendices: Vec<&dyn Aprendiz> = algoritmos.iter().map(|a| a.as_ref()).collect();
+    let (mut control, mut traza) = (opciones.control.take(), opciones.traza.take());
+    let vacios = planificador::ejecutar(&aprendices, &conjuntos, &opciones.planificacion(), control.as_mut(), traza.as_mut(), |c, a, resultados| {
+        println!("\n{} sobre los datos en {}...", algoritmos[a].nombre(), conjuntos[c].0);
+        mostrar(algoritmos[a].nombre(), conjuntos[c].0, &resultados, &mut opciones);
+    });
+    for c in vacios {
+        println!("No hay ningún test que ejecutar en {}", conjuntos[c].0);
+    }
+}
+
+// Imprime los resultados de un algoritmo sobre un conjunto de datos y, si hay archivo de salida, los escribe en él
+pub fn mostrar(algoritmo: &str, conjunto: &str, resultados: &ResultadosCV, opciones: &mut OpcionesValidacion) {
+    evaluacion_pesos::mostrar_resultados(resultados, opciones.presupuesto);
+    if opciones.metricas {
+        evaluacion_pesos::mostrar_metricas(resultados);
+    }
+    if let Some(s) = &mut opciones.salida {
+        if let Err(e) = s.escribir(algoritmo, conjunto, resultados) {
+            println!("No se pudieron escribir los resultados: {}", e);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn semillas_numericas_o_de_texto() {
+        assert_eq!(interpretar_semilla("12345"), 12345);
+        assert_eq!(interpretar_semilla(" 0xFF "), 255);
+        assert_eq!(interpretar_semilla("18446744073709551615"), u64::MAX);
+        assert_eq!(interpretar_semilla("hola"), evaluacion_pesos::hash_texto("hola"));
+        // Lo que no es un número válido se toma como texto
+        assert_eq!(interpretar_semilla("-1"), evaluacion_pesos::hash_texto("-1"));
+        assert_eq!(interpretar_semilla("0xZZ"), evaluacion_pesos::hash_texto("0xZZ"));
+    }
+
+    #[test]
+    fn evaluaciones_de_la_linea_de_ordenes() {
+        assert_eq!(interpretar_evaluaciones("500").map(|p| p.max_evaluaciones), Ok(500));
+        for texto in &["0", "-1", "2.5", "diez", ""] {
+            assert!(interpretar_evaluaciones(texto).is_err(), "{} no debería ser válido", texto);
+        }
+    }
+
+    #[test]
+    fn duraciones_con_unidades() {
+        assert_eq!(interpretar_duracion("30"), Ok(Duration::from_secs(30)));
+        assert_eq!(interpretar_duracion(" 30s "), Ok(Duration::from_secs(30)));
+        assert_eq!(interpretar_duracion("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(interpretar_duracion("1.5 m"), Ok(Duration::from_secs(90)));
+        assert_eq!(interpretar_duracion("2min"), Ok(Duration::from_secs(120)));
+        assert_eq!(interpretar_duracion("1h"), Ok(Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn duraciones_no_validas() {
+        for texto in &["", "s", "0", "0s", "-5s", "1.2.3s", "10d", "diez", "1e20", "100000000000000000000", "100000000000000000h"] {
+            assert!(interpretar_duracion(texto).is_err(), "{} no debería ser válido", texto);
+        }
+        assert!(interpretar_duracion(&"9".repeat(400)).is_err());
+    }
+}
@@ -0,0 +1,493 @@
+// Tests estadísticos para comparar algoritmos sobre varios conjuntos de datos, siguiendo a Demšar (2006)
+// Los datos se reciben como una matriz con una fila por bloque (conjunto de datos o partición)
+//   y una columna por algoritmo
+
+use evaluacion_pesos::ResultadosCV;
+use ordered_float::OrderedFloat;
+use resultados::{Columna, matriz_bloques};
+use std::fs::File;
+use std::io::Write;
+
+
+// Funciones de distribución
+
+
+// Función de error complementaria (Numerical Recipes, error relativo menor que 1.2e-7)
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5*z);
+    let r = t * (-z*z - 1.26551223 + t*(1.00002368 + t*(0.37409196 + t*(0.09678418 + t*(-0.18628806
+            + t*(0.27886807 + t*(-1.13520398 + t*(1.48851587 + t*(-0.82215223 + t*0.17087277))))))))).exp();
+    if x >= 0.0 { r } else { 2.0 - r }
+}
+
+// Función de distribución de la normal estándar
+pub fn normal_cdf(x: f64) -> f64 {
+    0.5 * erfc(-x / 2f64.sqrt())
+}
+
+// Logaritmo de la función gamma (aproximación de Lanczos)
+fn ln_gamma(x: f64) -> f64 {
+    let coeficientes = [76.18009172947146, -86.50532032941677, 24.01409824083091,
+                        -1.231739572450155, 0.1208650973866179e-2, -0.5395239384953e-5];
+    let tmp = x + 5.5 - (x + 0.5) * (x + 5.5).ln();
+    let serie = coeficientes.iter().enumerate().fold(1.000000000190015, |s, (i, c)| s + c / (x + 1.0 + i as f64));
+    -tmp + (2.5066282746310005 * serie / x).ln()
+}
+
+// Función gamma incompleta regularizada superior Q(a, x)
+fn gamma_q(a: f64, x: f64) -> f64 {
+    if x <= 0.0 { return 1.0 }
+    let ln_factor = -x + a * x.ln() - ln_gamma(a);
+    if x < a + 1.0 {
+        // Desarrollo en serie de P(a, x)
+        let (mut termino, mut suma, mut n) = (1.0 / a, 1.0 / a, a);
+        for _ in 0..1000 {
+            n += 1.0;
+            termino *= x / n;
+            suma += termino;
+            if termino.abs() < suma.abs() * 1e-15 { break }
+        }
+        1.0 - suma * ln_factor.exp()
+    } else {
+        // Fracción continua de Q(a, x) por el método de Lentz
+        let minimo = 1e-300;
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / minimo;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..1000 {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an*d + b;
+            if d.abs() < minimo { d = minimo }
+            c = b + an/c;
+            if c.abs() < minimo { c = minimo }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < 1e-15 { break }
+        }
+        ln_factor.exp() * h
+    }
+}
+
+// Probabilidad de que una chi-cuadrado con los grados de libertad indicados supere x
+pub fn chi2_p(x: f64, grados: f64) -> f64 {
+    gamma_q(grados / 2.0, x / 2.0)
+}
+
+// Fracción continua de la función beta incompleta (Numerical Recipes)
+fn beta_cf(a: f64, b: f64, x: f64) -> f64 {
+    let minimo = 1e-300;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a+b) * x / (a+1.0);
+    if d.abs() < minimo { d = minimo }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..1000 {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+        for an in &[m * (b-m) * x / ((a+m2-1.0) * (a+m2)), -(a+m) * (a+b+m) * x / ((a+m2) * (a+m2+1.0))] {
+            d = 1.0 + an*d;
+            if d.abs() < minimo { d = minimo }
+            c = 1.0 + an/c;
+            if c.abs() < minimo { c = minimo }
+            d = 1.0 / d;
+            h *= d * c;
+        }
+        if (d*c - 1.0).abs() < 1e-15 { break }
+    }
+    h
+}
+
+// Función beta incompleta regularizada I_x(a, b)
+fn beta_incompleta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 { return 0.0 }
+    if x >= 1.0 { return 1.0 }
+    let factor = (ln_gamma(a+b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0-x).ln()).exp();
+    if x < (a+1.0) / (a+b+2.0) {
+        factor * beta_cf(a, b, x) / a
+    } else {
+        1.0 - factor * beta_cf(b, a, 1.0-x) / b
+    }
+}
+
+// Probabilidad de que una F de Snedecor con los grados de libertad indicados supere x
+pub fn f_p(x: f64, grados1: f64, grados2: f64) -> f64 {
+    if x <= 0.0 { return 1.0 }
+    beta_incompleta(grados2 / 2.0, grados1 / 2.0, grados2 / (grados2 + grados1 * x))
+}
+
+// Función de distribución del rango studentizado de k normales con infinitos grados de libertad:
+//   P(q) = k ∫ φ(z) (Φ(z+q) - Φ(z))^(k-1) dz, integrada por la regla de Simpson
+fn rango_studentizado_cdf(q: f64, k: usize) -> f64 {
+    let (a, b, n) = (-8.0, 8.0, 1600);
+    let h = (b - a) / n as f64;
+    let f = |z: f64| (-z*z/2.0).exp() / (2.0 * ::std::f64::consts::PI).sqrt() * (normal_cdf(z+q) - normal_cdf(z)).powi(k as i32 - 1);
+    let suma: f64 = (0..n+1).map(|i| {
+        let peso = if i == 0 || i == n { 1.0 } else if i % 2 == 1 { 4.0 } else { 2.0 };
+        peso * f(a + i as f64 * h)
+    }).sum();
+    k as f64 * suma * h / 3.0
+}
+
+// Valor crítico q_alpha del test de Nemenyi para k algoritmos: cuantil 1-alpha
+//   del rango studentizado dividido entre raíz de 2. Se obtiene por bisección
+pub fn q_nemenyi(k: usize, alpha: f64) -> f64 {
+    let (mut inf, mut sup) = (0.0, 20.0);
+    while sup - inf > 1e-7 {
+        let medio = (inf + sup) / 2.0;
+        if rango_studentizado_cdf(medio, k) < 1.0 - alpha { inf = medio } else { sup = medio }
+    }
+    (inf + sup) / 2.0 / 2f64.sqrt()
+}
+
+
+
+// Rangos
+
+
+// Asigna rangos a unos valores (1 al mejor), dando a los empates la media de los rangos que ocupan
+pub fn rangos(valores: &[f64], mayor_es_mejor: bool) -> Vec<f64> {
+    let mut orden: Vec<usize> = (0..valores.len()).collect();
+    orden.sort_by_key(|i| if mayor_es_mejor { OrderedFloat(-valores[*i]) } else { OrderedFloat(valores[*i]) });
+    let mut rangos = vec![0.0; valores.len()];
+    let mut i = 0;
+    while i < orden.len() {
+        let mut j = i;
+        while j+1 < orden.len() && valores[orden[j+1]] == valores[orden[i]] { j += 1 }
+        let rango = (i + j) as f64 / 2.0 + 1.0;
+        for o in &orden[i..j+1] { rangos[*o] = rango }
+        i = j + 1;
+    }
+    rangos
+}
+
+// Rango medio de cada algoritmo en todos los bloques
+pub fn rangos_medios(datos: &[Vec<f64>], mayor_es_mejor: bool) -> Vec<f64> {
+    let k = datos.first().map_or(0, |f| f.len());
+    let mut medios = vec![0.0; k];
+    for fila in datos {
+        for (m, r) in medios.iter_mut().zip(rangos(fila, mayor_es_mejor)) {
+            *m += r / datos.len() as f64;
+        }
+    }
+    medios
+}
+
+
+
+// Tests
+
+
+// Resultado del test de rangos con signo de Wilcoxon entre dos algoritmos
+#[derive(Clone, Copy, Debug)]
+pub struct Wilcoxon {
+    pub r_mas: f64,    // Suma de los rangos de los bloques en los que gana el primer algoritmo
+    pub r_menos: f64,  // Suma de los rangos de los bloques en los que gana el segundo
+    pub n: usize,      // Número de bloques con diferencia no nula
+    pub p: f64,        // p-valor bilateral exacto
+}
+
+// Test de rangos con signo de Wilcoxon para dos muestras pareadas
+// Se descartan los bloques sin diferencia y el p-valor se calcula de forma exacta con la distribución
+//   de la suma de rangos positivos, lo que también es válido cuando hay rangos empatados
+pub fn wilcoxon(a: &[f64], b: &[f64], mayor_es_mejor: bool) -> Wilcoxon {
+    let diferencias: Vec<f64> = a.iter().zip(b).map(|(x, y)| if mayor_es_mejor { x - y } else { y - x })
+        .filter(|d| *d != 0.0).collect();
+    let n = diferencias.len();
+    let absolutas: Vec<f64> = diferencias.iter().map(|d| d.abs()).collect();
+    let r = rangos(&absolutas, false);
+    let r_mas: f64 = r.iter().zip(&diferencias).filter(|(_, d)| **d > 0.0).fold(0.0, |s, (r, _)| s + r);
+    let r_menos: f64 = r.iter().zip(&diferencias).filter(|(_, d)| **d < 0.0).fold(0.0, |s, (r, _)| s + r);
+
+    // Distribución de la suma de rangos positivos, usando el doble de los rangos para que sean enteros
+    let dobles: Vec<usize> = r.iter().map(|r| (2.0 * r).round() as usize).collect();
+    let total: usize = dobles.iter().sum();
+    let mut distribucion = vec![0.0; total + 1];
+    distribucion[0] = 1.0;
+    for d in &dobles {
+        for s in (0..total+1).rev() {
+            distribucion[s] = 0.5 * distribucion[s] + if s >= *d { 0.5 * distribucion[s - d] } else { 0.0 };
+        }
+    }
+    let minimo = (2.0 * r_mas.min(r_menos)).round() as usize;
+    let p = if n == 0 { 1.0 } else { (2.0 * distribucion[..minimo+1].iter().sum::<f64>()).min(1.0) };
+
+    Wilcoxon { r_mas, r_menos, n, p }
+}
+
+// Resultado del test de Friedman
+#[derive(Clone, Debug)]
+pub struct Friedman {
+    pub rangos_medios: Vec<f64>,
+    pub chi2: f64,        // Estadístico de Friedman, con k-1 grados de libertad
+    pub p_chi2: f64,
+    pub f: f64,           // Corrección de Iman y Davenport, con k-1 y (k-1)(N-1) grados de libertad
+    pub p_f: f64,
+}
+
+// Test de Friedman sobre las posiciones de los algoritmos en cada bloque
+pub fn friedman(datos: &[Vec<f64>], mayor_es_mejor: bool) -> Friedman {
+    let n = datos.len() as f64;
+    let rangos_medios = rangos_medios(datos, mayor_es_mejor);
+    let k = rangos_medios.len() as f64;
+    let suma_cuadrados: f64 = rangos_medios.iter().map(|r| r*r).sum();
+    let chi2 = 12.0 * n / (k * (k+1.0)) * (suma_cuadrados - k * (k+1.0) * (k+1.0) / 4.0);
+    let denominador = n * (k-1.0) - chi2;
+    let f = if denominador > 0.0 { (n-1.0) * chi2 / denominador } else { f64::INFINITY };
+    let p_f = if f.is_finite() { f_p(f, k-1.0, (k-1.0) * (n-1.0)) } else { 0.0 };
+
+    Friedman { p_chi2: chi2_p(chi2, k-1.0), rangos_medios, chi2, f, p_f }
+}
+
+// Error típico de la diferencia de rangos medios entre dos algoritmos
+pub fn error_rangos(k: usize, n: usize) -> f64 {
+    (k as f64 * (k as f64 + 1.0) / (6.0 * n as f64)).sqrt()
+}
+
+// Comparación de un algoritmo con el de control en el procedimiento de Holm
+#[derive(Clone, Copy, Debug)]
+pub struct ComparacionHolm {
+    pub algoritmo: usize,
+    pub z: f64,
+    pub p: f64,
+    pub p_ajustado: f64,  // p-valor ajustado por el procedimiento de Holm
+    pub rechazada: bool,  // Si se rechaza la igualdad con el de control al nivel de significación pedido
+}
+
+// Procedimiento post-hoc de Holm comparando todos los algoritmos con el de control a partir de sus rangos medios
+// Las comparaciones se devuelven en orden creciente de p-valor
+pub fn holm(rangos_medios: &[f64], n: usize, control: usize, alpha: f64) -> Vec<ComparacionHolm> {
+    let k = rangos_medios.len();
+    let se = error_rangos(k, n);
+    let mut comparaciones: Vec<ComparacionHolm> = (0..k).filter(|i| *i != control).map(|i| {
+        let z = (rangos_medios[i] - rangos_medios[control]) / se;
+        ComparacionHolm { algoritmo: i, z, p: 2.0 * (1.0 - normal_cdf(z.abs())), p_ajustado: 0.0, rechazada: false }
+    }).collect();
+    comparaciones.sort_by_key(|c| OrderedFloat(c.p));
+
+    let m = comparaciones.len();
+    let mut p_maximo: f64 = 0.0;
+    let mut rechazando = true;
+    for (i, c) in comparaciones.iter_mut().enumerate() {
+        p_maximo = p_maximo.max(((m - i) as f64 * c.p).min(1.0));
+        c.p_ajustado = p_maximo;
+        rechazando = rechazando && c.p <= alpha / (m - i) as f64;
+        c.rechazada = rechazando;
+    }
+    comparaciones
+}
+
+// Diferencia crítica del test de Nemenyi: dos algoritmos son significativamente distintos
+//   si sus rangos medios difieren en más de este valor
+pub fn diferencia_critica(k: usize, n: usize, alpha: f64) -> f64 {
+    q_nemenyi(k, alpha) * error_rangos(k, n)
+}
+
+// Grupos de algoritmos cuyos rangos medios no difieren más que la diferencia crítica, como los que se unen
+//   con una barra en los diagramas de diferencia crítica. Se devuelven como intervalos [inicio, fin] de
+//   posiciones en el orden de rango medio, descartando los contenidos en otros y los de un solo algoritmo
+pub fn grupos_nemenyi(rangos_medios: &[f64], cd: f64) -> Vec<(usize, usize)> {
+    let mut orden: Vec<f64> = rangos_medios.to_vec();
+    orden.sort_by_key(|r| OrderedFloat(*r));
+    let mut grupos: Vec<(usize, usize)> = Vec::new();
+    for inicio in 0..orden.len() {
+        let mut fin = inicio;
+        while fin+1 < orden.len() && orden[fin+1] - orden[inicio] <= cd { fin += 1 }
+        if fin > inicio && grupos.last().is_none_or(|g| g.1 < fin) {
+            grupos.push((inicio, fin));
+        }
+    }
+    grupos
+}
+
+
+
+// Comparación de los resultados guardados
+
+
+// Compara los algoritmos de los resultados leídos e imprime los tests
+// Si se indica un archivo, se escriben en él los rangos medios y la diferencia crítica en CSV para dibujar el diagrama
+pub fn mostrar_comparacion(algoritmos: &[(String, Vec<(String, ResultadosCV)>)], columna: Columna, por_particion: bool,
+            alpha: f64, control: Option<&str>, archivo_cd: Option<&str>) {
+    let (bloques, datos) = matriz_bloques(algoritmos, columna, por_particion);
+    let (k, n) = (algoritmos.len(), datos.len());
+    if k < 2 || n < 2 {
+        println!("Se necesitan al menos dos algoritmos evaluados en dos bloques comunes (hay {} algoritmos y {} bloques)", k, n);
+        return;
+    }
+    let mayor_es_mejor = columna.mayor_es_mejor();
+    let nombre = |i: usize| &algoritmos[i].0;
+
+    println!("Comparación de {} algoritmos en {} bloques según {:?}: {}", k, n, columna, bloques.join(", "));
+
+    // Ranking de los algoritmos
+    let friedman = friedman(&datos, mayor_es_mejor);
+    let mut orden: Vec<usize> = (0..k).collect();
+    orden.sort_by_key(|i| OrderedFloat(friedman.rangos_medios[*i]));
+    println!("\nPos. Rango medio    Media  Algoritmo");
+    for (pos, i) in orden.iter().enumerate() {
+        let media = datos.iter().map(|f| f[*i]).sum::<f64>() / n as f64;
+        println!("{:4} {:12.3} {:8.2}  {}", pos+1, friedman.rangos_medios[*i], media, nombre(*i));
+    }
+
+    println!("\nFriedman: chi2 = {:.4} ({} g.l.), p = {:.6}", friedman.chi2, k-1, friedman.p_chi2);
+    println!("Iman-Davenport: F = {:.4} ({} y {} g.l.), p = {:.6}", friedman.f, k-1, (k-1)*(n-1), friedman.p_f);
+
+    // Post-hoc de Holm con el mejor algoritmo (o el indicado) como control
+    let control = match control {
+        Some(c) => match algoritmos.iter().position(|a| a.0 == c) {
+            Some(i) => i,
+            None => { println!("\nNo hay resultados del algoritmo de control {}", c); return },
+        },
+        None => orden[0],
+    };
+    println!("\nHolm con {} como control (alpha = {}):", nombre(control), alpha);
+    for c in holm(&friedman.rangos_medios, n, control, alpha) {
+        println!("  z = {:7.3}, p = {:.6}, p ajustado = {:.6} {} {}", c.z, c.p, c.p_ajustado,
+                 if c.rechazada { "*" } else { " " }, nombre(c.algoritmo));
+    }
+
+    // Nemenyi: diferencia crítica y grupos de algoritmos sin diferencias significativas
+    let cd = diferencia_critica(k, n, alpha);
+    println!("\nNemenyi: diferencia crítica = {:.4} (alpha = {})", cd, alpha);
+    for (inicio, fin) in grupos_nemenyi(&friedman.rangos_medios, cd) {
+        let grupo: Vec<&str> = orden[inicio..fin+1].iter().map(|i| nombre(*i).as_str()).collect();
+        println!("  Sin diferencias significativas: {}", grupo.join(", "));
+    }
+
+    // Wilcoxon para cada par de algoritmos
+    println!("\nWilcoxon por pares (R+ corresponde al primer algoritmo):");
+    for (a, b) in orden.iter().enumerate().flat_map(|(p, a)| orden[p+1..].iter().map(move |b| (*a, *b))) {
+        let columna_a: Vec<f64> = datos.iter().map(|f| f[a]).collect();
+        let columna_b: Vec<f64> = datos.iter().map(|f| f[b]).collect();
+        let w = wilcoxon(&columna_a, &columna_b, mayor_es_mejor);
+        println!("  R+ = {:7.1}, R- = {:7.1}, p = {:.6} {} {} vs {}", w.r_mas, w.r_menos, w.p,
+                 if w.p <= alpha { "*" } else { " " }, nombre(a), nombre(b));
+    }
+
+    if let Some(ruta) = archivo_cd {
+        let escritura = File::create(ruta).and_then(|mut f| {
+            writeln!(f, "algoritmo,rango_medio,diferencia_critica")?;
+            for i in &orden {
+                writeln!(f, "\"{}\",{},{}", nombre(*i).replace('"', "\"\""), friedman.rangos_medios[*i], cd)?;
+            }
+            Ok(())
+        });
+        if let Err(e) = escritura {
+            println!("No se pudo escribir {}: {}", ruta, e);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cerca(a: f64, b: f64, tolerancia: f64) -> bool {
+        (a - b).abs() <= tolerancia
+    }
+
+    // Rangos de los cuatro algoritmos en los 14 conjuntos del ejemplo de Demšar (2006), sección 3.2.2:
+    //   C4.5, C4.5+m, C4.5+cf y C4.5+m+cf, con rangos medios 3.143, 2.000, 2.893 y 1.964
+    fn ejemplo_demsar() -> Vec<Vec<f64>> {
+        vec![vec![3.5, 2.0, 3.5, 1.0], vec![3.5, 2.0, 3.5, 1.0], vec![3.0, 2.0, 4.0, 1.0], vec![4.0, 3.0, 2.0, 1.0],
+             vec![4.0, 1.5, 1.5, 3.0], vec![3.0, 1.0, 2.0, 4.0], vec![3.0, 1.0, 4.0, 2.0], vec![2.0, 1.0, 4.0, 3.0],
+             vec![3.5, 1.0, 3.5, 2.0], vec![3.0, 2.0, 4.0, 1.0], vec![3.0, 1.0, 2.0, 4.0], vec![2.5, 4.0, 1.0, 2.5],
+             vec![2.0, 4.0, 3.0, 1.0], vec![4.0, 2.5, 2.5, 1.0]]
+    }
+
+    #[test]
+    fn distribuciones() {
+        assert!(cerca(normal_cdf(1.959964), 0.975, 1e-6));
+        assert!(cerca(normal_cdf(-1.0), 0.158655, 1e-6));
+        // Valores críticos al 5% de la chi-cuadrado y de la F de Snedecor
+        assert!(cerca(chi2_p(3.841, 1.0), 0.05, 1e-4));
+        assert!(cerca(chi2_p(7.815, 3.0), 0.05, 1e-4));
+        assert!(cerca(f_p(2.845, 3.0, 39.0), 0.05, 1e-3));
+        assert!(cerca(f_p(4.965, 1.0, 10.0), 0.05, 1e-4));
+    }
+
+    #[test]
+    fn valores_criticos_de_nemenyi() {
+        // Tabla 5 de Demšar (2006)
+        for (k, q) in &[(2, 1.960), (3, 2.343), (4, 2.569), (5, 2.728), (10, 3.164)] {
+            assert!(cerca(q_nemenyi(*k, 0.05), *q, 1e-3), "q_0.05({}) = {}", k, q_nemenyi(*k, 0.05));
+        }
+        for (k, q) in &[(2, 1.645), (3, 2.052), (5, 2.459)] {
+            assert!(cerca(q_nemenyi(*k, 0.10), *q, 1e-3), "q_0.10({}) = {}", k, q_nemenyi(*k, 0.10));
+        }
+        assert!(cerca(diferencia_critica(4, 14, 0.05), 1.25, 5e-3));
+    }
+
+    #[test]
+    fn rangos_con_empates() {
+        assert_eq!(rangos(&[0.5, 0.9, 0.5, 0.1], true), vec![2.5, 1.0, 2.5, 4.0]);
+        assert_eq!(rangos(&[0.5, 0.9, 0.5, 0.1], false), vec![2.5, 4.0, 2.5, 1.0]);
+        assert_eq!(rangos_medios(&[vec![1.0, 2.0], vec![3.0, 3.0]], true), vec![1.75, 1.25]);
+    }
+
+    #[test]
+    fn wilcoxon_con_los_valores_criticos_de_la_tabla() {
+        // Mayor suma de rangos T con la que se rechaza la igualdad al 5% (bilateral) con n diferencias
+        for (n, t) in &[(6, 0), (7, 2), (8, 3), (9, 5), (10, 8), (12, 13), (15, 25)] {
+            // Diferencias 1..n en las que se hacen negativas, de mayor a menor, las necesarias para que R- = r
+            let prueba = |r: usize| {
+                let mut pendiente = r;
+                let diferencias: Vec<f64> = (1..n+1).rev().map(|i| {
+                    if i <= pendiente { pendiente -= i; -(i as f64) } else { i as f64 }
+                }).collect();
+                wilcoxon(&diferencias, &vec![0.0; *n], true)
+            };
+            let w = prueba(*t);
+            assert_eq!((w.n, w.r_menos, w.r_mas), (*n, *t as f64, (n*(n+1)/2 - t) as f64));
+            assert!(w.p <= 0.05, "n = {}, T = {}: p = {}", n, t, w.p);
+            assert!(prueba(t+1).p > 0.05, "n = {}, T = {}: p = {}", n, t+1, prueba(t+1).p);
+        }
+        // Los empates entre las muestras se descartan, y sin diferencias no se rechaza nada
+        assert_eq!(wilcoxon(&[1.0, 2.0, 3.0], &[1.0, 0.0, 3.0], true).n, 1);
+        assert_eq!(wilcoxon(&[1.0, 2.0], &[1.0, 2.0], true).p, 1.0);
+        // Con todos los bloques a favor, p = 2 / 2^n
+        assert!(cerca(wilcoxon(&[1.0; 5], &[0.0; 5], true).p, 0.0625, 1e-12));
+        assert_eq!(wilcoxon(&[1.0; 5], &[0.0; 5], false).r_menos, 15.0);
+    }
+
+    #[test]
+    fn friedman_e_iman_davenport() {
+        let f = friedman(&ejemplo_demsar(), false);
+        let esperados = [44.0 / 14.0, 28.0 / 14.0, 40.5 / 14.0, 27.5 / 14.0];
+        assert!(f.rangos_medios.iter().zip(&esperados).all(|(r, e)| cerca(*r, *e, 1e-12)));
+        assert!(cerca(f.chi2, 9.28, 5e-3));
+        assert!(cerca(f.f, 3.69, 5e-3));
+        // F(3, 39) = 3.69 supera el valor crítico de 2.85, por lo que se rechaza la hipótesis nula
+        assert!(f.p_f < 0.05 && f.p_f > 0.01);
+        assert!(f.p_chi2 < 0.05 && f.p_chi2 > 0.01);
+        // Si todos los bloques ordenan igual a los algoritmos, la corrección es infinita
+        let f = friedman(&[vec![1.0, 2.0, 3.0], vec![1.0, 2.0, 3.0]], true);
+        assert!(f.f.is_infinite() && f.p_f == 0.0);
+    }
+
+    #[test]
+    fn holm_con_el_ejemplo_de_demsar() {
+        let f = friedman(&ejemplo_demsar(), false);
+        let comparaciones = holm(&f.rangos_medios, 14, 3, 0.05);
+        assert_eq!(comparaciones.iter().map(|c| c.algoritmo).collect::<Vec<_>>(), vec![0, 2, 1]);
+        assert!(cerca(comparaciones[0].z, 2.415, 1e-3) && cerca(comparaciones[1].z, 1.903, 1e-3));
+        // Sólo C4.5 es peor que el de control: 0.016 < 0.05/3, pero 0.057 > 0.05/2
+        assert_eq!(comparaciones.iter().map(|c| c.rechazada).collect::<Vec<_>>(), vec![true, false, false]);
+        assert!(cerca(comparaciones[0].p_ajustado, 3.0 * comparaciones[0].p, 1e-12));
+        assert!(comparaciones.windows(2).all(|c| c[0].p_ajustado <= c[1].p_ajustado));
+        assert!(comparaciones[2].p_ajustado <= 1.0);
+    }
+
+    #[test]
+    fn grupos_de_nemenyi() {
+        assert_eq!(grupos_nemenyi(&[1.0, 1.5, 3.0, 3.2], 1.0), vec![(0, 1), (2, 3)]);
+        assert_eq!(grupos_nemenyi(&[3.0, 1.0, 2.0], 1.0), vec![(0, 1), (1, 2)]);
+        assert_eq!(grupos_nemenyi(&[1.0, 2.0, 3.0], 2.0), vec![(0, 2)]);
+        assert!(grupos_nemenyi(&[1.0, 3.0], 1.0).is_empty());
+    }
+}
@@ -1,11 +1,19 @@
-extern crate time;
 extern crate std;
 
+use aprendiz::Aprendizaje;
+use cache;
 use knn;
 use knn::Dato;
+use presupuesto::{Evaluador, Presupuesto};
 
 use rand::{Rng, SeedableRng, Isaac64Rng}; // generadores de números aleatorios
-use self::time::PreciseTime;    // medición de tiempo con resolución de 1 ns (aunque solo lo usaremos con precisión de 1 ms)
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::str::FromStr;
+use std::time::Instant;
 
 
 // Obtiene la distribución de las clases de una muestra como el total de elementos de cada clase
@@ -23,34 +31,185 @@ pub fn get_distribucion_clases(vm: &[Dato]) -> Vec<u32> {
 
 // Obtiene un vector con referencias a los datos de una clase a partir de su identificador
 pub fn get_datos_de_clase<'a>(vm: &[&'a Dato], id: i32) -> Vec<&'a Dato> {
-    vm.iter().filter(|m| m.id_categoria() == id).map(|m| *m).collect()
+    vm.iter().filter(|m| m.id_categoria() == id).copied().collect()
 }
 
 
+// Medida con la que se puntúa la clasificación de una muestra en la función objetivo
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MedidaClasificacion {
+    Acierto,            // Porcentaje de datos bien clasificados, la medida pedida en las prácticas
+    AciertoBalanceado,  // Media de la sensibilidad de cada clase, útil con clases desbalanceadas
+    MediaGeometrica,    // Media geométrica de la sensibilidad de cada clase (G-mean)
+}
+
+impl FromStr for MedidaClasificacion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<MedidaClasificacion, String> {
+        match s.trim().to_lowercase().as_str() {
+            "acierto" => Ok(MedidaClasificacion::Acierto),
+            "balanceado" => Ok(MedidaClasificacion::AciertoBalanceado),
+            "gmedia" => Ok(MedidaClasificacion::MediaGeometrica),
+            otra => Err(format!("Medida de clasificación desconocida: {}. Las medidas son acierto, balanceado y gmedia", otra)),
+        }
+    }
+}
+
+// Medida de clasificación usada por la función objetivo, que se puede cambiar en cada hilo de ejecución
+// No se pasa como parámetro porque todos los algoritmos llaman a la función objetivo directamente
+thread_local! {
+    static MEDIDA_CLASIFICACION: Cell<MedidaClasificacion> = const { Cell::new(MedidaClasificacion::Acierto) };
+}
+
+// Fija la medida de clasificación que usa la función objetivo en el hilo de ejecución actual
+pub fn fijar_medida_clasificacion(medida: MedidaClasificacion) {
+    MEDIDA_CLASIFICACION.with(|m| m.set(medida));
+}
+
+// Obtiene la medida de clasificación que usa la función objetivo en el hilo de ejecución actual
+pub fn medida_clasificacion() -> MedidaClasificacion {
+    MEDIDA_CLASIFICACION.with(|m| m.get())
+}
+
+
+// Matriz de confusión de una clasificación
+// El elemento [i][j] es el número de datos de la clase i a los que se ha asignado la clase j
+// Todas las medidas se devuelven como porcentajes salvo kappa, que está en [-1, 1]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatrizConfusion(pub Vec<Vec<usize>>);
+
+impl MatrizConfusion {
+    pub fn num_clases(&self) -> usize {
+        self.0.len()
+    }
+
+    // Número de datos de una clase
+    fn soporte(&self, clase: usize) -> usize {
+        self.0[clase].iter().sum()
+    }
+
+    // Número de datos a los que se ha asignado una clase
+    fn asignados(&self, clase: usize) -> usize {
+        self.0.iter().map(|fila| fila[clase]).sum()
+    }
+
+    pub fn total(&self) -> usize {
+        self.0.iter().map(|fila| fila.iter().sum::<usize>()).sum()
+    }
+
+    // Porcentaje de datos bien clasificados
+    pub fn tasa_acierto(&self) -> f64 {
+        let aciertos: usize = (0..self.num_clases()).map(|c| self.0[c][c]).sum();
+        100.0 * aciertos as f64 / self.total() as f64
+    }
+
+    // Porcentaje de los datos a los que se ha asignado una clase que son de esa clase
+    // Si no se ha asignado la clase a ningún dato se devuelve 0
+    pub fn precision(&self, clase: usize) -> f64 {
+        let asignados = self.asignados(clase);
+        if asignados == 0 { 0.0 } else { 100.0 * self.0[clase][clase] as f64 / asignados as f64 }
+    }
+
+    // Porcentaje de los datos de una clase a los que se ha asignado esa clase (recall)
+    // Si no hay datos de la clase se devuelve 0
+    pub fn sensibilidad(&self, clase: usize) -> f64 {
+        let soporte = self.soporte(clase);
+        if soporte == 0 { 0.0 } else { 100.0 * self.0[clase][clase] as f64 / soporte as f64 }
+    }
+
+    // Media armónica de la precisión y la sensibilidad de una clase
+    pub fn f1(&self, clase: usize) -> f64 {
+        let (p, r) = (self.precision(clase), self.sensibilidad(clase));
+        if p + r == 0.0 { 0.0 } else { 2.0 * p * r / (p + r) }
+    }
+
+    // Sensibilidades de las clases que aparecen en los datos clasificados
+    fn sensibilidades(&self) -> Vec<f64> {
+        (0..self.num_clases()).filter(|c| self.soporte(*c) > 0).map(|c| self.sensibilidad(c)).collect()
+    }
+
+    // Media de las sensibilidades de las clases que aparecen en los datos clasificados
+    pub fn acierto_balanceado(&self) -> f64 {
+        let sensibilidades = self.sensibilidades();
+        sensibilidades.iter().sum::<f64>() / sensibilidades.len() as f64
+    }
+
+    // Media geométrica de las sensibilidades de las clases que aparecen en los datos clasificados
+    // Es nula en cuanto no se acierta ningún dato de alguna clase
+    pub fn media_geometrica(&self) -> f64 {
+        let sensibilidades = self.sensibilidades();
+        100.0 * sensibilidades.iter().map(|s| s / 100.0).product::<f64>().powf(1.0 / sensibilidades.len() as f64)
+    }
+
+    // Kappa de Cohen: concordancia entre las clases asignadas y las reales descontando la esperada por azar
+    // Si la concordancia esperada por azar es total (por ejemplo, con un solo dato) se devuelve 0
+    pub fn kappa(&self) -> f64 {
+        let total = self.total() as f64;
+        let observada = self.tasa_acierto() / 100.0;
+        let esperada = (0..self.num_clases())
+            .map(|c| self.soporte(c) as f64 * self.asignados(c) as f64).sum::<f64>() / (total * total);
+        if esperada >= 1.0 { 0.0 } else { (observada - esperada) / (1.0 - esperada) }
+    }
+
+    // Puntuación de la clasificación según la medida indicada
+    pub fn medida(&self, medida: MedidaClasificacion) -> f64 {
+        match medida {
+            MedidaClasificacion::Acierto => self.tasa_acierto(),
+            MedidaClasificacion::AciertoBalanceado => self.acierto_balanceado(),
+            MedidaClasificacion::MediaGeometrica => self.media_geometrica(),
+        }
+    }
+}
+
+// Escoge el clasificador 1-NN adecuado para clasificar un conjunto de test
+// Usa leave-one-out si el conjunto de entrenamiento y el de prueba coinciden
+fn clasificador(entrenamiento: &[Dato], test: &[Dato]) -> fn(&[Dato], &Dato, &[f64]) -> i32 {
+    match ((entrenamiento as *const _, entrenamiento.len()) == (test as *const _, test.len()), entrenamiento[0].solo_flotantes()) {
+        (true,  true ) => knn::get_mas_cercano_distinto_f,
+        (true,  false) => knn::get_mas_cercano_distinto,
+        (false, true ) => knn::get_mas_cercano_f,
+        (false, false) => knn::get_mas_cercano,
+    }
+}
+
+// Clasifica una muestra con el criterio 1-NN (con leave-one-out si el conjunto de test es el de entrenamiento)
+//   y obtiene la matriz de confusión de la clasificación
+pub fn matriz_confusion(entrenamiento: &[Dato], test: &[Dato], w: &[f64]) -> MatrizConfusion {
+    let clasificador = clasificador(entrenamiento, test);
+    let num_clases = get_distribucion_clases(entrenamiento).len().max(get_distribucion_clases(test).len());
+    let mut matriz = vec![vec![0; num_clases]; num_clases];
+    for dato in test {
+        matriz[dato.id_categoria() as usize][clasificador(entrenamiento, dato, w) as usize] += 1;
+    }
+    MatrizConfusion(matriz)
+}
+
 // Puntúa una distribución de pesos según su tasa de aciertos clasificando una muestra
 //   con el criterio 1-NN (con leave-one-out si el conjunto de test es el de entrenamiento)
-// Se devuelve el porcentaje de clases correctamente asignadas
+// Se devuelve el porcentaje de clases correctamente asignadas o, si se ha cambiado la medida
+//   de clasificación con fijar_medida_clasificacion, la puntuación según dicha medida
 // Asume que los pesos están normalizados: el máximo es 1
-fn evaluar_clasificacion(entrenamiento: &[Dato], test: &[Dato], w: &[f64]) -> f64 {
+pub fn evaluar_clasificacion(entrenamiento: &[Dato], test: &[Dato], w: &[f64]) -> f64 {
+    let medida = medida_clasificacion();
+    if medida != MedidaClasificacion::Acierto {
+        return matriz_confusion(entrenamiento, test, w).medida(medida);
+    }
+
     let instancias_test = test.len();
 
     // Escoge el clasificador con leave-one-out si el conjunto de entrenamiento y el de prueba coinciden
-    let clasificador = match ((entrenamiento as *const _, entrenamiento.len()) == (test as *const _, test.len()), entrenamiento[0].solo_flotantes()) {
-        (true,  true ) => knn::get_mas_cercano_distinto_f,
-        (true,  false) => knn::get_mas_cercano_distinto,
-        (false, true ) => knn::get_mas_cercano_f,
-        (false, false) => knn::get_mas_cercano,
-    };
+    let clasificador = clasificador(entrenamiento, test);
 
     // Clasifica los datos del conjunto de prueba y cuenta cuántos coinciden con su clase correcta
-    let bien_clasificadas = test.iter().filter(|dato| clasificador(&entrenamiento, &dato, &w) == dato.id_categoria()).count();
+    let bien_clasificadas = test.iter().filter(|dato| clasificador(entrenamiento, dato, w) == dato.id_categoria()).count();
 
     100.0 * (bien_clasificadas as f64) / (instancias_test as f64)
 }
 
 // Puntúa una distribución de pesos según su simplicidad
 // Asume que los pesos están normalizados: el máximo es 1
-fn evaluar_simplicidad(w: &[f64]) -> f64 {
+pub fn evaluar_simplicidad(w: &[f64]) -> f64 {
     100.0 * (w.iter().filter(|p| **p < 0.2).count() as f64) / (w.len() as f64)
 }
 
@@ -63,8 +222,50 @@ fn evaluar_agregado(tasa_clas: f64, tasa_red: f64) -> f64 {
 // Puntúa una distribución de pesos según su tasa de aciertos en leave-one-out
 //   clasificando una muestra y según su simplicidad
 // Esta será la función objetivo usada por todos los algoritmos salvo el RELIEF
-pub fn evaluar(datos: &[Dato], w: &[f64]) -> f64 {
-    evaluar_agregado(evaluar_clasificacion(&datos, &datos, &w), evaluar_simplicidad(&w))
+// La evaluación se cuenta en el evaluador, y si se ha agotado su presupuesto devuelve menos infinito (ver presupuesto.rs)
+// Si la caché está activa, los pesos ya evaluados se recuperan de ella sin contar la evaluación (ver cache.rs)
+pub fn evaluar(evaluador: &Evaluador, datos: &[Dato], w: &[f64]) -> f64 {
+    cache::evaluar(evaluador, datos, w, || evaluador.evaluar(|| puntuar(datos, w)))
+}
+
+// Puntúa una distribución de pesos como evaluar, pero sin contar la evaluación
+pub fn puntuar(datos: &[Dato], w: &[f64]) -> f64 {
+    evaluar_agregado(evaluar_clasificacion(datos, datos, w), evaluar_simplicidad(w))
+}
+
+// Puntúa una distribución de pesos según su tasa de aciertos en leave-one-out y según
+//   su simplicidad, devolviendo ambas puntuaciones por separado sin agregarlas
+// Esta será la función objetivo de los algoritmos multiobjetivo
+// Si se ha agotado el presupuesto de evaluaciones, devuelve menos infinito en ambas
+pub fn evaluar_objetivos(evaluador: &Evaluador, datos: &[Dato], w: &[f64]) -> (f64, f64) {
+    evaluador.evaluar_con(|| (evaluar_clasificacion(datos, datos, w), evaluar_simplicidad(w)))
+        .unwrap_or((f64::NEG_INFINITY, f64::NEG_INFINITY))
+}
+
+// Solución de un algoritmo multiobjetivo: sus puntuaciones de clasificación y simplicidad y sus pesos
+pub type SolucionMO = ((f64, f64), Vec<f64>);
+
+// Procedimiento que obtiene un frente de Pareto de pesos a partir de una muestra, como los algoritmos multiobjetivo
+type ObtencionFrente<'a> = dyn Fn(&[Dato], &Evaluador, &mut Isaac64Rng) -> Vec<SolucionMO> + 'a;
+
+// Calcula el hipervolumen de un conjunto de soluciones en el espacio de objetivos (clasificación, simplicidad)
+// Es el área dominada por las soluciones tomando como punto de referencia el (0, 0) y se
+//   devuelve como porcentaje del área del cuadrado [0, 100]x[0, 100] de puntuaciones posibles
+// Las soluciones dominadas no aportan área, por lo que no es necesario descartarlas antes
+pub fn hipervolumen(soluciones: &[SolucionMO]) -> f64 {
+    let mut puntos: Vec<(f64, f64)> = soluciones.iter().map(|s| s.0).collect();
+    // Ordenamos de mayor a menor clasificación: cada punto que mejore la simplicidad de
+    //   los anteriores añade una franja de altura igual a su tasa de clasificación
+    puntos.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+    let mut area = 0.0;
+    let mut red_anterior = 0.0;
+    for (clas, red) in puntos {
+        if red > red_anterior {
+            area += clas * (red - red_anterior);
+            red_anterior = red;
+        }
+    }
+    area / 100.0
 }
 
 // Obtiene un vector de (cualquier tipo) a partir de un slice de referencias a (cualquier tipo)
@@ -77,71 +278,693 @@ macro_rules! desreferenciar {
     };
 }
 
-// Implementación de 5-fold cross validation
-// Recibe una función que implemente un algoritmo que obtenga pesos de una muestra de entrenamiento,
-//   los datos de entrenamiento y validación y una semilla para el PRNG
-// Muestra por pantalla los parámetros pedidos: Tasa_clas, Tasa_red, Agregado y Tiempo,
-//   tanto para cada uno de los tests como la media de estos en los cinco tests
-// Se asume que el algoritmo devuelve los pesos debidamente normalizados
-pub fn ffcv(algoritmo: &Fn(&[Dato], &mut Isaac64Rng) -> Vec<f64>, datos: &[Dato], seed: &[u64]){
-    // Inicializamos un PRNG usando la semilla recibida
-    // Escogemos Isaac64Rng, que implementa el algoritmo ISAAC-64, de Robert Jenkins
-    // Véase https://docs.rs/rand/0.4.2/rand/struct.Isaac64Rng.html para una descripción del RNG
-    //  y http://www.burtleburtle.net/bob/rand/isaacafa.html para información sobre el algoritmo
-    let mut rng = Isaac64Rng::from_seed(seed);
+// Esquema de validación con el que se evalúan los algoritmos
+// Todos los esquemas salvo leave-one-out mantienen aproximadamente la distribución de clases
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Validacion {
+    KFold(usize, usize),  // Validación cruzada con k particiones, repetida con permutaciones independientes
+    HoldOut(f64, usize),  // División en entrenamiento y test con la proporción de test indicada, repetida
+    LeaveOneOut,          // Cada dato es el conjunto de test de una ejecución distinta
+}
+
+impl Default for Validacion {
+    fn default() -> Validacion {
+        Validacion::KFold(5, 1) // 5-fold cross validation, el esquema pedido en las prácticas
+    }
+}
+
+// Interpreta un esquema de validación escrito como texto. Formatos admitidos:
+//   "k" o "kxr":  k-fold cross validation repetida r veces (por ejemplo, "5" o "5x2")
+//   "holdout:p" o "holdout:pxr":  hold-out con proporción de test p repetido r veces (por ejemplo, "holdout:0.3x10")
+//   "loo":  leave-one-out
+impl FromStr for Validacion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Validacion, String> {
+        // Separa un texto de la forma "axr" en a y el número de repeticiones, que es 1 si no se indica
+        fn repeticiones(s: &str) -> Result<(&str, usize), String> {
+            let mut partes = s.splitn(2, 'x');
+            let base = partes.next().unwrap();
+            let r = match partes.next() {
+                Some(r) => r.parse().map_err(|_| format!("Número de repeticiones no válido: {}", r))?,
+                None => 1,
+            };
+            if r == 0 { return Err("El número de repeticiones debe ser positivo".to_string()) }
+            Ok((base, r))
+        }
+
+        let s = s.trim().to_lowercase();
+        if s == "loo" {
+            Ok(Validacion::LeaveOneOut)
+        } else if let Some(resto) = s.strip_prefix("holdout:") {
+            let (p, r) = repeticiones(resto)?;
+            let p: f64 = p.parse().map_err(|_| format!("Proporción de test no válida: {}", p))?;
+            if p <= 0.0 || p >= 1.0 { return Err("La proporción de test debe estar en (0, 1)".to_string()) }
+            Ok(Validacion::HoldOut(p, r))
+        } else {
+            let (k, r) = repeticiones(&s)?;
+            let k: usize = k.parse().map_err(|_| format!("Esquema de validación no válido: {}", s))?;
+            if k < 2 { return Err("Se necesitan al menos dos particiones".to_string()) }
+            Ok(Validacion::KFold(k, r))
+        }
+    }
+}
+
+// Resultado de evaluar un algoritmo en uno de los tests de una validación
+#[derive(Clone, Debug)]
+pub struct ResultadoTest {
+    pub repeticion: usize,  // Repetición del esquema de validación a la que pertenece el test, empezando en 0
+    pub particion: usize,   // Partición usada como conjunto de test dentro de la repetición, empezando en 0
+    pub ejecucion: usize,   // Ejecución independiente del algoritmo en el mismo test, empezando en 0
+    pub tasa_clas: f64,     // Porcentaje de aciertos en el conjunto de test, sea cual sea la medida de clasificación
+    pub tasa_red: f64,
+    pub agregado: f64,      // Agregado de la medida de clasificación en uso y la tasa de reducción
+    pub tiempo_ms: i64,
+    pub evaluaciones: usize,      // Evaluaciones de la función objetivo que ha hecho el algoritmo
+    pub aciertos_cache: usize,    // Evaluaciones que ha resuelto la caché sin contarlas (ver cache.rs)
+    pub semilla: u64,             // Semilla maestra de la validación
+    pub semilla_test: u64,        // Semilla derivada con la que se ejecutó el algoritmo en este test
+    pub pesos: Vec<f64>,          // Pesos aprendidos por el algoritmo
+    pub matriz: MatrizConfusion,  // Matriz de confusión de la clasificación del conjunto de test
+}
+
+// Resultados de evaluar un algoritmo en todos los tests de una validación o de unas particiones predefinidas
+#[derive(Clone, Debug, Default)]
+pub struct ResultadosCV {
+    pub tests: Vec<ResultadoTest>,
+}
+
+impl ResultadosCV {
+    // Media de Tasa_clas, Tasa_red, Agregado y Tiempo en todos los tests
+    pub fn medias(&self) -> (f64, f64, f64, f64) {
+        let n = self.tests.len() as f64;
+        let suma = self.tests.iter().fold((0.0, 0.0, 0.0, 0.0), |s, r| {
+            (s.0 + r.tasa_clas, s.1 + r.tasa_red, s.2 + r.agregado, s.3 + r.tiempo_ms as f64)
+        });
+        (suma.0 / n, suma.1 / n, suma.2 / n, suma.3 / n)
+    }
+
+    // Nombre de cada test: su partición o, si hay varias repeticiones, repetición.partición
+    // Si hay varias ejecuciones por test, se añade la ejecución tras una barra: partición/ejecución
+    pub fn nombre_test(&self, r: &ResultadoTest) -> String {
+        let nombre = if self.tests.iter().any(|r| r.repeticion > 0) {
+            format!("{}.{}", 1+r.repeticion, 1+r.particion)
+        } else {
+            format!("{}", 1+r.particion)
+        };
+        if self.tests.iter().any(|r| r.ejecucion > 0) { format!("{}/{}", nombre, 1+r.ejecucion) } else { nombre }
+    }
+
+    // Ejecuciones independientes del algoritmo que aparecen en los resultados, en orden
+    pub fn ejecuciones(&self) -> Vec<usize> {
+        let mut ejecuciones: Vec<usize> = self.tests.iter().map(|r| r.ejecucion).collect();
+        ejecuciones.sort();
+        ejecuciones.dedup();
+        ejecuciones
+    }
 
+    // Media de Tasa_clas, Tasa_red, Agregado y Tiempo en los tests de cada una de las ejecuciones
+    pub fn medias_ejecuciones(&self) -> Vec<(usize, (f64, f64, f64, f64))> {
+        self.ejecuciones().into_iter().map(|e| {
+            (e, ResultadosCV { tests: self.tests.iter().filter(|r| r.ejecucion == e).cloned().collect() }.medias())
+        }).collect()
+    }
+
+    // Dispersión entre ejecuciones de las medias de Tasa_clas, Tasa_red, Agregado y Tiempo
+    pub fn dispersion(&self) -> (Dispersion, Dispersion, Dispersion, Dispersion) {
+        let medias = self.medias_ejecuciones();
+        let columna = |i: usize| -> Vec<f64> { medias.iter().map(|m| [(m.1).0, (m.1).1, (m.1).2, (m.1).3][i]).collect() };
+        (Dispersion::de(&columna(0), true), Dispersion::de(&columna(1), true),
+         Dispersion::de(&columna(2), true), Dispersion::de(&columna(3), false))
+    }
+}
+
+// Estadísticos de los valores de una medida en varias ejecuciones independientes
+#[derive(Clone, Copy, Debug)]
+pub struct Dispersion {
+    pub media: f64,
+    pub desviacion: f64,  // Desviación típica muestral
+    pub mejor: f64,
+    pub peor: f64,
+    pub mediana: f64,
+}
+
+impl Dispersion {
+    // Calcula los estadísticos de unos valores, indicando si en la medida es mejor un valor mayor o uno menor
+    pub fn de(valores: &[f64], mayor_es_mejor: bool) -> Dispersion {
+        let n = valores.len();
+        let mut ordenados = valores.to_vec();
+        ordenados.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        let media = valores.iter().sum::<f64>() / n as f64;
+        let desviacion = if n > 1 {
+            (valores.iter().map(|x| (x - media).powi(2)).sum::<f64>() / (n - 1) as f64).sqrt()
+        } else {
+            0.0
+        };
+        let mediana = if n % 2 == 1 { ordenados[n / 2] } else { (ordenados[n / 2 - 1] + ordenados[n / 2]) / 2.0 };
+        let (minimo, maximo) = (ordenados[0], ordenados[n - 1]);
+        let (mejor, peor) = if mayor_es_mejor { (maximo, minimo) } else { (minimo, maximo) };
+        Dispersion { media, desviacion, mejor, peor, mediana }
+    }
+}
+
+// Semillas
+// Cada permutación de los datos y cada ejecución de un algoritmo usan un RNG propio, cuya semilla se deriva
+//   de la semilla maestra y de la posición del test. Así los resultados de un test no dependen de los números
+//   aleatorios que hayan consumido los anteriores, y cualquier test se puede repetir por separado
+
+const FLUJO_PARTICIONES: u64 = 1;  // Etiqueta de las semillas con las que se permutan los datos en cada repetición
+const FLUJO_ALGORITMO: u64 = 2;    // Etiqueta de las semillas con las que se ejecuta el algoritmo en cada test
+
+// Función de mezcla de SplitMix64: transforma un entero de 64 bits en otro con todos sus bits bien repartidos
+fn mezclar(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+// Deriva de una semilla maestra la semilla de un flujo de números aleatorios identificado por unas etiquetas
+pub fn derivar_semilla(maestra: u64, etiquetas: &[u64]) -> u64 {
+    etiquetas.iter().fold(mezclar(maestra), |s, e| mezclar(s ^ mezclar(*e)))
+}
+
+// Transforma un texto en un entero de 64 bits con el hash FNV-1a de sus bytes
+pub fn hash_texto(texto: &str) -> u64 {
+    texto.bytes().fold(0xcbf29ce484222325, |h, b| (h ^ u64::from(b)).wrapping_mul(0x100000001b3))
+}
+
+// Semilla con la que se ejecuta el algoritmo en una de las ejecuciones de un test de una validación
+pub fn semilla_test(maestra: u64, repeticion: usize, particion: usize, ejecucion: usize) -> u64 {
+    derivar_semilla(maestra, &[FLUJO_ALGORITMO, repeticion as u64, particion as u64, ejecucion as u64])
+}
+
+// Inicializa un PRNG con una semilla
+// Escogemos Isaac64Rng, que implementa el algoritmo ISAAC-64, de Robert Jenkins
+// Véase https://docs.rs/rand/0.4.2/rand/struct.Isaac64Rng.html para una descripción del RNG
+//  y http://www.burtleburtle.net/bob/rand/isaacafa.html para información sobre el algoritmo
+pub fn rng_semilla(semilla: u64) -> Isaac64Rng {
+    Isaac64Rng::from_seed(&[semilla])
+}
+
+
+// División de los datos en entrenamiento y test para uno de los tests de una validación
+pub struct Division<'a> {
+    pub repeticion: usize,
+    pub particion: usize,
+    pub entrenamiento: Vec<&'a Dato>,
+    pub test: Vec<&'a Dato>,
+}
+
+// Divide unos datos en particiones con aproximadamente la misma distribución de clases
+// Los datos se permutan antes con el RNG recibido, por lo que las particiones
+//   solo dependen del estado del RNG y de los datos
+fn particionar<'a, Trng: Rng>(datos: &'a [Dato], n_fold: usize, rng: &mut Trng) -> Vec<Vec<&'a Dato>> {
     // Obtenemos una permutación de los datos que solo depende de la semilla utilizada
     // Nótese que el estado del PRNG se verá alterado tras esta operación a un valor
     //   que solo depende del estado anterior y del número de datos
     let mut datos_vr: Vec<&Dato> = datos.iter().collect();
     rng.shuffle(&mut datos_vr);
 
-    // Dividimos los datos en cinco particiones con aproximadamente la misma distribución de clases
+    // Dividimos los datos en particiones con aproximadamente la misma distribución de clases
     let mut distribucion = get_distribucion_clases(datos);
-    let n_fold = 5;   // Por si se quiere cambiar el número de particiones
     let mut particion: Vec<Vec<&Dato>> = vec![Vec::new(); n_fold];
-    for cl_id in 0..distribucion.len() {
+    for (cl_id, restantes) in distribucion.iter_mut().enumerate() {
         // Seleccionamos los datos de cada clase y los repartimos en la partición
         let mut datos_clase = get_datos_de_clase(&datos_vr, cl_id as i32).into_iter();
-        for i in 0..n_fold {
-            let tomados = (distribucion[cl_id] as f64 /((n_fold-i) as f64)).round() as u32;
-            let nuevos: Vec<&Dato> = datos_clase.by_ref().take(tomados as usize).collect();
-            particion[i].extend(nuevos);
-            distribucion[cl_id] -= tomados;
+        for (i, p) in particion.iter_mut().enumerate() {
+            let tomados = (*restantes as f64 /((n_fold-i) as f64)).round() as u32;
+            p.extend(datos_clase.by_ref().take(tomados as usize));
+            *restantes -= tomados;
+        }
+    }
+    particion
+}
+
+// Divide unos datos en un conjunto de entrenamiento y otro de test con aproximadamente
+//   la misma distribución de clases, de forma que el de test contenga la proporción indicada
+fn dividir_holdout<'a, Trng: Rng>(datos: &'a [Dato], proporcion_test: f64, rng: &mut Trng) -> (Vec<&'a Dato>, Vec<&'a Dato>) {
+    let mut datos_vr: Vec<&Dato> = datos.iter().collect();
+    rng.shuffle(&mut datos_vr);
+
+    let (mut entrenamiento, mut test) = (Vec::new(), Vec::new());
+    for (cl_id, n_clase) in get_distribucion_clases(datos).iter().enumerate() {
+        let tomados = (*n_clase as f64 * proporcion_test).round() as usize;
+        let datos_clase = get_datos_de_clase(&datos_vr, cl_id as i32);
+        test.extend_from_slice(&datos_clase[..tomados]);
+        entrenamiento.extend_from_slice(&datos_clase[tomados..]);
+    }
+    (entrenamiento, test)
+}
+
+// Obtiene las divisiones en entrenamiento y test de todos los tests de un esquema de validación
+// Los datos de cada repetición se permutan con un RNG cuya semilla se deriva de la maestra y de la repetición
+// Devuelve un error si el esquema no se puede aplicar a los datos: si hay más particiones que datos o si algún
+//   test se queda sin datos de entrenamiento o de test (por ejemplo, con una proporción de hold-out muy pequeña)
+pub fn divisiones(datos: &[Dato], validacion: Validacion, semilla: u64) -> Result<Vec<Division<'_>>, String> {
+    let rng = |r: usize| rng_semilla(derivar_semilla(semilla, &[FLUJO_PARTICIONES, r as u64]));
+    let mut resultado = Vec::new();
+    match validacion {
+        Validacion::KFold(n_fold, repeticiones) => {
+            if n_fold > datos.len() {
+                return Err(format!("no se pueden hacer {} particiones con {} datos", n_fold, datos.len()));
+            }
+            for r in 0..repeticiones {
+                let particion = particionar(datos, n_fold, &mut rng(r));
+                for i in 0..n_fold {
+                    let entrenamiento = particion.iter().enumerate().filter(|&(j, _)| j != i)
+                        .flat_map(|(_, p)| p.iter().cloned()).collect();
+                    resultado.push(Division { repeticion: r, particion: i, entrenamiento, test: particion[i].clone() });
+                }
+            }
+        },
+        Validacion::HoldOut(proporcion_test, repeticiones) => {
+            for r in 0..repeticiones {
+                let (entrenamiento, test) = dividir_holdout(datos, proporcion_test, &mut rng(r));
+                resultado.push(Division { repeticion: r, particion: 0, entrenamiento, test });
+            }
+        },
+        Validacion::LeaveOneOut => {
+            for i in 0..datos.len() {
+                let entrenamiento = datos.iter().enumerate().filter(|&(j, _)| j != i).map(|(_, d)| d).collect();
+                resultado.push(Division { repeticion: 0, particion: i, entrenamiento, test: vec![&datos[i]] });
+            }
+        },
+    }
+
+    // Un test sin datos no se puede puntuar, y sin entrenamiento no hay con qué clasificar
+    if let Some(d) = resultado.iter().find(|d| d.test.is_empty() || d.entrenamiento.is_empty()) {
+        let vacio = if d.test.is_empty() { "test" } else { "entrenamiento" };
+        return Err(format!("el conjunto de {} del test {}.{} queda vacío con {} datos", vacio, 1+d.repeticion, 1+d.particion, datos.len()));
+    }
+    if resultado.is_empty() {
+        return Err("no hay datos que validar".to_string());
+    }
+    Ok(resultado)
+}
+
+// Implementación de la validación de un algoritmo según el esquema indicado (5-fold cross validation por defecto)
+// Recibe una función que implemente un algoritmo que obtenga pesos de una muestra de entrenamiento y cuente
+//   las evaluaciones que hace para ello (como Aprendiz::aprender), los datos de entrenamiento y validación, la semilla maestra, el esquema de validación y el número de
+//   ejecuciones independientes del algoritmo en cada test, que usan las mismas particiones y distintas semillas
+// Devuelve los parámetros pedidos para cada uno de los tests: Tasa_clas, Tasa_red, Agregado y Tiempo,
+//   junto a los pesos aprendidos, el número de evaluaciones de la función objetivo y la semilla de cada test,
+//   o un error si el esquema de validación no se puede aplicar a los datos (ver divisiones)
+// Se asume que el algoritmo devuelve los pesos debidamente normalizados
+pub fn ffcv(algoritmo: &dyn Fn(&[Dato], &mut Isaac64Rng) -> Aprendizaje, datos: &[Dato], semilla: u64, validacion: Validacion, ejecuciones: usize) -> Result<ResultadosCV, String> {
+    let tests = divisiones(datos, validacion, semilla)?.iter().flat_map(|division| {
+        let entrenamiento = desreferenciar!(&division.entrenamiento);
+        let test = desreferenciar!(&division.test);
+        (0..ejecuciones).map(|e| evaluar_test(algoritmo, &entrenamiento, &test, division.repeticion, division.particion, e, semilla))
+                        .collect::<Vec<_>>()
+    }).collect();
+    Ok(ResultadosCV { tests })
+}
+
+// Evaluación de un algoritmo en particiones de entrenamiento y test predefinidas
+//   (por ejemplo, las que se leen con knn::leer_entrenamiento_test)
+// Recibe una función que implemente un algoritmo como la de ffcv,
+//   los pares de conjuntos de entrenamiento y test, la semilla maestra y el número de ejecuciones en cada partición
+// Devuelve los mismos parámetros que ffcv para cada una de las particiones
+pub fn evaluar_particiones(algoritmo: &dyn Fn(&[Dato], &mut Isaac64Rng) -> Aprendizaje, particiones: &[(Vec<Dato>, Vec<Dato>)], semilla: u64, ejecuciones: usize) -> ResultadosCV {
+    let tests = particiones.iter().enumerate().flat_map(|(i, (entrenamiento, test))| {
+        (0..ejecuciones).map(move |e| evaluar_test(algoritmo, entrenamiento, test, 0, i, e, semilla))
+    }).collect();
+    ResultadosCV { tests }
+}
+
+// Ejecuta un algoritmo sobre todos los datos y evalúa los pesos obtenidos en esos mismos datos con leave-one-out
+// Devuelve los mismos parámetros que ffcv en un único test
+pub fn evaluar_entrenamiento(algoritmo: &dyn Fn(&[Dato], &mut Isaac64Rng) -> Aprendizaje, datos: &[Dato], semilla: u64) -> ResultadosCV {
+    ResultadosCV { tests: vec![evaluar_test(algoritmo, datos, datos, 0, 0, 0, semilla)] }
+}
+
+// Comprueba que unos pesos se pueden usar para clasificar unos datos: que hay uno por atributo y todos son finitos
+pub fn comprobar_pesos(pesos: &[f64], datos: &[Dato]) -> Result<(), String> {
+    let n_atributos = datos.first().map_or(0, |d| d.num_atributos());
+    if pesos.len() != n_atributos {
+        Err(format!("Hay {} pesos, pero los datos tienen {} atributos", pesos.len(), n_atributos))
+    } else if pesos.iter().any(|w| !w.is_finite()) {
+        Err("Los pesos deben ser números finitos".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+// Algoritmo que ignora los datos y devuelve siempre los pesos indicados, para puntuar con ffcv, evaluar_particiones
+//   o evaluar_entrenamiento pesos diseñados a mano o aprendidos por otros programas con la misma función objetivo
+// Los pesos se usan tal cual, sin normalizar. Se comprueba antes que se pueden usar con los datos a los que se
+//   va a aplicar (ver comprobar_pesos), y si no es así se devuelve el motivo
+pub fn pesos_fijos<'a>(pesos: &'a [f64], datos: &[Dato]) -> Result<impl Fn(&[Dato], &mut Isaac64Rng) -> Aprendizaje + 'a, String> {
+    comprobar_pesos(pesos, datos)?;
+    Ok(move |_: &[Dato], _rng: &mut Isaac64Rng| Aprendizaje { pesos: pesos.to_vec(), evaluaciones: 0 })
+}
+
+// Ejecuta un algoritmo sobre un conjunto de entrenamiento, midiendo el tiempo que tarda,
+//   y evalúa los pesos obtenidos sobre un conjunto de test
+// El algoritmo usa un RNG con la semilla derivada de la maestra para la ejecución del test indicada
+pub fn evaluar_test(algoritmo: &dyn Fn(&[Dato], &mut Isaac64Rng) -> Aprendizaje, entrenamiento: &[Dato], test: &[Dato],
+                repeticion: usize, particion: usize, ejecucion: usize, semilla: u64) -> ResultadoTest {
+    let semilla_test = semilla_test(semilla, repeticion, particion, ejecucion);
+    let mut rng = rng_semilla(semilla_test);
+    cache::reiniciar();
+    let inicio = Instant::now();   // Tomamos el instante de tiempo inicial
+    let Aprendizaje { pesos, evaluaciones } = algoritmo(entrenamiento, &mut rng); // Ejecutamos el algoritmo y obtenemos los pesos
+    let tiempo_ms = inicio.elapsed().as_millis() as i64;   // y el tiempo transcurrido hasta el final
+    let aciertos_cache = cache::aciertos();
+
+    let matriz = matriz_confusion(entrenamiento, test, &pesos); // Evaluamos los pesos en el conjunto de prueba
+    let tasa_clas = matriz.tasa_acierto();
+    let tasa_red = evaluar_simplicidad(&pesos); // Computamos la simplicidad de los pesos obtenidos
+    let agregado = evaluar_agregado(matriz.medida(medida_clasificacion()), tasa_red);
+
+    ResultadoTest { repeticion, particion, ejecucion, tasa_clas, tasa_red, agregado, tiempo_ms, evaluaciones, aciertos_cache, semilla, semilla_test, pesos, matriz }
+}
+
+// Muestra por pantalla los parámetros pedidos: Tasa_clas, Tasa_red, Agregado y Tiempo (y las evaluaciones
+//   si el presupuesto tiene tiempo límite), tanto para cada uno de los tests como la media de estos en todos los tests
+// Si hay varias repeticiones, cada test se identifica como repetición.partición
+// Si hay varias ejecuciones, se muestran también la media de cada una y la dispersión de esas medias
+pub fn mostrar_resultados(resultados: &ResultadosCV, presupuesto: Presupuesto) {
+    // Con tiempo límite, el número de evaluaciones que caben en él es un resultado más
+    let limite_tiempo = presupuesto.tiempo.is_some();
+    let evaluaciones = |n: f64| if limite_tiempo { format!(". Evaluaciones: {:6.0}", n) } else { String::new() };
+    for r in &resultados.tests {
+        println!("Test {}: {:6.2}% aciertos, {:6.2}% reducción. Agregado: {:6.2}. Tiempo:{:6} ms{}",
+                      resultados.nombre_test(r), r.tasa_clas, r.tasa_red, r.agregado, r.tiempo_ms, evaluaciones(r.evaluaciones as f64));
+    }
+
+    let medias = resultados.medias();
+    let media_evaluaciones = resultados.tests.iter().map(|r| r.evaluaciones as f64).sum::<f64>() / resultados.tests.len() as f64;
+    println!("Media : {:6.2}% aciertos, {:6.2}% reducción. Agregado: {:6.2}. Tiempo:{:6} ms{}", medias.0, medias.1, medias.2, medias.3.round(), evaluaciones(media_evaluaciones));
+
+    // Con la caché activa, se muestra la proporción de evaluaciones que ha resuelto
+    if cache::modo_cache() != cache::ModoCache::Desactivada {
+        let aciertos: usize = resultados.tests.iter().map(|r| r.aciertos_cache).sum();
+        let consultas = aciertos + resultados.tests.iter().map(|r| r.evaluaciones).sum::<usize>();
+        println!("Caché : {} aciertos en {} evaluaciones ({:.2}%)", aciertos, consultas, 100.0 * aciertos as f64 / consultas.max(1) as f64);
+    }
+
+    if resultados.ejecuciones().len() > 1 {
+        for (e, m) in resultados.medias_ejecuciones() {
+            println!("Ejecución {}: {:6.2}% aciertos, {:6.2}% reducción. Agregado: {:6.2}. Tiempo:{:6} ms", e+1, m.0, m.1, m.2, m.3.round());
+        }
+        let d = resultados.dispersion();
+        println!("{:10} {:>8} {:>8} {:>8} {:>8} {:>8}", "", "Media", "Desv.", "Mejor", "Peor", "Mediana");
+        for (nombre, d) in &[("Tasa_clas", d.0), ("Tasa_red", d.1), ("Agregado", d.2), ("Tiempo", d.3)] {
+            println!("{:10} {:8.2} {:8.2} {:8.2} {:8.2} {:8.2}", nombre, d.media, d.desviacion, d.mejor, d.peor, d.mediana);
+        }
+    }
+}
+
+// Muestra por pantalla las medidas de clasificación detalladas de cada test: la matriz de confusión,
+//   el acierto balanceado, la media geométrica de las sensibilidades, kappa de Cohen y la precisión,
+//   la sensibilidad y F1 de cada clase, además de la media de las medidas globales
+pub fn mostrar_metricas(resultados: &ResultadosCV) {
+    let mut medias: Vec<f64> = vec![0.0; 3];
+
+    for r in &resultados.tests {
+        let m = &r.matriz;
+        println!("Test {}: matriz de confusión {:?}", resultados.nombre_test(r), m.0);
+        println!("    Acierto balanceado: {:6.2}%. Media geométrica: {:6.2}%. Kappa: {:6.3}",
+                      m.acierto_balanceado(), m.media_geometrica(), m.kappa());
+        for c in 0..m.num_clases() {
+            println!("    Clase {}: precisión {:6.2}%, sensibilidad {:6.2}%, F1 {:6.2}%",
+                      c, m.precision(c), m.sensibilidad(c), m.f1(c));
+        }
+        for (media, valor) in medias.iter_mut().zip([m.acierto_balanceado(), m.media_geometrica(), m.kappa()].iter()) {
+            *media += valor;
         }
     }
 
-    let mut estadisticos: Vec<(f64, f64, f64, i64)> = Vec::new();
+    for m in &mut medias {
+        *m /= resultados.tests.len() as f64;
+    }
+
+    println!("Media : acierto balanceado: {:6.2}%. Media geométrica: {:6.2}%. Kappa: {:6.3}", medias[0], medias[1], medias[2]);
+}
+
+// Validación de algoritmos multiobjetivo según el esquema indicado
+// Recibe una función que obtenga un frente de Pareto de pesos a partir de una muestra de entrenamiento,
+//   los datos de entrenamiento y validación, el presupuesto de cada test, la semilla maestra, el esquema de validación y,
+//   opcionalmente, un prefijo de ruta con el que exportar el frente obtenido en cada test a un archivo CSV
+// Muestra por pantalla el tamaño de cada frente, su hipervolumen en entrenamiento y en test y el tiempo
+pub fn ffcv_multiobjetivo(algoritmo: &ObtencionFrente, datos: &[Dato], presupuesto: Presupuesto, semilla: u64, validacion: Validacion, prefijo_frentes: Option<&str>) {
+    let divisiones = match divisiones(datos, validacion, semilla) {
+        Ok(d) => d,
+        Err(e) => { println!("No se puede validar con estos datos: {}", e); return },
+    };
+    let n_tests = divisiones.len();
+
     let mut medias: Vec<f64> = vec![0.0; 4];
 
-    for i in 0..n_fold {
-        let test = desreferenciar!(&particion[i]);
-        let mut entrenamiento: Vec<Dato> = Vec::new();
-        for j in 0..n_fold { if j != i {
-            entrenamiento.extend(desreferenciar!(&particion[j]));
-        } };
+    for (i, division) in divisiones.into_iter().enumerate() {
+        let entrenamiento = desreferenciar!(&division.entrenamiento);
+        let test = desreferenciar!(&division.test);
+
+        let mut rng = rng_semilla(semilla_test(semilla, division.repeticion, division.particion, 0));
+        cache::reiniciar();
+        let inicio = Instant::now();
+        let frente = algoritmo(&entrenamiento, &Evaluador::nuevo(presupuesto), &mut rng);
+        let tiempo_ms = inicio.elapsed().as_millis() as i64;
+
+        // Evaluamos cada solución del frente en el conjunto de prueba
+        let frente_test: Vec<SolucionMO> = frente.iter().map(|s| {
+            ((evaluar_clasificacion(&entrenamiento, &test, &s.1), evaluar_simplicidad(&s.1)), s.1.clone())
+        }).collect();
+
+        let hv_entrenamiento = hipervolumen(&frente);
+        let hv_test = hipervolumen(&frente_test);
+
+        println!("Test {}: {:3} soluciones. Hipervolumen: {:6.2} (entrenamiento), {:6.2} (test). Tiempo:{:6} ms",
+                      1+i, frente.len(), hv_entrenamiento, hv_test, tiempo_ms);
+        for (m, valor) in medias.iter_mut().zip([frente.len() as f64, hv_entrenamiento, hv_test, tiempo_ms as f64].iter()) {
+            *m += valor;
+        }
+
+        if let Some(prefijo) = prefijo_frentes {
+            let ruta = format!("{}-{}.csv", prefijo, 1+i);
+            if let Err(e) = exportar_frente(&ruta, &frente, &frente_test) {
+                println!("No se pudo escribir el archivo {}: {}", ruta, e);
+            }
+        }
+    }
+
+    for m in &mut medias {
+        *m /= n_tests as f64;
+    }
+
+    println!("Media : {:6.2} soluciones. Hipervolumen: {:6.2} (entrenamiento), {:6.2} (test). Tiempo:{:6} ms", medias[0], medias[1], medias[2], medias[3].round());
+}
+
+// Escribe un frente de Pareto en un archivo CSV, con una fila por solución
+// Cada fila contiene los objetivos en entrenamiento, la tasa de clasificación y el agregado en test y los pesos
+fn exportar_frente(ruta: &str, frente: &[SolucionMO], frente_test: &[SolucionMO]) -> Result<(), io::Error> {
+    let mut archivo = File::create(ruta)?;
+    let n_atributos = frente.first().map_or(0, |s| s.1.len());
+    let cabecera_pesos: Vec<String> = (1..=n_atributos).map(|i| format!("w{}", i)).collect();
+    writeln!(archivo, "tasa_clas_entrenamiento,tasa_red,tasa_clas_test,agregado_test{}{}",
+             if n_atributos > 0 { "," } else { "" }, cabecera_pesos.join(","))?;
+    for (s, st) in frente.iter().zip(frente_test.iter()) {
+        let pesos: Vec<String> = s.1.iter().map(|p| p.to_string()).collect();
+        writeln!(archivo, "{},{},{},{},{}", (s.0).0, (s.0).1, (st.0).0, evaluar_agregado((st.0).0, (st.0).1), pesos.join(","))?;
+    }
+    Ok(())
+}
+
 
-        let t1 = PreciseTime::now();   // Tomamos el instante de tiempo inicial
-        let pesos = algoritmo(&entrenamiento, &mut rng); // Ejecutamos el algoritmo y obtenemos los pesos
-        let t2 = PreciseTime::now();   // Ídem con el final
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let tiempo_ms = t1.to(t2).num_milliseconds();
+    // Datos de una característica con n datos de cada una de las clases indicadas
+    fn datos(n: usize, clases: i32) -> Vec<Dato> {
+        (0..clases).flat_map(|c| (0..n).map(move |i| Dato::new(&[i as f64], &[], c))).collect()
+    }
+
+    #[test]
+    fn pesos_fijos_comprueba_los_datos() {
+        let d = datos(3, 2);
+        let algoritmo = pesos_fijos(&[0.5], &d).unwrap();
+        assert_eq!(algoritmo(&d, &mut rng_semilla(1)).pesos, vec![0.5]);
+        assert!(pesos_fijos(&[0.5, 1.0], &d).is_err());
+        assert!(pesos_fijos(&[f64::NAN], &d).is_err());
+    }
+
+    #[test]
+    fn semillas_derivadas() {
+        // Primer valor de SplitMix64 con semilla 0 y hashes FNV-1a de referencia
+        assert_eq!(mezclar(0), 0xe220a8397b1dcdaf);
+        assert_eq!(hash_texto(""), 0xcbf29ce484222325);
+        assert_eq!(hash_texto("a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(hash_texto("foobar"), 0x85944171f73967e8);
+
+        assert_eq!(semilla_test(7, 1, 2, 3), semilla_test(7, 1, 2, 3));
+        // Cada posición del test da una semilla distinta, también al intercambiar sus componentes
+        let mut semillas: Vec<u64> = iproduct!(0..3, 0..5, 0..3).map(|(r, p, e)| semilla_test(7, r, p, e)).collect();
+        semillas.push(semilla_test(8, 0, 0, 0));
+        semillas.push(derivar_semilla(7, &[FLUJO_PARTICIONES, 0]));
+        let n = semillas.len();
+        semillas.sort();
+        semillas.dedup();
+        assert_eq!(semillas.len(), n);
+    }
+
+    #[test]
+    fn cada_repeticion_permuta_por_separado() {
+        let d = datos(10, 2);
+        let particiones = |v: Validacion| -> Vec<Vec<f64>> {
+            divisiones(&d, v, 3).unwrap().iter().map(|t| t.test.iter().map(|x| x[0]).collect()).collect()
+        };
+        // La primera repetición no depende de cuántas se hagan después
+        let una = particiones(Validacion::KFold(5, 1));
+        let tres = particiones(Validacion::KFold(5, 3));
+        assert_eq!(una[..], tres[..5]);
+        assert_ne!(tres[..5], tres[5..10]);
+        assert_eq!(particiones(Validacion::KFold(5, 3)), tres);
+    }
+
+    // Resultado de una ejecución de un test, con el agregado como media de las dos tasas
+    fn resultado(repeticion: usize, particion: usize, ejecucion: usize, tasa_clas: f64, tiempo_ms: i64) -> ResultadoTest {
+        ResultadoTest {
+            repeticion, particion, ejecucion, tasa_clas, tasa_red: 50.0, agregado: (tasa_clas + 50.0) / 2.0, tiempo_ms,
+            evaluaciones: 0, aciertos_cache: 0, semilla: 0, semilla_test: 0, pesos: Vec::new(), matriz: MatrizConfusion(Vec::new()),
+        }
+    }
+
+    #[test]
+    fn dispersion_de_unos_valores() {
+        let d = Dispersion::de(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0], true);
+        assert_eq!((d.media, d.mejor, d.peor, d.mediana), (5.0, 9.0, 2.0, 4.5));
+        assert!((d.desviacion - (32.0f64 / 7.0).sqrt()).abs() < 1e-12);
+        let d = Dispersion::de(&[3.0, 1.0, 2.0], false);
+        assert_eq!((d.media, d.desviacion, d.mejor, d.peor, d.mediana), (2.0, 1.0, 1.0, 3.0, 2.0));
+        // Con un solo valor no hay dispersión
+        let d = Dispersion::de(&[4.5], true);
+        assert_eq!((d.media, d.desviacion, d.mejor, d.peor, d.mediana), (4.5, 0.0, 4.5, 4.5, 4.5));
+    }
 
-        let tasa_clas = evaluar_clasificacion(&entrenamiento, &test, &pesos); // Evaluamos los pesos en el conjunto de prueba
-        let tasa_red = evaluar_simplicidad(&pesos); // Computamos la simplicidad de los pesos obtenidos
-        let agregado = evaluar_agregado(tasa_clas, tasa_red);
+    #[test]
+    fn dispersion_entre_ejecuciones() {
+        let r = ResultadosCV { tests: vec![
+            resultado(0, 0, 0, 80.0, 10), resultado(0, 1, 0, 90.0, 30),
+            resultado(0, 0, 1, 70.0, 20), resultado(0, 1, 1, 70.0, 20),
+            resultado(0, 0, 2, 100.0, 5), resultado(0, 1, 2, 90.0, 15),
+        ]};
+        assert_eq!(r.ejecuciones(), vec![0, 1, 2]);
+        let medias: Vec<(usize, f64, f64)> = r.medias_ejecuciones().iter().map(|(e, m)| (*e, m.0, m.3)).collect();
+        assert_eq!(medias, vec![(0, 85.0, 20.0), (1, 70.0, 20.0), (2, 95.0, 10.0)]);
+        let (clas, red, agregado, tiempo) = r.dispersion();
+        assert_eq!((clas.media, clas.mejor, clas.peor, clas.mediana), (250.0 / 3.0, 95.0, 70.0, 85.0));
+        assert_eq!((red.desviacion, agregado.mejor), (0.0, 72.5));
+        // En el tiempo es mejor el menor
+        assert_eq!((tiempo.mejor, tiempo.peor), (10.0, 20.0));
+        assert_eq!(r.nombre_test(&r.tests[3]), "2/2");
+    }
+
+    #[test]
+    fn nombres_de_los_tests() {
+        let una = ResultadosCV { tests: vec![resultado(0, 0, 0, 0.0, 0), resultado(0, 4, 0, 0.0, 0)] };
+        assert_eq!(una.nombre_test(&una.tests[1]), "5");
+        let varias = ResultadosCV { tests: vec![resultado(0, 0, 0, 0.0, 0), resultado(1, 2, 0, 0.0, 0)] };
+        assert_eq!(varias.nombre_test(&varias.tests[1]), "2.3");
+        assert_eq!(varias.nombre_test(&varias.tests[0]), "1.1");
+    }
 
-        println!("Test {}: {:6.2}% aciertos, {:6.2}% reducción. Agregado: {:6.2}. Tiempo:{:6} ms",
-                      1+i, tasa_clas, tasa_red, agregado, tiempo_ms);
-        estadisticos.push((tasa_clas, tasa_red, agregado, tiempo_ms));
-        for m in 0..4 {
-            medias[m] += [tasa_clas, tasa_red, agregado, tiempo_ms as f64][m];
+    #[test]
+    fn validacion_desde_texto() {
+        assert_eq!("5".parse(), Ok(Validacion::KFold(5, 1)));
+        assert_eq!("5x2".parse(), Ok(Validacion::KFold(5, 2)));
+        assert_eq!(" 10X3 ".parse(), Ok(Validacion::KFold(10, 3)));
+        assert_eq!("holdout:0.3x3".parse(), Ok(Validacion::HoldOut(0.3, 3)));
+        assert_eq!("holdout:0.25".parse(), Ok(Validacion::HoldOut(0.25, 1)));
+        assert_eq!("loo".parse(), Ok(Validacion::LeaveOneOut));
+        for invalido in &["", "1", "0", "5x0", "5xa", "cinco", "holdout:1", "holdout:0", "holdout:-0.2", "holdout:0.3x0", "holdout:"] {
+            assert!(invalido.parse::<Validacion>().is_err(), "{} no debería ser válido", invalido);
         }
     }
 
-    for m in 0..4 {
-        medias[m] /= n_fold as f64;
+    #[test]
+    fn kfold_usa_cada_dato_una_vez_como_test() {
+        let d = datos(10, 2);
+        let divisiones = divisiones(&d, Validacion::KFold(5, 2), 1).unwrap();
+        assert_eq!(divisiones.len(), 10);
+        for r in 0..2 {
+            let mut vistos: Vec<*const Dato> = divisiones.iter().filter(|x| x.repeticion == r)
+                .flat_map(|x| x.test.iter().map(|t| *t as *const Dato)).collect();
+            vistos.sort();
+            vistos.dedup();
+            assert_eq!(vistos.len(), d.len());
+        }
+        // Cada partición conserva la distribución de clases
+        assert!(divisiones.iter().all(|x| x.test.len() == 4 && x.entrenamiento.len() == 16
+                                          && x.test.iter().filter(|t| t.id_categoria() == 0).count() == 2));
     }
 
-    println!("Media : {:6.2}% aciertos, {:6.2}% reducción. Agregado: {:6.2}. Tiempo:{:6} ms", medias[0], medias[1], medias[2], medias[3].round());
+    #[test]
+    fn divisiones_rechaza_tests_vacios() {
+        let d = datos(2, 2);
+        assert!(divisiones(&d, Validacion::KFold(5, 1), 1).is_err());
+        assert!(divisiones(&d, Validacion::KFold(2, 1), 1).is_ok());
+        // Al repartir cada clase por separado, con un dato de cada clase por cada dos particiones quedan particiones vacías
+        assert!(divisiones(&d, Validacion::KFold(4, 1), 1).is_err());
+        assert!(divisiones(&d, Validacion::HoldOut(0.1, 1), 1).is_err());
+        assert!(divisiones(&d, Validacion::HoldOut(0.5, 1), 1).is_ok());
+        assert!(divisiones(&d, Validacion::HoldOut(0.9, 1), 1).is_err());
+        assert!(divisiones(&datos(1, 1), Validacion::LeaveOneOut, 1).is_err());
+        assert_eq!(divisiones(&d, Validacion::LeaveOneOut, 1).unwrap().len(), 4);
+        assert!(divisiones(&[], Validacion::LeaveOneOut, 1).is_err());
+    }
+
+    #[test]
+    fn medidas_de_la_matriz_de_confusion() {
+        let m = MatrizConfusion(vec![vec![5, 0], vec![2, 3]]);
+        let cerca = |a: f64, b: f64| (a - b).abs() < 1e-9;
+        assert_eq!(m.total(), 10);
+        assert!(cerca(m.tasa_acierto(), 80.0));
+        assert!(cerca(m.precision(0), 500.0 / 7.0));
+        assert!(cerca(m.precision(1), 100.0));
+        assert!(cerca(m.sensibilidad(0), 100.0));
+        assert!(cerca(m.sensibilidad(1), 60.0));
+        assert!(cerca(m.f1(1), 75.0));
+        assert!(cerca(m.acierto_balanceado(), 80.0));
+        assert!(cerca(m.media_geometrica(), 100.0 * 0.6f64.sqrt()));
+        assert!(cerca(m.kappa(), 0.6));
+        assert!(cerca(m.medida(MedidaClasificacion::AciertoBalanceado), 80.0));
+    }
+
+    #[test]
+    fn matriz_de_confusion_con_clases_ausentes() {
+        // La clase 1 no aparece en los datos: no cuenta en las medias y su precisión es 0
+        let m = MatrizConfusion(vec![vec![2, 2, 0], vec![0, 0, 0], vec![0, 0, 4]]);
+        assert_eq!(m.precision(1), 0.0);
+        assert_eq!(m.sensibilidad(1), 0.0);
+        assert!((m.acierto_balanceado() - 75.0).abs() < 1e-9);
+        // Si no se acierta ningún dato de una clase, la media geométrica es nula
+        assert_eq!(MatrizConfusion(vec![vec![3, 0], vec![2, 0]]).media_geometrica(), 0.0);
+        // Con una sola clase la concordancia esperada es total
+        assert_eq!(MatrizConfusion(vec![vec![4]]).kappa(), 0.0);
+    }
+
+    #[test]
+    fn matriz_de_confusion_con_leave_one_out() {
+        // El dato 0.45 está más cerca de la clase 0; los demás, de la suya
+        let d: Vec<Dato> = [(0.0, 0), (0.1, 0), (0.9, 1), (1.0, 1), (0.45, 1)].iter().map(|&(x, c)| Dato::new(&[x], &[], c)).collect();
+        let m = matriz_confusion(&d, &d, &[1.0]);
+        assert_eq!(m.0, vec![vec![2, 0], vec![1, 2]]);
+        assert!((evaluar_clasificacion(&d, &d, &[1.0]) - 80.0).abs() < 1e-9);
+        let medidas: Vec<MedidaClasificacion> = ["acierto", "Balanceado", " gmedia "].iter().map(|m| m.parse().unwrap()).collect();
+        assert_eq!(medidas, vec![MedidaClasificacion::Acierto, MedidaClasificacion::AciertoBalanceado, MedidaClasificacion::MediaGeometrica]);
+        assert!("kappa".parse::<MedidaClasificacion>().is_err());
+    }
+
+    #[test]
+    fn hipervolumen_de_un_frente() {
+        // Un solo punto domina el rectángulo que forma con el origen
+        assert!((hipervolumen(&[((50.0, 50.0), vec![])]) - 25.0).abs() < 1e-12);
+        // Dos puntos no dominados: 80x20 más 40x(60-20); el dominado no añade área
+        let frente = vec![((80.0, 20.0), vec![]), ((40.0, 60.0), vec![]), ((30.0, 10.0), vec![])];
+        assert!((hipervolumen(&frente) - (80.0*20.0 + 40.0*40.0) / 100.0).abs() < 1e-12);
+        assert_eq!(hipervolumen(&[]), 0.0);
+    }
 }
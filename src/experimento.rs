@@ -0,0 +1,268 @@
+// Archivos de experimento
+// Describen en JSON los conjuntos de datos, los algoritmos con sus parámetros, la semilla y el esquema
+//   de validación de un experimento, de forma que los estudios de parámetros no requieran recompilar.
+// Ejemplo:
+//   {
+//     "datos": ["instances/parkinsons.arff", "particiones/iris"],
+//     "algoritmos": ["relief", {"algoritmo": "agg_blx", "nombre": "AGG-BLX (60)", "parametros": {"tamano_generacional": 60}},
+//                    {"algoritmo": "relief", "nombre": "RELIEF + BL", "mejoras": ["busqueda_local_desde"]}],
+//     "parametros": {"max_evaluaciones": 10000},
+//     "semilla": 42,
+//     "validacion": "5x2",
+//     "ejecuciones": 10,
+//     "hilos": 4,
+//     "medida": "acierto",
+//     "metricas": false,
+//     "salida": "resultados.jsonl",
+//     "traza": "convergencia.csv",
+//     "control": "lote.jsonl",
+//     "control_poblacion": false,
+//     "limite_tiempo": "30s",
+//     "solo_tiempo": false,
+//     "cache": "no"
+//   }
+// Solo "algoritmos" es obligatorio. Los datos pueden ser archivos o directorios de particiones predefinidas,
+//   y los parámetros de cada algoritmo se añaden a los comunes, que a su vez cambian los valores por defecto
+// "limite_tiempo" es el tiempo máximo de cada ejecución, como en --time-limit, y con "solo_tiempo" no hay máximo de evaluaciones
+// "cache" es el modo de la caché de evaluaciones, como en --cache
+// Las "mejoras" de un algoritmo se aplican en orden a la solución que obtiene (ver aprendiz::Tuberia), y
+//   los algoritmos se describen con el mismo formato en list-algorithms --json
+
+use aprendiz::{Aprendiz, Tuberia};
+use cache;
+use cache::ModoCache;
+use catalogo;
+use cli;
+use cli::OpcionesValidacion;
+use evaluacion_pesos;
+use evaluacion_pesos::{MedidaClasificacion, Validacion};
+use json::Valor;
+use knn;
+use parametros;
+use parametros::Parametros;
+use planificador;
+use planificador::Datos;
+use presupuesto::Presupuesto;
+use resultados::Salida;
+use std::path::Path;
+
+
+// Experimento completo leído de un archivo
+pub struct Experimento {
+    pub datos: Vec<String>,
+    pub algoritmos: Vec<Box<dyn Aprendiz>>,
+    pub semilla: u64,
+    pub validacion: Validacion,
+    pub ejecuciones: usize,
+    pub hilos: usize,          // Hilos entre los que se reparten los tests (0 para usar todos los núcleos)
+    pub medida: MedidaClasificacion,
+    pub metricas: bool,
+    pub salida: Option<String>,
+    pub traza: Option<String>,     // Archivo CSV en el que se escriben las trazas de convergencia
+    pub control: Option<String>,   // Punto de control con el que se reanuda el experimento si se interrumpe
+    pub control_poblacion: bool,   // Si los algoritmos genéticos guardan también su población en el punto de control
+    pub presupuesto: Presupuesto,  // Tiempo límite de cada ejecución y, si solo cuenta el tiempo, máximo de evaluaciones
+    pub cache: ModoCache,          // Modo de la caché de evaluaciones
+}
+
+
+// Lee un campo de texto opcional
+fn texto<'a>(valor: &'a Valor, nombre: &str) -> Result<Option<&'a str>, String> {
+    match valor.campo(nombre) {
+        Some(v) => v.como_texto().map(Some).ok_or_else(|| format!("\"{}\" debe ser un texto", nombre)),
+        None => Ok(None),
+    }
+}
+
+// Lee un algoritmo de la lista de algoritmos: su clave en el catálogo o un objeto con la clave,
+//   y opcionalmente el nombre con el que se muestra, sus parámetros y las mejoras que se le aplican
+fn leer_algoritmo(valor: &Valor, comunes: &Parametros) -> Result<Box<dyn Aprendiz>, String> {
+    let clave = match valor {
+        Valor::Texto(clave) => clave.as_str(),
+        Valor::Objeto(_) => texto(valor, "algoritmo")?.ok_or("Falta \"algoritmo\" en un algoritmo del experimento")?,
+        _ => return Err("Cada algoritmo debe ser su nombre o un objeto".to_string()),
+    };
+    let entrada = catalogo::buscar(clave).ok_or_else(|| format!("Algoritmo desconocido: {}", clave))?;
+
+    let mut parametros = *comunes;
+    if let Some(p) = valor.campo("parametros") {
+        parametros.actualizar(p).map_err(|e| format!("{}: {}", clave, e))?;
+    }
+    let mejoras = match valor.campo("mejoras") {
+        Some(m) => m.como_lista().ok_or_else(|| format!("{}: \"mejoras\" debe ser una lista", clave))?.iter().map(|m| {
+            let nombre = m.como_texto().ok_or_else(|| format!("{}: cada mejora debe ser un texto", clave))?;
+            catalogo::buscar_mejora(nombre).ok_or_else(|| format!("Mejora desconocida: {}", nombre))
+        }).collect::<Result<Vec<_>, String>>()?,
+        None => Vec::new(),
+    };
+    let nombre = texto(valor, "nombre")?;
+
+    if mejoras.is_empty() {
+        let funcion = entrada.aprendiz().con_nombre(nombre.unwrap_or(entrada.nombre)).con_parametros(parametros);
+        return Ok(Box::new(funcion));
+    }
+    let mut tuberia = Tuberia::desde(entrada.aprendiz()).parametros(parametros);
+    for m in mejoras {
+        tuberia = tuberia.mejora(m.clave, m.funcion);
+    }
+    if let Some(n) = nombre {
+        tuberia = tuberia.nombre(n);
+    }
+    Ok(Box::new(tuberia.construir()))
+}
+
+impl Experimento {
+    // Interpreta un experimento a partir de su representación en JSON
+    pub fn de_json(valor: &Valor) -> Result<Experimento, String> {
+        let mut comunes = Parametros::default();
+        if let Some(p) = valor.campo("parametros") {
+            comunes.actualizar(p)?;
+        }
+
+        let algoritmos = valor.campo("algoritmos").and_then(|a| a.como_lista())
+            .ok_or("El experimento debe tener una lista de \"algoritmos\"")?
+            .iter().map(|a| leer_algoritmo(a, &comunes)).collect::<Result<Vec<_>, String>>()?;
+
+        let datos = match valor.campo("datos") {
+            Some(d) => d.como_lista().ok_or("\"datos\" debe ser una lista de archivos")?
+                        .iter().map(|d| d.como_texto().map(|s| s.to_string()).ok_or("Cada conjunto de datos debe ser un texto"))
+                        .collect::<Result<Vec<_>, _>>()?,
+            None => cli::INSTANCIAS.iter().map(|s| s.to_string()).collect(),
+        };
+
+        let validacion = match texto(valor, "validacion")? {
+            Some(v) => v.parse().map_err(|e| format!("Esquema de validación no válido: {}", e))?,
+            None => Validacion::default(),
+        };
+        let ejecuciones = match valor.campo("ejecuciones") {
+            Some(n) => n.como_numero().filter(|n| *n >= 1.0 && n.fract() == 0.0).map(|n| n as usize)
+                        .ok_or("\"ejecuciones\" debe ser un entero positivo")?,
+            None => 1,
+        };
+        let hilos = match valor.campo("hilos") {
+            Some(n) => n.como_numero().filter(|n| *n >= 0.0 && n.fract() == 0.0).map(|n| n as usize)
+                        .ok_or("\"hilos\" debe ser un entero no negativo")?,
+            None => 1,
+        };
+        let medida = match texto(valor, "medida")? {
+            Some(m) => m.parse()?,
+            None => MedidaClasificacion::Acierto,
+        };
+        // La semilla puede ser un número entero o un texto, como en el argumento --seed
+        let semilla = match valor.campo("semilla") {
+            Some(Valor::Texto(t)) => cli::interpretar_semilla(t),
+            Some(v) => v.como_numero().filter(|x| *x >= 0.0 && x.fract() == 0.0 && *x < 2f64.powi(64)).map(|x| x as u64)
+                        .ok_or("\"semilla\" debe ser un entero no negativo o un texto")?,
+            None => cli::interpretar_semilla(cli::SEMILLA_POR_DEFECTO),
+        };
+        let metricas = match valor.campo("metricas") {
+            Some(m) => m.como_booleano().ok_or("\"metricas\" debe ser true o false")?,
+            None => false,
+        };
+        let control_poblacion = match valor.campo("control_poblacion") {
+            Some(m) => m.como_booleano().ok_or("\"control_poblacion\" debe ser true o false")?,
+            None => false,
+        };
+
+        let mut presupuesto = Presupuesto::default();
+        if let Some(t) = texto(valor, "limite_tiempo")? {
+            presupuesto.tiempo = Some(cli::interpretar_duracion(t)?);
+        }
+        let solo_tiempo = match valor.campo("solo_tiempo") {
+            Some(m) => m.como_booleano().ok_or("\"solo_tiempo\" debe ser true o false")?,
+            None => false,
+        };
+        if solo_tiempo {
+            if presupuesto.tiempo.is_none() {
+                return Err("\"solo_tiempo\" requiere \"limite_tiempo\"".to_string());
+            }
+            presupuesto.evaluaciones = Some(usize::MAX);
+        }
+        let cache = match texto(valor, "cache")? {
+            Some(m) => m.parse()?,
+            None => ModoCache::Desactivada,
+        };
+
+        Ok(Experimento {
+            datos,
+            algoritmos,
+            semilla,
+            validacion,
+            ejecuciones,
+            hilos,
+            medida,
+            metricas,
+            salida: texto(valor, "salida")?.map(|s| s.to_string()),
+            traza: texto(valor, "traza")?.map(|s| s.to_string()),
+            control: texto(valor, "control")?.map(|s| s.to_string()),
+            control_poblacion,
+            presupuesto,
+            cache,
+        })
+    }
+
+    // Lee un experimento de un archivo JSON
+    pub fn leer(ruta: &str) -> Result<Experimento, String> {
+        Experimento::de_json(&parametros::leer_json(ruta)?).map_err(|e| format!("{}: {}", ruta, e))
+    }
+
+    // Ejecuta el experimento: valida cada algoritmo con sus parámetros en cada conjunto de datos
+    pub fn ejecutar(&self) {
+        evaluacion_pesos::fijar_medida_clasificacion(self.medida);
+        cache::fijar_modo_cache(self.cache);
+        let salida = self.salida.as_ref().map(|ruta| Salida::crear(ruta).unwrap_or_else(|e| {
+            cli::salir(&format!("No se pudo crear el archivo {}: {}", ruta, e))
+        }));
+        let mut opciones = OpcionesValidacion {
+            semilla: self.semilla,
+            repetir: None,
+            ejecuciones: self.ejecuciones,
+            hilos: self.hilos,
+            validacion: self.validacion,
+            presupuesto: self.presupuesto,
+            metricas: self.metricas,
+            salida,
+            control: None,
+            traza: None,
+        };
+        let mut traza = self.traza.as_ref().map(|ruta| cli::crear_traza(ruta));
+        let mut control = self.control.as_ref().map(|ruta| cli::abrir_control(ruta, self.control_poblacion, self.validacion, self.presupuesto));
+
+        let mut conjuntos = Vec::new();
+        for conjunto in &self.datos {
+            // Los directorios contienen particiones predefinidas, que se usan en lugar de la validación
+            let datos = if Path::new(conjunto).is_dir() {
+                knn::leer_directorio_particiones(conjunto).map(Datos::Particiones)
+            } else {
+                knn::leer_archivo(conjunto).map(Datos::Archivo)
+            };
+            match datos {
+                Ok(d) => conjuntos.push((conjunto.as_str(), d)),
+                Err(e) => println!("No se pudieron leer los datos de {}: {}", conjunto, e),
+            }
+        }
+
+        let algoritmos: Vec<&dyn Aprendiz> = self.algoritmos.iter().map(|a| a.as_ref()).collect();
+        planificador::ejecutar(&algoritmos, &conjuntos, &opciones.planificacion(), control.as_mut(), traza.as_mut(), |c, a, resultados| {
+            println!("\n{} sobre los datos en {}...", self.algoritmos[a].nombre(), conjuntos[c].0);
+            cli::mostrar(self.algoritmos[a].nombre(), conjuntos[c].0, &resultados, &mut opciones);
+        });
+    }
+}
+
+
+// Experimento de ejemplo con todos los parámetros a su valor por defecto, para usarlo como plantilla
+pub fn plantilla() -> Valor {
+    let datos: Vec<&str> = cli::INSTANCIAS.to_vec();
+    objeto_json!{
+        "datos" => datos,
+        "algoritmos" => vec!["relief", "busqueda_local"],
+        "parametros" => Parametros::default().a_json(),
+        "semilla" => cli::SEMILLA_POR_DEFECTO,
+        "validacion" => "5",
+        "ejecuciones" => 1usize,
+        "hilos" => 1usize,
+        "medida" => "acierto",
+        "metricas" => false
+    }
+}
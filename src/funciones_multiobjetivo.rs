@@ -0,0 +1,257 @@
+use knn::Dato;
+use evaluacion_pesos::{evaluar_objetivos, SolucionMO};
+use parametros::parametros;
+use presupuesto::Evaluador;
+use problema::{Cruce, Vecino};
+use ordered_float::OrderedFloat;
+use rand::{Isaac64Rng, Rng};
+
+use funciones_practica1::*;               // Recuperamos el operador de vecino y las soluciones aleatorias
+use funciones_practica2::{blx_03, ca};    // Recuperamos los operadores de cruce
+
+
+
+// Algunas funciones auxiliares
+// El tamaño de la población, la tasa de cruce y el máximo de evaluaciones son parámetros (ver parametros.rs)
+
+type Generador<'a, Trng> = dyn Fn(&[Dato], &Evaluador, &mut Trng) -> Vec<f64> + 'a;  // Genera una solución inicial a partir de la muestra
+
+
+// Devuelve si unas puntuaciones dominan a otras, es decir, si no son
+//   peores en ningún objetivo y son mejores en alguno de ellos
+fn domina(a: (f64, f64), b: (f64, f64)) -> bool {
+    a.0 >= b.0 && a.1 >= b.1 && (a.0 > b.0 || a.1 > b.1)
+}
+
+// Ordenación no dominada rápida descrita por Deb et al. (2002)
+// Devuelve los frentes como vectores de índices de la población: el primero contiene las soluciones
+//   no dominadas, el segundo las que solo son dominadas por las del primero, y así sucesivamente
+fn ordenacion_no_dominada(poblacion: &[SolucionMO]) -> Vec<Vec<usize>> {
+    let n = poblacion.len();
+    let mut dominadas: Vec<Vec<usize>> = vec![Vec::new(); n]; // Soluciones dominadas por cada solución
+    let mut n_dominantes: Vec<usize> = vec![0; n];            // Número de soluciones que dominan a cada solución
+    for i in 0..n {
+        for j in (i+1)..n {
+            if domina(poblacion[i].0, poblacion[j].0) {
+                dominadas[i].push(j);
+                n_dominantes[j] += 1;
+            } else if domina(poblacion[j].0, poblacion[i].0) {
+                dominadas[j].push(i);
+                n_dominantes[i] += 1;
+            }
+        }
+    }
+
+    let mut frentes = vec![];
+    let mut actual: Vec<usize> = (0..n).filter(|i| n_dominantes[*i] == 0).collect();
+    while !actual.is_empty() {
+        // Las soluciones del siguiente frente son las que solo estaban dominadas por las del actual
+        let mut siguiente = vec![];
+        for i in &actual {
+            for j in &dominadas[*i] {
+                n_dominantes[*j] -= 1;
+                if n_dominantes[*j] == 0 {
+                    siguiente.push(*j);
+                }
+            }
+        }
+        frentes.push(actual);
+        actual = siguiente;
+    }
+    frentes
+}
+
+// Calcula la distancia de hacinamiento de cada una de las soluciones de un frente
+// Las soluciones extremas en algún objetivo reciben distancia infinita para que se conserven siempre
+fn distancia_hacinamiento(poblacion: &[SolucionMO], frente: &[usize]) -> Vec<f64> {
+    let mut distancia = vec![0.0; frente.len()];
+    let objetivos: [fn(&SolucionMO) -> f64; 2] = [|s| (s.0).0, |s| (s.0).1];
+    for objetivo in &objetivos {
+        let valor = |k: usize| objetivo(&poblacion[frente[k]]);
+        let mut orden: Vec<usize> = (0..frente.len()).collect();
+        orden.sort_by_key(|k| OrderedFloat(valor(*k)));
+
+        let (primero, ultimo) = (orden[0], orden[orden.len()-1]);
+        let rango = valor(ultimo) - valor(primero);
+        distancia[primero] = f64::INFINITY;
+        distancia[ultimo] = f64::INFINITY;
+        if rango > 0.0 {
+            for v in orden.windows(3) {
+                distancia[v[1]] += (valor(v[2]) - valor(v[0])) / rango;
+            }
+        }
+    }
+    distancia
+}
+
+// Reduce una población al tamaño indicado quedándose con los mejores frentes y, dentro del
+//   último frente que cabe parcialmente, con las soluciones de mayor distancia de hacinamiento
+// Devuelve la nueva población junto al rango y la distancia de hacinamiento de cada solución
+fn reemplazo(poblacion: Vec<SolucionMO>, tamano: usize) -> (Vec<SolucionMO>, Vec<(usize, f64)>) {
+    let frentes = ordenacion_no_dominada(&poblacion);
+    let mut seleccionados: Vec<(usize, (usize, f64))> = Vec::with_capacity(tamano);
+    for (rango, frente) in frentes.iter().enumerate() {
+        if seleccionados.len() == tamano { break; }
+        let distancias = distancia_hacinamiento(&poblacion, frente);
+        let mut candidatos: Vec<(usize, (usize, f64))> = frente.iter().zip(distancias)
+            .map(|(i, d)| (*i, (rango, d))).collect();
+        if seleccionados.len() + candidatos.len() > tamano {
+            // El frente no cabe completo: preferimos las soluciones más aisladas
+            candidatos.sort_by_key(|c| OrderedFloat(-(c.1).1));
+            candidatos.truncate(tamano - seleccionados.len());
+        }
+        seleccionados.extend(candidatos);
+    }
+
+    let mut poblacion: Vec<Option<SolucionMO>> = poblacion.into_iter().map(Some).collect();
+    seleccionados.into_iter().map(|(i, clasificacion)| (poblacion[i].take().unwrap(), clasificacion)).unzip()
+}
+
+// Selecciona por torneo binario una solución de la población según su rango y, en caso
+//   de empate, según su distancia de hacinamiento. Devuelve su índice
+fn torneo<Trng: Rng>(clasificacion: &[(usize, f64)], rng: &mut Trng) -> usize {
+    let a = rng.gen_range(0, clasificacion.len());
+    let b = rng.gen_range(0, clasificacion.len());
+    let (ca, cb) = (clasificacion[a], clasificacion[b]);
+    if ca.0 < cb.0 || (ca.0 == cb.0 && ca.1 >= cb.1) { a } else { b }
+}
+
+
+
+// Implementaciones de los algoritmos multiobjetivo
+// Reciben un conjunto de entrenamiento y un generador de números aleatorios
+// Devuelven el frente de soluciones no dominadas, con sus puntuaciones, ordenado
+//   de mayor a menor tasa de clasificación
+
+// Tipo de dato de los algoritmos multiobjetivo, como aprendiz::Algoritmo en los de un solo objetivo
+pub type AlgoritmoMO = fn(&[Dato], &Evaluador, &mut Isaac64Rng) -> Vec<SolucionMO>;
+
+
+// Algoritmo NSGA-II (Deb et al., 2002) general
+// Maximiza por separado la tasa de clasificación y la de reducción
+// Recibe la muestra, una función generadora de soluciones iniciales, un operador de cruce,
+//   un operador de generación de un vecino para efectuar mutaciones, el evaluador de las soluciones y un RNG
+// Cada gen de los hijos se muta con probabilidad 1/n, siendo n el número de características
+pub fn nsga2_general<Trng: Rng>(entrenamiento: &[Dato], gen_iniciales: &Generador<Trng>, cruce: &Cruce<Trng>, vecino: &Vecino<Trng>, evaluador: &Evaluador, rng: &mut Trng) -> Vec<SolucionMO> {
+    let param = parametros();
+    let n_caracteristicas = entrenamiento[0].num_atributos();
+    let tasa_mutacion = 1.0 / n_caracteristicas as f64;
+
+    // Rellenamos la población con elementos seleccionados por gen_iniciales (probablemente aleatorios)
+    let poblacion_inicial: Vec<SolucionMO> = (0..param.tamano_nsga2).map(|_| {
+        let cromosoma = gen_iniciales(entrenamiento, evaluador, rng);
+        (evaluar_objetivos(evaluador, entrenamiento, &cromosoma), cromosoma)
+    }).collect();
+    let (mut poblacion, mut clasificacion) = reemplazo(poblacion_inicial, param.tamano_nsga2);
+
+    while !evaluador.agotado() {
+        // Generamos tantos hijos como soluciones hay en la población (o los que permita el presupuesto)
+        let mut hijos: Vec<SolucionMO> = Vec::with_capacity(param.tamano_nsga2);
+        while hijos.len() < param.tamano_nsga2 && !evaluador.agotado() {
+            let padre1 = poblacion[torneo(&clasificacion, rng)].1.clone();
+            let padre2 = poblacion[torneo(&clasificacion, rng)].1.clone();
+            let descendientes = if rng.gen::<f64>() < param.tasa_cruce_nsga2 {
+                cruce(&padre1, &padre2, rng)
+            } else {
+                vec![padre1, padre2]
+            };
+
+            for d in descendientes {
+                if hijos.len() == param.tamano_nsga2 || evaluador.agotado() { break; }
+                let mut d_mutado = d;
+                for gen in 0..n_caracteristicas {
+                    if rng.gen::<f64>() < tasa_mutacion {
+                        d_mutado = vecino(&d_mutado, gen, rng);
+                    }
+                }
+                hijos.push((evaluar_objetivos(evaluador, entrenamiento, &d_mutado), d_mutado));
+            }
+        }
+
+        // Padres e hijos compiten por sobrevivir a la siguiente generación
+        poblacion.extend(hijos);
+        let resultado = reemplazo(poblacion, param.tamano_nsga2);
+        poblacion = resultado.0;
+        clasificacion = resultado.1;
+    }
+
+    // Devolvemos el primer frente quedándonos con una sola solución por cada punto del espacio de objetivos
+    let mut frente: Vec<SolucionMO> = Vec::new();
+    for i in &ordenacion_no_dominada(&poblacion)[0] {
+        if !frente.iter().any(|s| s.0 == poblacion[*i].0) {
+            frente.push(poblacion[*i].clone());
+        }
+    }
+    frente.sort_by_key(|s| (OrderedFloat(-(s.0).0), OrderedFloat(-(s.0).1)));
+    frente
+}
+
+// NSGA-II con cruce BLX-0.3 y el operador de vecino de la práctica 1 como mutación
+pub fn nsga2_blx<Trng: Rng>(entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Trng) -> Vec<SolucionMO> {
+    nsga2_general(entrenamiento, &vector_au, &blx_03, &vecino_bl, evaluador, rng)
+}
+
+// NSGA-II con cruce aritmético y el operador de vecino de la práctica 1 como mutación
+pub fn nsga2_ca<Trng: Rng>(entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Trng) -> Vec<SolucionMO> {
+    nsga2_general(entrenamiento, &vector_au, &ca, &vecino_bl, evaluador, rng)
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Población con las puntuaciones indicadas y pesos vacíos
+    fn poblacion(puntos: &[(f64, f64)]) -> Vec<SolucionMO> {
+        puntos.iter().map(|p| (*p, Vec::new())).collect()
+    }
+
+    #[test]
+    fn domina_exige_mejorar_algun_objetivo() {
+        assert!(domina((2.0, 1.0), (1.0, 1.0)));
+        assert!(!domina((1.0, 1.0), (1.0, 1.0)));
+        assert!(!domina((2.0, 0.0), (0.0, 2.0)));
+        assert!(!domina((0.0, 2.0), (2.0, 0.0)));
+    }
+
+    #[test]
+    fn ordenacion_no_dominada_separa_los_frentes() {
+        let p = poblacion(&[(1.0, 1.0), (3.0, 3.0), (2.0, 2.0), (3.0, 1.0), (1.0, 3.0)]);
+        let mut frentes = ordenacion_no_dominada(&p);
+        for f in &mut frentes {
+            f.sort();
+        }
+        assert_eq!(frentes, vec![vec![1], vec![2, 3, 4], vec![0]]);
+    }
+
+    #[test]
+    fn distancia_hacinamiento_de_un_frente() {
+        let p = poblacion(&[(0.0, 3.0), (1.0, 2.0), (2.0, 1.0), (3.0, 0.0)]);
+        let d = distancia_hacinamiento(&p, &[0, 1, 2, 3]);
+        assert_eq!(d[0], f64::INFINITY);
+        assert_eq!(d[3], f64::INFINITY);
+        assert!((d[1] - 4.0/3.0).abs() < 1e-12);
+        assert!((d[2] - 4.0/3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn reemplazo_conserva_los_mejores_frentes_y_los_extremos() {
+        let p = poblacion(&[(0.0, 3.0), (1.0, 2.1), (1.1, 2.0), (3.0, 0.0), (0.5, 0.5)]);
+        let (nueva, clasificacion) = reemplazo(p, 3);
+        let puntos: Vec<(f64, f64)> = nueva.iter().map(|s| s.0).collect();
+        assert_eq!(puntos.len(), 3);
+        assert!(puntos.contains(&(0.0, 3.0)));
+        assert!(puntos.contains(&(3.0, 0.0)));
+        assert!(!puntos.contains(&(0.5, 0.5)));
+        assert!(clasificacion.iter().all(|c| c.0 == 0));
+    }
+
+    #[test]
+    fn torneo_prefiere_el_menor_rango() {
+        let mut rng = ::evaluacion_pesos::rng_semilla(1);
+        let clasificacion = [(0, 1.0), (1, f64::INFINITY)];
+        let elegidos: Vec<usize> = (0..100).map(|_| torneo(&clasificacion, &mut rng)).collect();
+        assert!(elegidos.iter().filter(|i| **i == 0).count() > 50);
+    }
+}
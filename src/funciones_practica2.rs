@@ -1,28 +1,58 @@
 use std;    // Usaremos BTreeMap para ordenar los cromosomas en el procedimiento generacional
 use knn::Dato;
 use evaluacion_pesos::evaluar;
+use presupuesto::Evaluador;
+use problema::{Cruce, GenInicial, Problema, PonderacionCaracteristicas, Vecino};
+use parametros::parametros;
+use punto_control;
+use punto_control::EstadoPoblacion;
+use traza;
+use traza::Diversidad;
 use ordered_float::OrderedFloat;
 use rand::Rng;
 use std::cell::RefCell;
 
 use funciones_practica1::*;    // Recuperamos la búsqueda local y las funciones de combinación de algoritmos
 
-type ArbolBinario = std::collections::BTreeMap<(OrderedFloat<f64>, usize), Vec<f64>>;
+type Clave = (OrderedFloat<f64>, usize);  // Evaluación cambiada de signo e identificador de un cromosoma, que lo ordenan en la población
+type ArbolBinario = std::collections::BTreeMap<Clave, Vec<f64>>;
+type OperadorMemetico<'a, Trng> = dyn Fn(&dyn Problema, &mut ArbolBinario, &Evaluador, &mut Trng) + 'a;  // Aplica la búsqueda local a la población
 
 
 
 // Algunas constantes y funciones auxiliares
 
 
-// Operador de cruce BLX-alfa con alfa = 0.3
+// Calcula la diversidad de una población de un problema, dada por la evaluación y la solución de cada cromosoma,
+//   e informa de su estado a la traza de convergencia
+// Solo se calcula si hay traza o si la población se puede reiniciar (ver debe_reiniciar)
+fn diversidad_poblacion<'a, I: Iterator<Item = (f64, &'a [f64])>>(problema: &dyn Problema, evaluador: &Evaluador, poblacion: I) -> Option<Diversidad> {
+    if !traza::activa() && parametros().diversidad_reinicio <= 0.0 { return None }
+    let poblacion: Vec<(f64, &[f64])> = poblacion.collect();
+    let media = poblacion.iter().map(|c| c.0).sum::<f64>() / poblacion.len() as f64;
+    let mejor = poblacion.iter().map(|c| c.0).fold(f64::NEG_INFINITY, f64::max);
+    let diversidad = traza::diversidad(problema, &poblacion);
+    traza::registrar(evaluador, media, mejor, None, Some(diversidad));
+    Some(diversidad)
+}
+
+// Indica si una población ha perdido tanta diversidad que se considera que ha convergido prematuramente
+//   y debe reiniciarse: si la distancia media entre sus cromosomas es menor que el parámetro diversidad_reinicio
+fn debe_reiniciar(diversidad: Option<Diversidad>) -> bool {
+    diversidad.is_some_and(|d| d.distancia_media < parametros().diversidad_reinicio)
+}
+
+
+// Operador de cruce BLX-alfa con alfa = 0.3 (el parámetro alfa_blx)
 // Recibe los padres y un RNG y devuelve dos hijos
-fn blx_03<Trng: Rng>(padre1: &[f64], padre2: &[f64], rng: &mut Trng) -> Vec<Vec<f64>> {
+pub fn blx_03<Trng: Rng>(padre1: &[f64], padre2: &[f64], rng: &mut Trng) -> Vec<Vec<f64>> {
+    let alfa = parametros().alfa_blx;
     let mut hijos = Vec::new();
     for _i in 0..2 {
-        let mut c_hijo = padre1.iter().zip(padre2.iter()).map(|(x, y)| {
+        let mut c_hijo: Vec<f64> = padre1.iter().zip(padre2.iter()).map(|(x, y)| {
             let x_menor = *x < *y;
             let (cmin, cmax) = if x_menor { (*x, *y) } else { (*y, *x) };
-            let ai = (cmax - cmin)*0.3;
+            let ai = (cmax - cmin)*alfa;
             let (a, b) = (cmin - ai, cmax + ai);
             let valor = if a == b {
                 cmin
@@ -30,7 +60,7 @@ fn blx_03<Trng: Rng>(padre1: &[f64], padre2: &[f64], rng: &mut Trng) -> Vec<Vec<
                 rng.gen_range(a, b)
             };
             // Devolvemos valor truncado a [0, 1]
-            if valor <= 0.0 { 0.0 } else if valor >= 1.0 { 1.0 } else { valor }
+            valor.clamp(0.0, 1.0)
         }).collect();
         normalizar(&mut c_hijo);  // Normalizamos el cromosoma para que el máximo sea 1
         hijos.push(c_hijo);
@@ -41,44 +71,37 @@ fn blx_03<Trng: Rng>(padre1: &[f64], padre2: &[f64], rng: &mut Trng) -> Vec<Vec<
 
 // Operador de cruce aritmético
 // Recibe los padres y un RNG y devuelve un hijo
-fn ca<Trng: Rng>(padre1: &[f64], padre2: &[f64], _rng: &mut Trng) -> Vec<Vec<f64>> {
-    let mut unico_hijo = padre1.iter().zip(padre2.iter()).map(|(x, y)| (*x + *y)/2.0).collect();
+pub fn ca<Trng: Rng>(padre1: &[f64], padre2: &[f64], _rng: &mut Trng) -> Vec<Vec<f64>> {
+    let mut unico_hijo: Vec<f64> = padre1.iter().zip(padre2.iter()).map(|(x, y)| (*x + *y)/2.0).collect();
     normalizar(&mut unico_hijo);  // Normalizamos el vector, porque seguramente el máximo ha dejado de ser 1
     vec![unico_hijo]
 }
 
-const TAMANO_GENERACIONAL: usize = 30;
-const TAMANO_ESTACIONARIO: usize = 30;
-const TASA_CRUCE_GENERACIONAL: f64 = 0.7;
-const TASA_MUTACION_GEN: f64 = 0.001;
-const MAX_EVALUACIONES: usize = 15000;
+// Los tamaños de población, las tasas de cruce y mutación y el máximo de evaluaciones son parámetros (ver parametros.rs)
 const MAX_CICLOS_BL: usize = 100000000; // No hay límite de ciclos, sino de evaluaciones
 
 
 // Procedimiento memético consistente en no hacer nada
 // Si se usa en memetico_generacional el resultado es un algoritmo genético generacional
-fn nada<Trng: Rng>(_entrenamiento: &[Dato], _cromosomas: &mut ArbolBinario, _rng: &mut Trng) -> usize {
-    return 0; // No hace nada. Por consiguiente, no debe incrementar el contador de evaluaciones
+fn nada<Trng: Rng>(_problema: &dyn Problema, _cromosomas: &mut ArbolBinario, _evaluador: &Evaluador, _rng: &mut Trng) {
+    // No hace nada, y por tanto no hace ninguna evaluación
 }
 
 // Aplica búsqueda local a todos los cromosomas de una población
-fn bl_todos<Trng: Rng>(entrenamiento: &[Dato], cromosomas: &mut ArbolBinario, rng: &mut Trng) -> usize {
-    let max_evaluaciones = 2*entrenamiento[0].num_atributos();
-    let num_cromosomas = cromosomas.len();
+fn bl_todos<Trng: Rng>(problema: &dyn Problema, cromosomas: &mut ArbolBinario, evaluador: &Evaluador, rng: &mut Trng) {
+    let max_evaluaciones = 2*problema.dimension();
     let mut nuevos_cromosomas: ArbolBinario = cromosomas.iter().map(|(cr_id, cr)| {
-        let nuevo_cromosoma = busqueda_local_generica_desde(&entrenamiento, &cr, &vecino_bl, max_evaluaciones, MAX_CICLOS_BL, rng);
-        ((OrderedFloat(-evaluar(&entrenamiento, &nuevo_cromosoma)), cr_id.1), nuevo_cromosoma)
+        let nuevo_cromosoma = busqueda_local_generica_desde(problema, cr, &vecino_bl, max_evaluaciones, MAX_CICLOS_BL, evaluador, rng);
+        ((OrderedFloat(-problema.evaluar(evaluador, &nuevo_cromosoma)), cr_id.1), nuevo_cromosoma)
     }).collect();
 
     cromosomas.clear();
     cromosomas.append(&mut nuevos_cromosomas);
-
-    num_cromosomas*(1 + max_evaluaciones)  // Se devuelve el número de evaluaciones, que está fijado para cada cromosoma en el guion
 }
 
 // Aplica búsqueda local a los cromosomas de una población con un 10% de probabilidad
-fn bl_01<Trng: Rng>(entrenamiento: &[Dato], cromosomas: &mut ArbolBinario, rng: &mut Trng) -> usize {
-    let max_evaluaciones = 2*entrenamiento[0].num_atributos();
+fn bl_01<Trng: Rng>(problema: &dyn Problema, cromosomas: &mut ArbolBinario, evaluador: &Evaluador, rng: &mut Trng) {
+    let max_evaluaciones = 2*problema.dimension();
     let mut cromosomas_borrados = vec![]; // Almacena las claves de los cromosomas que van a ser borrados
     let mut nuevos_cromosomas: ArbolBinario = cromosomas.iter().filter_map(|(cr_id, cr)| {
         if rng.gen::<f64>() >= 0.1 {
@@ -86,69 +109,79 @@ fn bl_01<Trng: Rng>(entrenamiento: &[Dato], cromosomas: &mut ArbolBinario, rng:
         } else {
             // En el resto de casos, se cambia el cromosoma
             cromosomas_borrados.push(*cr_id);
-            let nuevo_cromosoma = busqueda_local_generica_desde(&entrenamiento, &cr, &vecino_bl, max_evaluaciones, MAX_CICLOS_BL, rng);
-            Some(((OrderedFloat(-evaluar(&entrenamiento, &nuevo_cromosoma)), cr_id.1), nuevo_cromosoma))
+            let nuevo_cromosoma = busqueda_local_generica_desde(problema, cr, &vecino_bl, max_evaluaciones, MAX_CICLOS_BL, evaluador, rng);
+            Some(((OrderedFloat(-problema.evaluar(evaluador, &nuevo_cromosoma)), cr_id.1), nuevo_cromosoma))
         }
     }).collect();
 
-    let num_cromosomas = cromosomas_borrados.len();
     for cr_id in cromosomas_borrados {
         cromosomas.remove(&cr_id);  // Borramos los cromosomas que han sido explorados usando su clave
     }
     cromosomas.append(&mut nuevos_cromosomas);
-
-    num_cromosomas*(1 + max_evaluaciones)
 }
 
 // Aplica búsqueda local al diez por ciento de los mejores cromosomas de una población
-fn bl_01mej<Trng: Rng>(entrenamiento: &[Dato], cromosomas: &mut ArbolBinario, rng: &mut Trng) -> usize {
-    let max_evaluaciones = 2*entrenamiento[0].num_atributos();
+fn bl_01mej<Trng: Rng>(problema: &dyn Problema, cromosomas: &mut ArbolBinario, evaluador: &Evaluador, rng: &mut Trng) {
+    let max_evaluaciones = 2*problema.dimension();
     let num_cromosomas = (cromosomas.len() as f64 * 0.1).round() as usize;
     let mut cromosomas_borrados = vec![]; // Almacena las claves de los cromosomas que van a ser borrados
     // Como el árbol de cromosomas está ordenado con los mejores primero, tomamos los primeros
     let mut nuevos_cromosomas: ArbolBinario = cromosomas.iter().take(num_cromosomas).map(|(cr_id, cr)| {
         cromosomas_borrados.push(*cr_id);
-        let nuevo_cromosoma = busqueda_local_generica_desde(&entrenamiento, &cr, &vecino_bl, max_evaluaciones, MAX_CICLOS_BL, rng);
-        ((OrderedFloat(-evaluar(&entrenamiento, &nuevo_cromosoma)), cr_id.1), nuevo_cromosoma)
+        let nuevo_cromosoma = busqueda_local_generica_desde(problema, cr, &vecino_bl, max_evaluaciones, MAX_CICLOS_BL, evaluador, rng);
+        ((OrderedFloat(-problema.evaluar(evaluador, &nuevo_cromosoma)), cr_id.1), nuevo_cromosoma)
     }).collect();
 
     for cr_id in cromosomas_borrados {
         cromosomas.remove(&cr_id);  // Borramos los cromosomas que han sido explorados usando su clave
     }
     cromosomas.append(&mut nuevos_cromosomas);
-
-    num_cromosomas*(1 + max_evaluaciones)
 }
 
 
 // Ejecuta un algoritmo memético basado en un algoritmo genético
 //    según un esquema generacional con elitismo
-// Recibe la muestra, una función generadora de soluciones iniciales,
+// Recibe el problema a optimizar (ver problema.rs), una función generadora de soluciones iniciales,
 //   un operador de cruce, un operador de generación de un vecino para efectuar mutaciones,
-//   un operador memético (que devuelve el número de evaluaciones usadas) y un RNG
+//   un operador memético, el evaluador de las soluciones y un RNG
 // El procedimiento generador de soluciones iniciales debe no ser determinista
-pub fn memetico_generacional<Trng: Rng>(entrenamiento: &[Dato], gen_iniciales: &Fn(&[Dato], &mut Trng) -> Vec<f64>, cruce: &Fn(&[f64], &[f64], &mut Trng) -> Vec<Vec<f64>>, vecino: &Fn(&[f64], usize, &mut Trng) -> Vec<f64>, op_memetica: (usize, &Fn(&[Dato], &mut ArbolBinario, &mut Trng) -> usize), rng: &mut Trng) -> Vec<f64> {
+pub fn memetico_generacional<Trng: Rng>(problema: &dyn Problema, gen_iniciales: &GenInicial<Trng>, cruce: &Cruce<Trng>, vecino: &Vecino<Trng>, op_memetica: (usize, &OperadorMemetico<Trng>), evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    let param = parametros();
+    let _ambito = traza::entrar();
     // Rellenamos la población con elementos seleccionados por gen_iniciales (probablemente aleatorios)
+    //   o, si se está reanudando la ejecución, con la población guardada en el punto de control
     let mut poblacion = ArbolBinario::new();  // Por cada elemento, su evaluación y su identificador como clave (se ordenará según su evaluación, y en caso de empate según identificador)
-    for i in 0..TAMANO_GENERACIONAL {
-        let cromosoma_aleatorio = gen_iniciales(&entrenamiento, rng);
-        poblacion.insert((OrderedFloat(-evaluar(&entrenamiento, &cromosoma_aleatorio)), i), cromosoma_aleatorio);
+    let mut n_evaluaciones_inicial = param.tamano_generacional;
+    let mut n_generaciones = 0;   // Número de generaciones desde la última vez que se aplicó el procedimiento memético
+    match punto_control::cargar_poblacion(evaluador) {
+        Some(estado) => {
+            for (evaluacion, id, cromosoma) in estado.individuos {
+                poblacion.insert((OrderedFloat(-evaluacion), id), cromosoma);
+            }
+            n_evaluaciones_inicial = estado.evaluaciones;
+            n_generaciones = estado.generacion;
+        },
+        None => for i in 0..param.tamano_generacional {
+            let cromosoma_aleatorio = gen_iniciales(problema, rng);
+            poblacion.insert((OrderedFloat(-problema.evaluar(evaluador, &cromosoma_aleatorio)), i), cromosoma_aleatorio);
+        },
     }
 
     // Parámetros que afectan al operador de mutación
-    let n_caracteristicas = entrenamiento[0].num_atributos();
-    let n_genes = n_caracteristicas*TAMANO_GENERACIONAL;
-    let esperanza_mutaciones = (TASA_MUTACION_GEN*(n_genes as f64)).round() as usize;
+    let n_caracteristicas = problema.dimension();
+    let n_genes = n_caracteristicas*param.tamano_generacional;
+    let esperanza_mutaciones = (param.tasa_mutacion_gen*(n_genes as f64)).round() as usize;
 
-    let n_evaluaciones = RefCell::<usize>::new(TAMANO_GENERACIONAL); // número de veces que se ha evaluado la función objetivo
-    let mut n_generaciones = 0;   // Número de generaciones desde la última vez que se aplicó el procedimiento memético
+    let n_evaluaciones = RefCell::<usize>::new(n_evaluaciones_inicial); // número de veces que se ha evaluado la función objetivo
+    let mut generaciones_control = 0;   // Número de generaciones desde la última vez que se guardó la población
+    diversidad_poblacion(problema, evaluador, poblacion.iter().map(|(id, cr)| (-(id.0).0, cr.as_slice())));
 
     loop {
         // El vector de nuevos cromosomas almacena también su identificador
         //   y su evaluación si no es un cromosoma nuevo
-        let mut nueva_poblacion: Vec<(Option<(OrderedFloat<f64>, usize)>, Vec<f64>)> = Vec::with_capacity(TAMANO_GENERACIONAL);
+        let mut nueva_poblacion: Vec<(Option<Clave>, Vec<f64>)> = Vec::with_capacity(param.tamano_generacional);
 
-        while nueva_poblacion.len() < TAMANO_GENERACIONAL {
+        while nueva_poblacion.len() < param.tamano_generacional {
             let mejor = |c1: (_, _), c2: (_, _)| -> (_, bool) {
                 if c1.0 <= c2.0 { (c1, true) } else { (c2, false) }
             }; // Devuelve el mejor de dos cromosomas y true si es el primero. En caso de empate devuelve el más antiguo. Recuérdese que se almacena el opuesto de la evaluación del cromosoma
@@ -159,12 +192,14 @@ pub fn memetico_generacional<Trng: Rng>(entrenamiento: &[Dato], gen_iniciales: &
 
             // Hacemos grupos de 4. Se cruza el mejor de los dos primeros con el mejor de
             //   los dos segundos hasta que hagamos cruces para cubrir el 70% de la población
+            // Al redondear no pueden formarse más grupos de los que caben en la población
+            let n_cruces = (((param.tamano_generacional as f64)*param.tasa_cruce_generacional/4.0).round() as usize).min(param.tamano_generacional/4);
             let mut emp_iter = emparejamientos.into_iter();
-            for _t in 0..((TAMANO_GENERACIONAL as f64)*TASA_CRUCE_GENERACIONAL/4.0).round() as usize {
+            for _t in 0..n_cruces {
                 let grupo: Vec<(_, &Vec<f64>)> = emp_iter.by_ref().take(4).collect();
                 let padre1 = mejor(grupo[0], grupo[1]).0;
                 let padre2 = mejor(grupo[2], grupo[3]).0;
-                let resultado_cruce = cruce(&padre1.1, &padre2.1, rng);
+                let resultado_cruce = cruce(padre1.1, padre2.1, rng);
                 let num_nuevos = resultado_cruce.len();
                 for nuevos in resultado_cruce {
                     nueva_poblacion.push((None, nuevos));
@@ -195,16 +230,22 @@ pub fn memetico_generacional<Trng: Rng>(entrenamiento: &[Dato], gen_iniciales: &
             let (individuo, gen) = m;
             nueva_poblacion[individuo] = (None, vecino(&nueva_poblacion[individuo].1, gen, rng));
         }
+        // Si no hay cruces ni mutaciones (con tasas muy bajas o poblaciones pequeñas), mutamos un gen al azar
+        //   para que cada generación evalúe algún cromosoma nuevo y el presupuesto llegue a agotarse
+        if nueva_poblacion.iter().all(|c| c.0.is_some()) {
+            let (individuo, gen) = numero_a_mutacion(rng.gen());
+            nueva_poblacion[individuo] = (None, vecino(&nueva_poblacion[individuo].1, gen, rng));
+        }
 
         // Reemplazamos los cromosomas de la población por los nuevos
         // Si tenían tupla evaluación-identificador, se sigue usando. Si no, se evalúa y se asigna una
         let mut vieja_poblacion = ArbolBinario::new();
         std::mem::swap(&mut vieja_poblacion, &mut poblacion);
 
-        for (_i, c) in nueva_poblacion.iter().enumerate() {
+        for c in nueva_poblacion.iter() {
             poblacion.insert(c.0.unwrap_or_else(|| {
                   *n_evaluaciones.borrow_mut() += 1;
-                  (OrderedFloat(-evaluar(&entrenamiento, &c.1)), *n_evaluaciones.borrow())
+                  (OrderedFloat(-problema.evaluar(evaluador, &c.1)), *n_evaluaciones.borrow())
                 }),
                 (*c.1).to_vec());
         }
@@ -213,33 +254,63 @@ pub fn memetico_generacional<Trng: Rng>(entrenamiento: &[Dato], gen_iniciales: &
         let mut elite_anteriores = vieja_poblacion.iter();
         let mejor_anterior = elite_anteriores.next().unwrap();
 
-        if !poblacion.contains_key(&mejor_anterior.0) {    // Si la mejor solución anterior no estaba,
+        if !poblacion.contains_key(mejor_anterior.0) {    // Si la mejor solución anterior no estaba,
             poblacion.insert(*mejor_anterior.0, mejor_anterior.1.to_vec()); // la introducimos
         }
-        if poblacion.len() == 1 + TAMANO_GENERACIONAL { // Si al introducir el mejor cromosoma anterior hay de más,
+        if poblacion.len() == 1 + param.tamano_generacional { // Si al introducir el mejor cromosoma anterior hay de más,
             let peor = *poblacion.iter().next_back().unwrap().0;  // eliminamos el peor
             poblacion.remove(&peor);
         }
 
         // Si se diese el caso de haber más de una repetición de cromosomas en la nueva generación,
         //   se sigue rellenando con los mejores cromosomas de la generación anterior
-        while poblacion.len() < TAMANO_GENERACIONAL {
+        while poblacion.len() < param.tamano_generacional {
             let siguiente_mejor = elite_anteriores.next().unwrap();
-            if !poblacion.contains_key(&siguiente_mejor.0) {
+            if !poblacion.contains_key(siguiente_mejor.0) {
                 poblacion.insert(*siguiente_mejor.0, siguiente_mejor.1.to_vec());
             }
         }
 
-        // Aplicamos el procedimiento memético si corresponde, contando el número de evaluaciones adicionales
+        // Aplicamos el procedimiento memético si corresponde, contando las evaluaciones que ha hecho según el presupuesto
         n_generaciones += 1;
         if n_generaciones == op_memetica.0 {
-            *n_evaluaciones.borrow_mut() += op_memetica.1(&entrenamiento, &mut poblacion, rng);
+            let antes = evaluador.usadas();
+            op_memetica.1(problema, &mut poblacion, evaluador, rng);
+            *n_evaluaciones.borrow_mut() += evaluador.usadas() - antes;
             n_generaciones = 0;
         }
+        let diversidad = diversidad_poblacion(problema, evaluador, poblacion.iter().map(|(id, cr)| (-(id.0).0, cr.as_slice())));
 
-        if *n_evaluaciones.borrow() >= MAX_EVALUACIONES {
+        if evaluador.agotado() {
             break;
         }
+
+        // Si la población ha convergido, la reiniciamos conservando los mejores cromosomas (el parámetro elite_reinicio)
+        // Si el presupuesto se agota a mitad, se conservan también los que no se han llegado a sustituir
+        if debe_reiniciar(diversidad) {
+            let sustituidos: Vec<_> = poblacion.keys().skip(param.elite_reinicio).cloned().collect();
+            for id in sustituidos {
+                if evaluador.agotado() { break }
+                poblacion.remove(&id);
+                let cromosoma_aleatorio = gen_iniciales(problema, rng);
+                *n_evaluaciones.borrow_mut() += 1;
+                poblacion.insert((OrderedFloat(-problema.evaluar(evaluador, &cromosoma_aleatorio)), *n_evaluaciones.borrow()), cromosoma_aleatorio);
+            }
+            if evaluador.agotado() {
+                break;
+            }
+        }
+
+        // Guardamos la población en el punto de control si corresponde
+        generaciones_control += 1;
+        if generaciones_control == param.generaciones_control && punto_control::guardar_poblaciones() {
+            punto_control::guardar_poblacion(evaluador, &EstadoPoblacion {
+                evaluaciones: *n_evaluaciones.borrow(),
+                generacion: n_generaciones,
+                individuos: poblacion.iter().map(|(id, cr)| (-(id.0).0, id.1, cr.clone())).collect(),
+            });
+            generaciones_control = 0;
+        }
     };
 
     poblacion.iter().next().unwrap().1.to_vec() // Devolvemos la mejor solución encontrada
@@ -247,25 +318,35 @@ pub fn memetico_generacional<Trng: Rng>(entrenamiento: &[Dato], gen_iniciales: &
 
 
 // Ejecuta un algoritmo genético según un esquema generacional con elitismo
-// Recibe la muestra, una función generadora de soluciones iniciales, un operador de cruce,
-//   un operador de generación de un vecino para efectuar mutaciones y un RNG
+// Recibe el problema a optimizar (ver problema.rs), una función generadora de soluciones iniciales, un operador de cruce,
+//   un operador de generación de un vecino para efectuar mutaciones, el evaluador de las soluciones y un RNG
 // El procedimiento generador de soluciones iniciales debe no ser determinista
-pub fn genetico_generacional<Trng: Rng>(entrenamiento: &[Dato], gen_iniciales: &Fn(&[Dato], &mut Trng) -> Vec<f64>, cruce: &Fn(&[f64], &[f64], &mut Trng) -> Vec<Vec<f64>>, vecino: &Fn(&[f64], usize, &mut Trng) -> Vec<f64>, rng: &mut Trng) -> Vec<f64> {
+pub fn genetico_generacional<Trng: Rng>(problema: &dyn Problema, gen_iniciales: &GenInicial<Trng>, cruce: &Cruce<Trng>, vecino: &Vecino<Trng>, evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
     // Usa la función que aplica un algoritmo memético sin efectuar ningún procedimiento de explotación
-    return memetico_generacional(&entrenamiento, &gen_iniciales, &cruce, &vecino, (99999999, &nada), rng);
+    memetico_generacional(problema, &gen_iniciales, &cruce, &vecino, (99999999, &nada), evaluador, rng)
 }
 
 
 // Ejecuta un algoritmo genético con esquema estacionario
-// Recibe la muestra, una función generadora de soluciones iniciales, un operador de cruce,
-//   un operador de generación de un vecino para efectuar mutaciones y un RNG
+// Recibe el problema a optimizar (ver problema.rs), una función generadora de soluciones iniciales, un operador de cruce,
+//   un operador de generación de un vecino para efectuar mutaciones, el evaluador de las soluciones y un RNG
 // El procedimiento generador de soluciones iniciales debe no ser determinista
-pub fn genetico_estacionario<Trng: Rng>(entrenamiento: &[Dato], gen_iniciales: &Fn(&[Dato], &mut Trng) -> Vec<f64>, cruce: &Fn(&[f64], &[f64], &mut Trng) -> Vec<Vec<f64>>, vecino: &Fn(&[f64], usize, &mut Trng) -> Vec<f64>, rng: &mut Trng) -> Vec<f64> {
+pub fn genetico_estacionario<Trng: Rng>(problema: &dyn Problema, gen_iniciales: &GenInicial<Trng>, cruce: &Cruce<Trng>, vecino: &Vecino<Trng>, evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    let param = parametros();
+    let _ambito = traza::entrar();
     // Rellenamos la población con elementos seleccionados por gen_iniciales (probablemente aleatorios)
-    let mut poblacion: Vec<(Vec<f64>, f64)> = Vec::with_capacity(TAMANO_ESTACIONARIO);
-    for _i in 0..TAMANO_ESTACIONARIO {
-        let cromosoma = gen_iniciales(&entrenamiento, rng);
-        poblacion.push((cromosoma.clone(), evaluar(&entrenamiento, &cromosoma)));
+    //   o, si se está reanudando la ejecución, con la población guardada en el punto de control
+    let mut poblacion: Vec<(Vec<f64>, f64)> = Vec::with_capacity(param.tamano_estacionario);
+    let mut n_evaluaciones_inicial = param.tamano_estacionario;
+    match punto_control::cargar_poblacion(evaluador) {
+        Some(estado) => {
+            poblacion.extend(estado.individuos.into_iter().map(|(evaluacion, _, cromosoma)| (cromosoma, evaluacion)));
+            n_evaluaciones_inicial = estado.evaluaciones;
+        },
+        None => for _i in 0..param.tamano_estacionario {
+            let cromosoma = gen_iniciales(problema, rng);
+            poblacion.push((cromosoma.clone(), problema.evaluar(evaluador, &cromosoma)));
+        },
     }
 
     let mut peor; // (posición, evaluación) del peor cromosoma
@@ -274,18 +355,23 @@ pub fn genetico_estacionario<Trng: Rng>(entrenamiento: &[Dato], gen_iniciales: &
     }  // Esta macro fija la variable peor a una tupla con la posición y la valoración del peor cromosoma
     encontrar_peor!();
 
-    let n_caracteristicas = entrenamiento[0].num_atributos();
-    let n_evaluaciones = RefCell::<usize>::new(TAMANO_ESTACIONARIO); // número de veces que se ha evaluado la función objetivo
+    let n_caracteristicas = problema.dimension();
+    let n_evaluaciones = RefCell::<usize>::new(n_evaluaciones_inicial); // número de veces que se ha evaluado la función objetivo
+    // Se considera que pasa una generación cada vez que se evalúan tantos hijos como individuos tiene la población
+    let evaluaciones_control = param.generaciones_control*param.tamano_estacionario;
+    let mut siguiente_control = n_evaluaciones_inicial + evaluaciones_control;
+    let mut siguiente_generacion = n_evaluaciones_inicial + param.tamano_estacionario;
+    diversidad_poblacion(problema, evaluador, poblacion.iter().map(|(cr, ev)| (*ev, cr.as_slice())));
 
     loop {
-        let mut tasa_mutacion = TASA_MUTACION_GEN;
+        let mut tasa_mutacion = param.tasa_mutacion_gen;
         let hijos = {
             // Escogemos cuatro elementos al azar y los enfrentamos dos a dos
             // Puede resultar que se cruza un cromosoma consigo mismo. Si eso sucede, lo mutamos más de lo normal
-            let i_1a = rng.gen_range(0, TAMANO_ESTACIONARIO);
-            let i_1b = rng.gen_range(0, TAMANO_ESTACIONARIO);
-            let i_2a = rng.gen_range(0, TAMANO_ESTACIONARIO);
-            let i_2b = rng.gen_range(0, TAMANO_ESTACIONARIO);
+            let i_1a = rng.gen_range(0, param.tamano_estacionario);
+            let i_1b = rng.gen_range(0, param.tamano_estacionario);
+            let i_2a = rng.gen_range(0, param.tamano_estacionario);
+            let i_2b = rng.gen_range(0, param.tamano_estacionario);
 
             let padre1 = &poblacion[if poblacion[i_1a].1 >= poblacion[i_1b].1 { i_1a } else { i_1b }].0;
             let padre2 = &poblacion[if poblacion[i_2a].1 >= poblacion[i_2b].1 { i_2a } else { i_2b }].0;
@@ -294,7 +380,7 @@ pub fn genetico_estacionario<Trng: Rng>(entrenamiento: &[Dato], gen_iniciales: &
                 tasa_mutacion *= 100.0; // disparamos la tasa de mutaciones
             }
             // Cruzamos los dos ganadores e insertamos los hijos en la población si son mejores que el peor actual
-            cruce(&padre1, &padre2, rng)
+            cruce(padre1, padre2, rng)
         };
 
         for h in hijos {
@@ -308,17 +394,49 @@ pub fn genetico_estacionario<Trng: Rng>(entrenamiento: &[Dato], gen_iniciales: &
             }
 
             // Introducimos el hijo si no es peor que el actual peor, y eliminamos el peor
-            let ev_h = evaluar(&entrenamiento, &h_mutado);
+            let ev_h = problema.evaluar(evaluador, &h_mutado);
             *n_evaluaciones.borrow_mut() += 1;
             if ev_h > peor.1 {
                 poblacion[peor.0] = (h_mutado, ev_h);
                 encontrar_peor!();
             }
         }
+        // Al terminar cada generación calculamos la diversidad de la población
+        let mut diversidad = None;
+        if *n_evaluaciones.borrow() >= siguiente_generacion {
+            diversidad = diversidad_poblacion(problema, evaluador, poblacion.iter().map(|(cr, ev)| (*ev, cr.as_slice())));
+            siguiente_generacion += param.tamano_estacionario;
+        }
 
-        if *n_evaluaciones.borrow() >= MAX_EVALUACIONES {
+        if evaluador.agotado() {
             break;
         }
+
+        // Si la población ha convergido, la reiniciamos conservando los mejores cromosomas (el parámetro elite_reinicio)
+        // Si el presupuesto se agota a mitad, se conservan también los que no se han llegado a sustituir
+        if debe_reiniciar(diversidad) {
+            poblacion.sort_by_key(|c| OrderedFloat(-c.1));
+            for c in poblacion.iter_mut().skip(param.elite_reinicio) {
+                if evaluador.agotado() { break }
+                let cromosoma = gen_iniciales(problema, rng);
+                *c = (cromosoma.clone(), problema.evaluar(evaluador, &cromosoma));
+                *n_evaluaciones.borrow_mut() += 1;
+            }
+            encontrar_peor!();
+            if evaluador.agotado() {
+                break;
+            }
+        }
+
+        // Guardamos la población en el punto de control si corresponde
+        if *n_evaluaciones.borrow() >= siguiente_control && punto_control::guardar_poblaciones() {
+            punto_control::guardar_poblacion(evaluador, &EstadoPoblacion {
+                evaluaciones: *n_evaluaciones.borrow(),
+                generacion: 0,
+                individuos: poblacion.iter().enumerate().map(|(i, (cr, ev))| (*ev, i, cr.clone())).collect(),
+            });
+            siguiente_control += evaluaciones_control;
+        }
     };
 
 
@@ -327,41 +445,41 @@ pub fn genetico_estacionario<Trng: Rng>(entrenamiento: &[Dato], gen_iniciales: &
 
 
 // Algoritmo genético generacional con cruce BLX-0.3
-pub fn agg_blx<Trng: Rng>(entrenamiento: &[Dato], rng: &mut Trng) -> Vec<f64> {
-    genetico_generacional(&entrenamiento, &vector_au, &blx_03, &vecino_bl, rng)
+pub fn agg_blx<Trng: Rng>(entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    genetico_generacional(&PonderacionCaracteristicas::nueva(entrenamiento), &pesos_aleatorios, &blx_03, &vecino_bl, evaluador, rng)
 }
 
 // Algoritmo genético generacional con cruce aritmético
-pub fn agg_ca<Trng: Rng>(entrenamiento: &[Dato], rng: &mut Trng) -> Vec<f64> {
-    genetico_generacional(&entrenamiento, &vector_au, &ca, &vecino_bl, rng)
+pub fn agg_ca<Trng: Rng>(entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    genetico_generacional(&PonderacionCaracteristicas::nueva(entrenamiento), &pesos_aleatorios, &ca, &vecino_bl, evaluador, rng)
 }
 
 // Algoritmo genético estacionario con cruce BLX-0.3
-pub fn age_blx<Trng: Rng>(entrenamiento: &[Dato], rng: &mut Trng) -> Vec<f64> {
-    genetico_estacionario(&entrenamiento, &vector_au, &blx_03, &vecino_bl, rng)
+pub fn age_blx<Trng: Rng>(entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    genetico_estacionario(&PonderacionCaracteristicas::nueva(entrenamiento), &pesos_aleatorios, &blx_03, &vecino_bl, evaluador, rng)
 }
 
 // Algoritmo genético estacionario con cruce aritmético
-pub fn age_ca<Trng: Rng>(entrenamiento: &[Dato], rng: &mut Trng) -> Vec<f64> {
-    genetico_estacionario(&entrenamiento, &vector_au, &ca, &vecino_bl, rng)
+pub fn age_ca<Trng: Rng>(entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    genetico_estacionario(&PonderacionCaracteristicas::nueva(entrenamiento), &pesos_aleatorios, &ca, &vecino_bl, evaluador, rng)
 }
 
 // Algoritmo memético AM-(10, 1.0)
 // Cada 10 generaciones aplica la búsqueda local de la práctica 1 a todos los elementos de la población
-pub fn am_a<Trng: Rng>(entrenamiento: &[Dato], rng: &mut Trng) -> Vec<f64> {
-    memetico_generacional(&entrenamiento, &vector_au, &blx_03, &vecino_bl, (10, &bl_todos), rng)
+pub fn am_a<Trng: Rng>(entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    memetico_generacional(&PonderacionCaracteristicas::nueva(entrenamiento), &pesos_aleatorios, &blx_03, &vecino_bl, (10, &bl_todos), evaluador, rng)
 }
 
 // Algoritmo memético AM-(10, 0.1)
 // Cada 10 generaciones aplica la búsqueda local de la práctica 1 al 10% de los elementos de la población
-pub fn am_b<Trng: Rng>(entrenamiento: &[Dato], rng: &mut Trng) -> Vec<f64> {
-    memetico_generacional(&entrenamiento, &vector_au, &blx_03, &vecino_bl, (10, &bl_01), rng)
+pub fn am_b<Trng: Rng>(entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    memetico_generacional(&PonderacionCaracteristicas::nueva(entrenamiento), &pesos_aleatorios, &blx_03, &vecino_bl, (10, &bl_01), evaluador, rng)
 }
 
 // Algoritmo memético AM-(10, 0.1mej)
 // Cada 10 generaciones aplica la búsqueda local de la práctica 1 al 10% de los elementos de la población
-pub fn am_c<Trng: Rng>(entrenamiento: &[Dato], rng: &mut Trng) -> Vec<f64> {
-    memetico_generacional(&entrenamiento, &vector_au, &blx_03, &vecino_bl, (10, &bl_01mej), rng)
+pub fn am_c<Trng: Rng>(entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    memetico_generacional(&PonderacionCaracteristicas::nueva(entrenamiento), &pesos_aleatorios, &blx_03, &vecino_bl, (10, &bl_01mej), evaluador, rng)
 }
 
 
@@ -373,12 +491,10 @@ pub fn am_c<Trng: Rng>(entrenamiento: &[Dato], rng: &mut Trng) -> Vec<f64> {
 //   algún padre y la media es mayor o igual que 0.2, hay 10% de que se devuelva el mínimo
 // Recibe los padres y un RNG y devuelve un hijo
 fn ca_alt<Trng: Rng>(padre1: &[f64], padre2: &[f64], rng: &mut Trng) -> Vec<Vec<f64>> {
-    let mut unico_hijo = padre1.iter().zip(padre2.iter()).map(|(x, y)| {
+    let mut unico_hijo: Vec<f64> = padre1.iter().zip(padre2.iter()).map(|(x, y)| {
         let mut valor = (*x + *y)/2.0;
         let menor = if *x <= *y { *x } else { *y };
-        if valor >= 0.2 && menor < 0.2 {
-            if rng.gen::<f64>() < 0.1 { valor = menor }
-        };
+        if valor >= 0.2 && menor < 0.2 && rng.gen::<f64>() < 0.1 { valor = menor }
         valor
     }).collect();
     normalizar(&mut unico_hijo);  // Normalizamos el vector, porque seguramente el máximo ha dejado de ser 1
@@ -387,29 +503,28 @@ fn ca_alt<Trng: Rng>(padre1: &[f64], padre2: &[f64], rng: &mut Trng) -> Vec<Vec<
 
 
 // Algoritmo genético estacionario con cruce aritmético alternativo
-pub fn age_ca_alt<Trng: Rng>(entrenamiento: &[Dato], rng: &mut Trng) -> Vec<f64> {
-    genetico_estacionario(&entrenamiento, &vector_au, &ca_alt, &vecino_bl, rng)
+pub fn age_ca_alt<Trng: Rng>(entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    genetico_estacionario(&PonderacionCaracteristicas::nueva(entrenamiento), &pesos_aleatorios, &ca_alt, &vecino_bl, evaluador, rng)
 }
 
 
 // Algoritmo genético generacional con cruce BLX-0.3 y operador de mutación alternativo
 // El operador de mutación es el operador de vecino propuesto en la práctica 1
-pub fn agg_blx_mut2<Trng: Rng>(entrenamiento: &[Dato], rng: &mut Trng) -> Vec<f64> {
-    genetico_generacional(&entrenamiento, &vector_au, &blx_03, &vecino_bl_mut2, rng)
+pub fn agg_blx_mut2<Trng: Rng>(entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    genetico_generacional(&PonderacionCaracteristicas::nueva(entrenamiento), &pesos_aleatorios, &blx_03, &vecino_bl_mut2, evaluador, rng)
 }
 
 
 // Algoritmo genético estacionario con cruce BLX-0.3 y operador de mutación alternativo
 // El operador de mutación es el operador de vecino propuesto en la práctica 1
-pub fn age_blx_mut2<Trng: Rng>(entrenamiento: &[Dato], rng: &mut Trng) -> Vec<f64> {
-    genetico_estacionario(&entrenamiento, &vector_au, &blx_03, &vecino_bl_mut2, rng)
+pub fn age_blx_mut2<Trng: Rng>(entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    genetico_estacionario(&PonderacionCaracteristicas::nueva(entrenamiento), &pesos_aleatorios, &blx_03, &vecino_bl_mut2, evaluador, rng)
 }
 
 
 // Aplica búsqueda local por afinidad a los cromosomas de una población con un 10% de probabilidad
 // Véase el método potencia_optima en las funciones de la práctica 1
-fn af_01<Trng: Rng>(entrenamiento: &[Dato], cromosomas: &mut ArbolBinario, rng: &mut Trng) -> usize {
-    let mut num_evaluaciones = 0;
+fn af_01<Trng: Rng>(entrenamiento: &[Dato], cromosomas: &mut ArbolBinario, evaluador: &Evaluador, rng: &mut Trng) {
     let mut cromosomas_borrados = vec![]; // Almacena las claves de los cromosomas que van a ser borrados
     let mut nuevos_cromosomas: ArbolBinario = cromosomas.iter().filter_map(|(cr_id, cr)| {
         if rng.gen::<f64>() >= 0.1 {
@@ -417,9 +532,8 @@ fn af_01<Trng: Rng>(entrenamiento: &[Dato], cromosomas: &mut ArbolBinario, rng:
         } else {
             // En el resto de casos, se cambia el cromosoma
             cromosomas_borrados.push(*cr_id);
-            let nuevo_cromosoma = afinidad_optima(&entrenamiento, &cr, rng);
-            num_evaluaciones += 1 + cr.iter().filter(|w| **w != 0.0 && **w != 1.0).count();
-            Some(((OrderedFloat(-evaluar(&entrenamiento, &nuevo_cromosoma)), cr_id.1), nuevo_cromosoma))
+            let nuevo_cromosoma = afinidad_optima(entrenamiento, cr, evaluador, rng);
+            Some(((OrderedFloat(-evaluar(evaluador, entrenamiento, &nuevo_cromosoma)), cr_id.1), nuevo_cromosoma))
         }
     }).collect();
 
@@ -427,12 +541,76 @@ fn af_01<Trng: Rng>(entrenamiento: &[Dato], cromosomas: &mut ArbolBinario, rng:
         cromosomas.remove(&cr_id);  // Borramos los cromosomas que han sido explorados usando su clave
     }
     cromosomas.append(&mut nuevos_cromosomas);
-
-    num_evaluaciones
 }
 
 // Algoritmo memético AM-(10, 0.1,af)
 // Cada 10 generaciones aplica la optimización por afinidad a los elementos de la población con probabilidad 10%
-pub fn am_afinidad_01<Trng: Rng>(entrenamiento: &[Dato], rng: &mut Trng) -> Vec<f64> {
-    memetico_generacional(&entrenamiento, &vector_au, &blx_03, &vecino_bl, (10, &af_01), rng)
+pub fn am_afinidad_01<Trng: Rng>(entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    memetico_generacional(&PonderacionCaracteristicas::nueva(entrenamiento), &pesos_aleatorios, &blx_03, &vecino_bl, (10, &|_: &dyn Problema, c: &mut ArbolBinario, evaluador: &Evaluador, rng: &mut Trng| af_01(entrenamiento, c, evaluador, rng)), evaluador, rng)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parametros::{fijar_parametros, Parametros};
+    use presupuesto::pruebas::evaluador;
+    use problema::pruebas::Parabola;
+    use rand::{Isaac64Rng, SeedableRng};
+
+    // Dos clases separadas por la primera característica, con una segunda característica de ruido
+    fn datos() -> Vec<Dato> {
+        (0..20).map(|i| Dato::new(&[(i % 2) as f64 * 0.8 + 0.1, (i * 7 % 10) as f64 / 10.0], &[], i % 2)).collect()
+    }
+
+    #[test]
+    fn generacional_con_todos_los_cromosomas_cruzados() {
+        // Con tasa de cruce 1 el redondeo pedía más grupos de 4 de los que caben en la población
+        for tamano in &[4, 6, 30] {
+            fijar_parametros(Parametros { tasa_cruce_generacional: 1.0, tamano_generacional: *tamano, ..Parametros::default() });
+            let e = evaluador(200);
+            let pesos = agg_blx(&datos(), &e, &mut Isaac64Rng::from_seed(&[1]));
+            assert_eq!(pesos.len(), 2);
+            assert_eq!(e.usadas(), 200);
+        }
+    }
+
+    #[test]
+    fn generacional_sin_cruces_ni_mutaciones() {
+        // Sin cruces ni mutaciones ninguna generación tenía cromosomas nuevos, así que no se evaluaba nada y no terminaba
+        for (cruce, mutacion) in &[(0.0, 0.0), (0.05, 0.001)] {
+            fijar_parametros(Parametros { tasa_cruce_generacional: *cruce, tasa_mutacion_gen: *mutacion, ..Parametros::default() });
+            let e = evaluador(100);
+            agg_blx(&datos(), &e, &mut Isaac64Rng::from_seed(&[1]));
+            assert_eq!(e.usadas(), 100);
+        }
+        fijar_parametros(Parametros::default());
+    }
+
+    #[test]
+    fn los_reinicios_no_evaluan_sin_presupuesto() {
+        // Con un umbral de diversidad tan alto la población se reinicia en cada generación, y con
+        //   presupuestos distintos el presupuesto se agota en distintos puntos de un reinicio
+        fijar_parametros(Parametros { tamano_generacional: 6, tamano_estacionario: 6, diversidad_reinicio: 10.0,
+                                      elite_reinicio: 2, ..Parametros::default() });
+        let centro = Parabola::nueva(3, (0.0, 1.0));
+        for evaluaciones in 10..40 {
+            for estacionario in &[false, true] {
+                let e = evaluador(evaluaciones);
+                let gen_iniciales = |p: &dyn Problema, rng: &mut Isaac64Rng| {
+                    assert!(!e.agotado(), "se genera un cromosoma sin presupuesto para evaluarlo");
+                    pesos_aleatorios(p, rng)
+                };
+                let rng = &mut Isaac64Rng::from_seed(&[evaluaciones as u64]);
+                let (solucion, puntos) = traza::grabar(true, || if *estacionario {
+                    genetico_estacionario(&centro, &gen_iniciales, &blx_03, &vecino_bl, &e, rng)
+                } else {
+                    genetico_generacional(&centro, &gen_iniciales, &blx_03, &vecino_bl, &e, rng)
+                });
+                assert_eq!(e.usadas(), evaluaciones);
+                assert!(centro.funcion_objetivo(&solucion) >= puntos.last().unwrap().mejor);
+            }
+        }
+        fijar_parametros(Parametros::default());
+    }
 }
@@ -1,50 +1,45 @@
 use knn::Dato;
-use evaluacion_pesos::evaluar;
+use problema::{BusquedaLocal, GenInicial, Problema, PonderacionCaracteristicas, Vecino, acotar};
+use parametros::parametros;
+use presupuesto::Evaluador;
 use rand::Rng;
 use rand::distributions::{Sample, Normal};
 use std::mem::swap;
+use traza;
 
 use funciones_practica1::*;    // Recuperamos la búsqueda local
 
 
 
 // Algunas constantes y funciones auxiliares
+// El resto de valores que ajustan los algoritmos son parámetros (ver parametros.rs)
 
 
-const MAX_EVALUACIONES: usize = 15000;    // Máximo de evaluaciones de la función objetivo en cada algoritmo
-const MAX_EVALUACIONES_BL: usize = 1000;  // Cada búsqueda local en ILS efectúa este número de evaluaciones
 const MAX_CICLOS_BL: usize = 100000000;   // No hay límite de ciclos, sino de evaluaciones
-const FACTOR_VECINOS_ES: usize = 10;      // Factor del número de vecinos a explorar por iteración de ES
-const TAMANO_DE: usize = 50;              // Tamaño de la población en evolución diferencial
-const DE_CR: f64 = 0.5;                   // Tasa de cruce por gen de evolución diferencial
-const DE_F : f64 = 0.5;                   // Factor de evolución diferencial
 
 
 type PoblacionDE = (Vec<(f64, Vec<f64>)>, usize); // Tipo de dato de una población de DE. Se almacena el índice del mejor cromosoma
+type Enfriamiento<'a> = dyn Fn(f64, f64, f64, usize) -> f64 + 'a;  // Calcula la siguiente temperatura a partir de la actual, la inicial, la final y el número de iteraciones
+type OperadorDE<'a, Trng> = dyn Fn(&dyn Problema, &PoblacionDE, usize, &mut Trng) -> (Option<f64>, Vec<f64>) + 'a;  // Genera el vector de prueba de un cromosoma de DE
 
 // Aplica enfriamiento según un esquema de enfriamiento de Cauchy modificado
-fn enfriamiento_cauchy(actual: f64, t_inicial: f64, t_final: f64, num_iteraciones: usize) -> f64 {
+pub fn enfriamiento_cauchy(actual: f64, t_inicial: f64, t_final: f64, num_iteraciones: usize) -> f64 {
     let b = (t_inicial - t_final)/((num_iteraciones as f64)*t_inicial*t_final);
     actual/(1.0 + b*actual)
 }
 
 
 // Procedimiento de mutación para ILS
-// Una décima parte de las componentes son mutadas según una normal de desviación típica 0.4
+// Una décima parte de las componentes son mutadas según una normal de desviación típica 0.4 (el parámetro sigma_ils)
 fn vecino_ils<Trng: Rng>(w: &[f64], rng: &mut Trng) -> Vec<f64> {
+    let sigma = parametros().sigma_ils;
     let mut nw = w.to_vec();
 
     let mut componentes: Vec<usize> = (0..w.len()).collect();
-    let mut sl = &mut componentes;
-    rng.shuffle(&mut sl);
-    for i in sl.iter().take((w.len() as f64 / 10.0).round() as usize) {
-        nw[*i] += Normal::new(0.0, 0.4).sample(rng);
-
-        if nw[*i] < 0.0 {
-            nw[*i] = 0.0;
-        } else if nw[*i] > 1.0 {
-            nw[*i] = 1.0;
-        }
+    rng.shuffle(&mut componentes);
+    for i in componentes.iter().take((w.len() as f64 / 10.0).round() as usize) {
+        nw[*i] += Normal::new(0.0, sigma).sample(rng);
+        nw[*i] = nw[*i].clamp(0.0, 1.0);
     }
 
     // Normalizamos y devolvemos el vector mutado
@@ -54,39 +49,55 @@ fn vecino_ils<Trng: Rng>(w: &[f64], rng: &mut Trng) -> Vec<f64> {
 
 
 // Procedimiento de búsqueda local para ILS
-// Es la misma búsqueda local de la práctica 1 salvo el criterio de parada: se hacen siempre 1000 evaluaciones
-pub fn bl_ils<Trng: Rng>(entrenamiento: &[Dato], w_base: &[f64], rng: &mut Trng) -> Vec<f64> {
-    busqueda_local_generica_desde(&entrenamiento, &w_base, &vecino_bl, MAX_EVALUACIONES_BL, MAX_CICLOS_BL, rng)
+// Es la misma búsqueda local de la práctica 1 salvo el criterio de parada: se hacen siempre 1000 evaluaciones (el parámetro max_evaluaciones_bl_ils)
+pub fn bl_ils<Trng: Rng>(problema: &dyn Problema, w_base: &[f64], evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    busqueda_local_generica_desde(problema, w_base, &vecino_bl, parametros().max_evaluaciones_bl_ils, MAX_CICLOS_BL, evaluador, rng)
 }
 
 
 
 
 
+// Con tiempo límite, las iteraciones que caben se estiman por el ritmo de las hechas hasta ahora (iteracion
+//   cuando ha pasado la fracción indicada del tiempo), para que el enfriamiento llegue a la temperatura final
+//   al acabarse el tiempo aunque no haya máximo de evaluaciones. Nunca son más de las que caben en las evaluaciones
+fn iteraciones_estimadas(max_iteraciones: usize, iteracion: usize, fraccion_tiempo: Option<f64>) -> usize {
+    match fraccion_tiempo.filter(|f| *f > 0.0) {
+        Some(f) => max_iteraciones.min((iteracion as f64 / f).ceil() as usize),
+        None => max_iteraciones,
+    }
+}
+
 // Algoritmo de enfriamiento simulado general
-// Recibe el procedimiento con el que se elige una solución inicial,
-//   el operador de vecino y el esquema de enfriamiento
-pub fn simulated_annealing_general<Trng: Rng>(entrenamiento: &[Dato], gen_inicial: &Fn(&[Dato], &mut Trng) -> Vec<f64>, vecino: &Fn(&[f64], usize, &mut Trng) -> Vec<f64>, enfriamiento: &Fn(f64, f64, f64, usize) -> f64, rng: &mut Trng) -> Vec<f64> {
-    let n_caracteristicas = entrenamiento[0].num_atributos();
-    let max_vecinos = FACTOR_VECINOS_ES*n_caracteristicas;        // Máximo de vecinos en cada iteración
+// Recibe el problema a optimizar (ver problema.rs), el procedimiento con el que se elige una solución inicial,
+//   el operador de vecino, el esquema de enfriamiento y el evaluador de las soluciones
+pub fn simulated_annealing_general<Trng: Rng>(problema: &dyn Problema, gen_inicial: &GenInicial<Trng>, vecino: &Vecino<Trng>, enfriamiento: &Enfriamiento, evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    let param = parametros();
+    let n_caracteristicas = problema.dimension();
+    let max_vecinos = param.factor_vecinos_es*n_caracteristicas;        // Máximo de vecinos en cada iteración
     let max_exitos = (0.1*max_vecinos as f64).ceil() as usize;    // Máximo de éxitos en cada iteración
-    let num_iteraciones = ((MAX_EVALUACIONES-1) as f64/max_vecinos as f64).ceil() as usize; // Número de iteraciones
+    let max_iteraciones = (evaluador.restantes().saturating_sub(1) as f64/max_vecinos as f64).ceil() as usize; // Iteraciones que caben en las evaluaciones
+    let mut num_iteraciones = max_iteraciones;   // Número de iteraciones
 
-    let solucion_aleatoria = gen_inicial(&entrenamiento, rng);
-    let mut solucion_actual = (solucion_aleatoria.clone(), evaluar(&entrenamiento, &solucion_aleatoria));
+    let _ambito = traza::entrar();
+    let solucion_aleatoria = gen_inicial(problema, rng);
+    let mut solucion_actual = (solucion_aleatoria.clone(), problema.evaluar(evaluador, &solucion_aleatoria));
     let mut mejor_solucion = solucion_actual.clone();
 
-    let t_inicial: f64 = - 0.3 * solucion_actual.1 / (0.3_f64).ln();
+    // Se usa el valor absoluto de la evaluación para que la temperatura sea positiva en problemas con puntuaciones negativas
+    let t_inicial: f64 = - 0.3 * solucion_actual.1.abs() / (0.3_f64).ln();
     let t_final  : f64 = 0.001;
     let mut temperatura = t_inicial;
+    traza::registrar(evaluador, solucion_actual.1, mejor_solucion.1, Some(temperatura), None);
 
-    for _i in 0..num_iteraciones {
+    let mut iteracion = 0;
+    while iteracion < num_iteraciones {
         let mut exitos_restantes = max_exitos;
         for _j in 0..max_vecinos {
-            if exitos_restantes == 0 { break; }
+            if exitos_restantes == 0 || evaluador.agotado() { break; }
             let c = rng.gen_range(0, n_caracteristicas);  // Escogemos una característica
             let nueva_solucion = vecino(&solucion_actual.0, c, rng);
-            let ev = evaluar(&entrenamiento, &nueva_solucion);
+            let ev = problema.evaluar(evaluador, &nueva_solucion);
             let diferencia = solucion_actual.1 - ev;  // Si es negativa, la nueva solución es mejor (mayor evaluación)
             if diferencia < 0.0 || rng.gen::<f64>() <= (-diferencia / temperatura).exp() {  // K = 1.0
                 exitos_restantes -= 1;
@@ -95,10 +106,13 @@ pub fn simulated_annealing_general<Trng: Rng>(entrenamiento: &[Dato], gen_inicia
                     mejor_solucion = solucion_actual.clone();
                 }
             }
+            traza::registrar(evaluador, solucion_actual.1, mejor_solucion.1, Some(temperatura), None);
         }
 
-        if exitos_restantes == max_exitos { break; }  // Si no hay mejora en una iteración, terminamos
+        if exitos_restantes == max_exitos || evaluador.agotado() { break; }  // Si no hay mejora en una iteración o no queda presupuesto, terminamos
 
+        iteracion += 1;
+        num_iteraciones = iteraciones_estimadas(max_iteraciones, iteracion, evaluador.fraccion_tiempo());
         temperatura = enfriamiento(temperatura, t_inicial, t_final, num_iteraciones);
     }
 
@@ -108,60 +122,70 @@ pub fn simulated_annealing_general<Trng: Rng>(entrenamiento: &[Dato], gen_inicia
 // Algoritmo de enfriamiento simulado partiendo de una solución aleatoria
 // Utiliza el operador de vecino de la práctica 1 como operador de mutación
 //   y un esquema de enfriamiento de Cauchy modificado
-pub fn es<Trng: Rng>(entrenamiento: &[Dato], rng: &mut Trng) -> Vec<f64> {
-    simulated_annealing_general(&entrenamiento, &vector_au, &vecino_bl, &enfriamiento_cauchy, rng)
+pub fn es<Trng: Rng>(entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    simulated_annealing_general(&PonderacionCaracteristicas::nueva(entrenamiento), &pesos_aleatorios, &vecino_bl, &enfriamiento_cauchy, evaluador, rng)
 }
 
 
 // Algoritmo de búsqueda local iterativa general
-// Recibe el procedimiento con el que se elige una solución inicial,
-//   el operador de mutación y el procedimiento de búsqueda
-pub fn iterated_local_search_general<Trng: Rng>(entrenamiento: &[Dato], gen_inicial: &Fn(&[Dato], &mut Trng) -> Vec<f64>, mutacion_brusca: &Fn(&[f64], &mut Trng) -> Vec<f64>, bl: &Fn(&[Dato], &[f64], &mut Trng) -> Vec<f64>, rng: &mut Trng) -> Vec<f64> {
-    let solucion = gen_inicial(&entrenamiento, rng);
-    let solucion_bl = bl(&entrenamiento, &solucion, rng);
-    let mut mejor_solucion = (solucion_bl.clone(), evaluar(&entrenamiento, &solucion_bl));
-    for _i in 1..(MAX_EVALUACIONES / MAX_EVALUACIONES_BL) {
+// Recibe el problema a optimizar (ver problema.rs), el procedimiento con el que se elige una solución inicial,
+//   el operador de mutación, el procedimiento de búsqueda y el evaluador de las soluciones
+pub fn iterated_local_search_general<Trng: Rng>(problema: &dyn Problema, gen_inicial: &GenInicial<Trng>, mutacion_brusca: &dyn Fn(&[f64], &mut Trng) -> Vec<f64>, bl: &BusquedaLocal<Trng>, evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    let _ambito = traza::entrar();
+    let solucion = gen_inicial(problema, rng);
+    let solucion_bl = bl(problema, &solucion, evaluador, rng);
+    let mut mejor_solucion = (solucion_bl.clone(), problema.evaluar(evaluador, &solucion_bl));
+    traza::registrar(evaluador, mejor_solucion.1, mejor_solucion.1, None, None);
+    while !evaluador.agotado() {
         let solucion_mutada = mutacion_brusca(&mejor_solucion.0, rng);
-        let nueva_solucion = bl(&entrenamiento, &solucion_mutada, rng);
-        let evaluacion = evaluar(&entrenamiento, &nueva_solucion);
+        let nueva_solucion = bl(problema, &solucion_mutada, evaluador, rng);
+        let evaluacion = problema.evaluar(evaluador, &nueva_solucion);
         if evaluacion > mejor_solucion.1 {
             mejor_solucion = (nueva_solucion.clone(), evaluacion);
         }
-    } // Se hacen 1001 evaluaciones en cada bucle: se evalúa una vez más para puntuar el resultado de la BL
+        traza::registrar(evaluador, evaluacion, mejor_solucion.1, None, None);
+    } // Se repite hasta agotar el presupuesto, que puede acabarse en mitad de una BL (ver presupuesto.rs)
 
     mejor_solucion.0
 }
 
 // Algoritmo de búsqueda local reiterada con la búsqueda local de la práctica 1
 //   y el operador de mutación brusco descrito en este guion
-pub fn ils<Trng: Rng>(entrenamiento: &[Dato], rng: &mut Trng) -> Vec<f64> {
-    iterated_local_search_general(&entrenamiento, &vector_au, &vecino_ils, &bl_ils, rng)
+pub fn ils<Trng: Rng>(entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    iterated_local_search_general(&PonderacionCaracteristicas::nueva(entrenamiento), &pesos_aleatorios, &vecino_ils, &bl_ils, evaluador, rng)
 }
 
 // Algoritmo de evolución diferencial general
-// Recibe el procedimiento con el que se genera cada elemento de
-//   la población inicial y el operador de evolución diferencial concreto
-pub fn differential_evolution_general<Trng: Rng>(entrenamiento: &[Dato], gen_inicial: &Fn(&[Dato], &mut Trng) -> Vec<f64>, operador_de: &Fn(&PoblacionDE, usize, &mut Trng) -> (Option<f64>, Vec<f64>), rng: &mut Trng) -> Vec<f64> {
+// Recibe el problema a optimizar (ver problema.rs), el procedimiento con el que se genera cada elemento de
+//   la población inicial, el operador de evolución diferencial concreto y el evaluador de las soluciones
+pub fn differential_evolution_general<Trng: Rng>(problema: &dyn Problema, gen_inicial: &GenInicial<Trng>, operador_de: &OperadorDE<Trng>, evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    let param = parametros();
+    let _ambito = traza::entrar();
+    // Informa del estado de la población: su media, su mejor cromosoma y su diversidad
+    let registrar = |poblacion: &PoblacionDE| if traza::activa() {
+        let media = poblacion.0.iter().map(|c| c.0).sum::<f64>() / poblacion.0.len() as f64;
+        let cromosomas: Vec<(f64, &[f64])> = poblacion.0.iter().map(|c| (c.0, c.1.as_slice())).collect();
+        traza::registrar(evaluador, media, poblacion.0[poblacion.1].0, None, Some(traza::diversidad(problema, &cromosomas)));
+    };
     // Inicializamos y evaluamos la población, guardando el índice del mejor
     let mut poblacion: PoblacionDE = (vec![], 0);
-    for i in 0..TAMANO_DE {
-        let nuevo_cromosoma = gen_inicial(&entrenamiento, rng);
-        let ev = evaluar(&entrenamiento, &nuevo_cromosoma);
+    for i in 0..param.tamano_de {
+        let nuevo_cromosoma = gen_inicial(problema, rng);
+        let ev = problema.evaluar(evaluador, &nuevo_cromosoma);
         poblacion.0.push((ev, nuevo_cromosoma));
         if ev > poblacion.0[poblacion.1].0 {
             poblacion.1 = i;  // Si el nuevo es mejor que el anterior mejor, pasa a ser el mejor
         }
     }
-    let mut evaluaciones_restantes = MAX_EVALUACIONES - TAMANO_DE;
+    registrar(&poblacion);
 
-    while evaluaciones_restantes > 0 {
+    while !evaluador.agotado() {
         let mut nueva_poblacion = poblacion.clone();
-        for i in 0..TAMANO_DE {
-            if evaluaciones_restantes == 0 { break; }
-            let nuevo_c = operador_de(&poblacion, i, rng);
+        for i in 0..param.tamano_de {
+            if evaluador.agotado() { break; }
+            let nuevo_c = operador_de(problema, &poblacion, i, rng);
             if nuevo_c.0.is_none() {
-                let ev = evaluar(&entrenamiento, &nuevo_c.1);
-                evaluaciones_restantes -= 1;
+                let ev = problema.evaluar(evaluador, &nuevo_c.1);
                 if ev > poblacion.0[i].0 {
                     nueva_poblacion.0[i] = (ev, nuevo_c.1);
                     if ev > nueva_poblacion.0[nueva_poblacion.1].0 {
@@ -171,6 +195,7 @@ pub fn differential_evolution_general<Trng: Rng>(entrenamiento: &[Dato], gen_ini
             }
         }
         swap(&mut poblacion, &mut nueva_poblacion);
+        registrar(&poblacion);
     }
 
     // Devolvemos el mejor cromosoma encontrado
@@ -199,7 +224,8 @@ macro_rules! aleatorio_distinto {
 }
 
 // Operación de mutación del algoritmo DE/rand/1
-pub fn op_rand_1<Trng: Rng>(poblacion: &PoblacionDE, actual: usize, rng: &mut Trng) -> (Option<f64>, Vec<f64>) {
+// Las componentes mutadas se truncan a los límites del problema
+pub fn op_rand_1<Trng: Rng>(problema: &dyn Problema, poblacion: &PoblacionDE, actual: usize, rng: &mut Trng) -> (Option<f64>, Vec<f64>) {
     let tam_poblacion = poblacion.0.len();
     let num_cromosomas = poblacion.0[0].1.len();
 
@@ -207,16 +233,14 @@ pub fn op_rand_1<Trng: Rng>(poblacion: &PoblacionDE, actual: usize, rng: &mut Tr
     let p2 = aleatorio_distinto!(rng, tam_poblacion; actual, p1);
     let p3 = aleatorio_distinto!(rng, tam_poblacion; actual, p1, p2);
 
+    let (cr, f) = (parametros().de_cr, parametros().de_f);
     let mut nuevo_cromosoma = (Some(poblacion.0[actual].0), poblacion.0[actual].1.to_vec());
+    let j_rand = rng.gen_range(0, num_cromosomas);  // Componente que se cruza siempre, para que el vector de prueba sea nuevo
     for n in 0..num_cromosomas {
-        if rng.gen::<f64>() < DE_CR {
+        if n == j_rand || rng.gen::<f64>() < cr {
             nuevo_cromosoma.0 = None;
-            nuevo_cromosoma.1[n] = poblacion.0[p1].1[n] + DE_F * (poblacion.0[p2].1[n] - poblacion.0[p3].1[n]);
-            if nuevo_cromosoma.1[n] < 0.0 {
-                nuevo_cromosoma.1[n] = 0.0;
-            } else if nuevo_cromosoma.1[n] > 1.0 {
-                nuevo_cromosoma.1[n] = 1.0;
-            }
+            nuevo_cromosoma.1[n] = poblacion.0[p1].1[n] + f * (poblacion.0[p2].1[n] - poblacion.0[p3].1[n]);
+            nuevo_cromosoma.1[n] = acotar(nuevo_cromosoma.1[n], problema.limites(n));
         }
     }
 
@@ -224,23 +248,22 @@ pub fn op_rand_1<Trng: Rng>(poblacion: &PoblacionDE, actual: usize, rng: &mut Tr
 }
 
 // Operación de mutación del algoritmo DE/current-to-best/1
-pub fn op_ctb_1<Trng: Rng>(poblacion: &PoblacionDE, actual: usize, rng: &mut Trng) -> (Option<f64>, Vec<f64>) {
+// Las componentes mutadas se truncan a los límites del problema
+pub fn op_ctb_1<Trng: Rng>(problema: &dyn Problema, poblacion: &PoblacionDE, actual: usize, rng: &mut Trng) -> (Option<f64>, Vec<f64>) {
     let tam_poblacion = poblacion.0.len();
     let num_cromosomas = poblacion.0[0].1.len();
 
     let p1 = aleatorio_distinto!(rng, tam_poblacion; actual, poblacion.1);
     let p2 = aleatorio_distinto!(rng, tam_poblacion; actual, poblacion.1, p1);
 
+    let (cr, f) = (parametros().de_cr, parametros().de_f);
     let mut nuevo_cromosoma = (Some(poblacion.0[actual].0), poblacion.0[actual].1.to_vec());
+    let j_rand = rng.gen_range(0, num_cromosomas);  // Componente que se cruza siempre, para que el vector de prueba sea nuevo
     for n in 0..num_cromosomas {
-        if rng.gen::<f64>() < DE_CR {
+        if n == j_rand || rng.gen::<f64>() < cr {
             nuevo_cromosoma.0 = None;
-            nuevo_cromosoma.1[n] += DE_F * (poblacion.0[poblacion.1].1[n] - nuevo_cromosoma.1[n] + poblacion.0[p1].1[n] - poblacion.0[p2].1[n]);
-            if nuevo_cromosoma.1[n] < 0.0 {
-                nuevo_cromosoma.1[n] = 0.0;
-            } else if nuevo_cromosoma.1[n] > 1.0 {
-                nuevo_cromosoma.1[n] = 1.0;
-            }
+            nuevo_cromosoma.1[n] += f * (poblacion.0[poblacion.1].1[n] - nuevo_cromosoma.1[n] + poblacion.0[p1].1[n] - poblacion.0[p2].1[n]);
+            nuevo_cromosoma.1[n] = acotar(nuevo_cromosoma.1[n], problema.limites(n));
         }
     }
 
@@ -248,13 +271,13 @@ pub fn op_ctb_1<Trng: Rng>(poblacion: &PoblacionDE, actual: usize, rng: &mut Trn
 }
 
 // Algoritmo de evolución diferencial con el operador DE/rand/1
-pub fn de_rand_1<Trng: Rng>(entrenamiento: &[Dato], rng: &mut Trng) -> Vec<f64> {
-    differential_evolution_general(&entrenamiento, &vector_au, &op_rand_1, rng)
+pub fn de_rand_1<Trng: Rng>(entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    differential_evolution_general(&PonderacionCaracteristicas::nueva(entrenamiento), &pesos_aleatorios, &op_rand_1, evaluador, rng)
 }
 
 // Algoritmo de evolución diferencial con el operador DE/current-to-best/1
-pub fn de_ctb_1<Trng: Rng>(entrenamiento: &[Dato], rng: &mut Trng) -> Vec<f64> {
-    differential_evolution_general(&entrenamiento, &vector_au, &op_ctb_1, rng)
+pub fn de_ctb_1<Trng: Rng>(entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    differential_evolution_general(&PonderacionCaracteristicas::nueva(entrenamiento), &pesos_aleatorios, &op_ctb_1, evaluador, rng)
 }
 
 
@@ -264,51 +287,103 @@ pub fn de_ctb_1<Trng: Rng>(entrenamiento: &[Dato], rng: &mut Trng) -> Vec<f64> {
 
 // Aplica enfriamiento según un esquema proporcional
 // Después de aplicarse num_iteraciones veces a partir de la temperatura inicial se tiene la final
-fn enfriamiento_proporcional(actual: f64, t_inicial: f64, t_final: f64, num_iteraciones: usize) -> f64 {
+pub fn enfriamiento_proporcional(actual: f64, t_inicial: f64, t_final: f64, num_iteraciones: usize) -> f64 {
     let b = (t_final/t_inicial).powf(1.0 / num_iteraciones as f64); // La idea es que t_inicial * b^num_iteraciones == t_final
     actual*b
 }
 
 // Procedimiento de búsqueda local para ILS con el operador de mutación alternativo propuesto en la práctica 1
-pub fn bl_ils_mut2<Trng: Rng>(entrenamiento: &[Dato], w_base: &[f64], rng: &mut Trng) -> Vec<f64> {
-    busqueda_local_generica_desde(&entrenamiento, &w_base, &vecino_bl_mut2, MAX_EVALUACIONES_BL, MAX_CICLOS_BL, rng)
+pub fn bl_ils_mut2<Trng: Rng>(problema: &dyn Problema, w_base: &[f64], evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    busqueda_local_generica_desde(problema, w_base, &vecino_bl_mut2, parametros().max_evaluaciones_bl_ils, MAX_CICLOS_BL, evaluador, rng)
 }
 
 // Algoritmo de enfriamiento simulado partiendo de una solución aleatoria
 // Utiliza el operador de vecino alternativo propuesto en la práctica 1 como operador de mutación
 //   y un esquema de enfriamiento de Cauchy modificado
-pub fn es_mut2<Trng: Rng>(entrenamiento: &[Dato], rng: &mut Trng) -> Vec<f64> {
-    simulated_annealing_general(&entrenamiento, &vector_au, &vecino_bl_mut2, &enfriamiento_cauchy, rng)
+pub fn es_mut2<Trng: Rng>(entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    simulated_annealing_general(&PonderacionCaracteristicas::nueva(entrenamiento), &pesos_aleatorios, &vecino_bl_mut2, &enfriamiento_cauchy, evaluador, rng)
 }
 
 // Algoritmo de enfriamiento simulado partiendo de una solución aleatoria
 // Utiliza el operador de vecino de la práctica 1 como operador de mutación
 //   y un esquema de enfriamiento proporcional, de convergencia más lenta
-pub fn es_prop<Trng: Rng>(entrenamiento: &[Dato], rng: &mut Trng) -> Vec<f64> {
-    simulated_annealing_general(&entrenamiento, &vector_au, &vecino_bl, &enfriamiento_proporcional, rng)
+pub fn es_prop<Trng: Rng>(entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    simulated_annealing_general(&PonderacionCaracteristicas::nueva(entrenamiento), &pesos_aleatorios, &vecino_bl, &enfriamiento_proporcional, evaluador, rng)
 }
 
 // Algoritmo de enfriamiento simulado partiendo de una solución aleatoria
 // Utiliza el operador de vecino alternativo propuesto en la práctica 1 como operador de mutación
 //   y un esquema de enfriamiento proporcional, de convergencia más lenta
-pub fn es_prop_mut2<Trng: Rng>(entrenamiento: &[Dato], rng: &mut Trng) -> Vec<f64> {
-    simulated_annealing_general(&entrenamiento, &vector_au, &vecino_bl_mut2, &enfriamiento_proporcional, rng)
+pub fn es_prop_mut2<Trng: Rng>(entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    simulated_annealing_general(&PonderacionCaracteristicas::nueva(entrenamiento), &pesos_aleatorios, &vecino_bl_mut2, &enfriamiento_proporcional, evaluador, rng)
 }
 
 // Algoritmo de búsqueda local reiterada con la búsqueda local alternativa propuesta en la práctica 1
 //   y el operador de mutación brusco descrito en este guion
-pub fn ils_mut2<Trng: Rng>(entrenamiento: &[Dato], rng: &mut Trng) -> Vec<f64> {
-    iterated_local_search_general(&entrenamiento, &vector_au, &vecino_ils, &bl_ils_mut2, rng)
+pub fn ils_mut2<Trng: Rng>(entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    iterated_local_search_general(&PonderacionCaracteristicas::nueva(entrenamiento), &pesos_aleatorios, &vecino_ils, &bl_ils_mut2, evaluador, rng)
 }
 
 // Algoritmo de búsqueda local reiterada con la búsqueda local por afinidad propuesta en la práctica 2
 //   y el operador de mutación brusco descrito en este guion
-pub fn ils_afinidad<Trng: Rng>(entrenamiento: &[Dato], rng: &mut Trng) -> Vec<f64> {
-    iterated_local_search_general(&entrenamiento, &vector_au, &vecino_ils, &afinidad_optima, rng)
+pub fn ils_afinidad<Trng: Rng>(entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    iterated_local_search_general(&PonderacionCaracteristicas::nueva(entrenamiento), &pesos_aleatorios, &vecino_ils, &|_: &dyn Problema, w: &[f64], evaluador: &Evaluador, rng: &mut Trng| afinidad_optima(entrenamiento, w, evaluador, rng), evaluador, rng)
 }
 
 // Algoritmo de búsqueda local reiterada con la búsqueda local por afinidad propuesta en la práctica 2,
 //   el operador de mutación brusco descrito en este guion y RELIEF como algoritmo de solución inicial
-pub fn ils_afinidad_relief<Trng: Rng>(entrenamiento: &[Dato], rng: &mut Trng) -> Vec<f64> {
-    iterated_local_search_general(&entrenamiento, &relief, &vecino_ils, &afinidad_optima, rng)
+pub fn ils_afinidad_relief<Trng: Rng>(entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    iterated_local_search_general(&PonderacionCaracteristicas::nueva(entrenamiento), &|_: &dyn Problema, rng: &mut Trng| relief(entrenamiento, evaluador, rng), &vecino_ils, &|_: &dyn Problema, w: &[f64], evaluador: &Evaluador, rng: &mut Trng| afinidad_optima(entrenamiento, w, evaluador, rng), evaluador, rng)
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parametros::{fijar_parametros, Parametros};
+    use presupuesto::pruebas::evaluador;
+    use problema;
+    use problema::pruebas::Parabola;
+    use rand::{Isaac64Rng, SeedableRng};
+
+    #[test]
+    fn de_cruza_al_menos_una_componente() {
+        // Con tasa de cruce 0 ningún vector de prueba era nuevo, así que no se evaluaba nada y no terminaba
+        fijar_parametros(Parametros { de_cr: 0.0, ..Parametros::default() });
+        let parabola = Parabola::nueva(2, (-1.0, 1.0));
+        let mut rng = Isaac64Rng::from_seed(&[1]);
+        let poblacion: PoblacionDE = ((0..4).map(|_| (0.0, problema::solucion_aleatoria(&parabola, &mut rng))).collect(), 0);
+        for operador in &[op_rand_1::<Isaac64Rng>, op_ctb_1::<Isaac64Rng>] {
+            let (evaluacion, prueba) = operador(&parabola, &poblacion, 1, &mut rng);
+            assert_eq!(evaluacion, None);
+            assert_eq!(prueba.iter().zip(&poblacion.0[1].1).filter(|(a, b)| a != b).count(), 1);
+        }
+        let e = evaluador(100);
+        differential_evolution_general(&parabola, &problema::solucion_aleatoria, &op_rand_1, &e, &mut rng);
+        assert_eq!(e.usadas(), 100);
+        fijar_parametros(Parametros::default());
+    }
+
+    #[test]
+    fn estima_las_iteraciones_por_el_tiempo() {
+        assert_eq!(iteraciones_estimadas(1000, 10, None), 1000);
+        assert_eq!(iteraciones_estimadas(1000, 10, Some(0.0)), 1000);
+        assert_eq!(iteraciones_estimadas(1000, 10, Some(0.25)), 40);
+        assert_eq!(iteraciones_estimadas(20, 10, Some(0.25)), 20);
+    }
+
+    #[test]
+    fn el_enfriamiento_solo_con_tiempo_llega_a_enfriar() {
+        // Sin máximo de evaluaciones, si cada iteración consume 1/64 del tiempo se estiman 64 iteraciones
+        //   y tras ellas se llega a la temperatura final
+        let (t_inicial, t_final) = (10.0, 0.001);
+        let mut temperatura = t_inicial;
+        for iteracion in 1..=64 {
+            let num_iteraciones = iteraciones_estimadas(usize::MAX, iteracion, Some(iteracion as f64 / 64.0));
+            assert_eq!(num_iteraciones, 64);
+            temperatura = enfriamiento_cauchy(temperatura, t_inicial, t_final, num_iteraciones);
+        }
+        assert!((temperatura - t_final).abs() < 1e-9, "{} no es {}", temperatura, t_final);
+    }
 }
@@ -0,0 +1,186 @@
+use knn::Dato;
+use problema::{GenInicial, Problema, PonderacionCaracteristicas, Vecino};
+use parametros::parametros;
+use presupuesto::Evaluador;
+use rand::Rng;
+use traza;
+
+use funciones_practica1::*;    // Recuperamos los operadores de vecino y las soluciones aleatorias
+
+
+
+// Búsqueda tabú
+// En cada iteración se muestrea una lista de candidatos con el operador de vecino y se pasa al mejor de
+//   ellos, aunque empeore la solución actual, salvo que su movimiento sea tabú
+// Los atributos tabú son pares (característica, sentido del cambio): al mover una característica en un
+//   sentido, moverla en el contrario queda prohibido durante la tenencia tabú, para no deshacer el movimiento
+// Criterio de aspiración: un movimiento tabú se admite si lleva a una solución mejor que la mejor encontrada
+// La memoria a largo plazo cuenta las veces que se mueve cada característica y las que ese movimiento
+//   mejora la solución actual. Tras varias iteraciones sin mejorar la mejor solución se alterna:
+//   - Intensificación: se vuelve a la mejor solución y se eligen más a menudo las características cuyos
+//     movimientos más han mejorado
+//   - Diversificación: se parte de la mejor solución con valores aleatorios en la mitad de las características
+//     que menos se han movido, y se vuelven a elegir todas por igual
+// Los tamaños de la lista de candidatos y de la tenencia y el estancamiento son parámetros (ver parametros.rs)
+
+
+// Elige una posición con probabilidad proporcional a su peso
+fn ruleta<Trng: Rng>(pesos: &[f64], rng: &mut Trng) -> usize {
+    let mut r = rng.gen::<f64>() * pesos.iter().sum::<f64>();
+    for (i, p) in pesos.iter().enumerate() {
+        if r < *p {
+            return i;
+        }
+        r -= *p;
+    }
+    pesos.len() - 1
+}
+
+// Algoritmo de búsqueda tabú general
+// Recibe el problema a optimizar (ver problema.rs), el procedimiento con el que se elige una solución inicial,
+//   el operador de vecino, el evaluador de las soluciones y un RNG
+// Termina al agotar el presupuesto del evaluador (ver presupuesto.rs)
+// Las iteraciones en las que ningún candidato cambia la solución cuentan como iteraciones sin mejora, para que
+//   el estancamiento la diversifique (y evalúe) aunque el operador de vecino no la mueva
+pub fn tabu_search_general<Trng: Rng>(problema: &dyn Problema, gen_inicial: &GenInicial<Trng>, vecino: &Vecino<Trng>, evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    let param = parametros();
+    let n_caracteristicas = problema.dimension();
+    let tenencia = ((param.tenencia_tabu * n_caracteristicas as f64).round() as usize).max(1);
+
+    let _ambito = traza::entrar();
+    let solucion_inicial = gen_inicial(problema, rng);
+    let mut solucion_actual = (solucion_inicial.clone(), problema.evaluar(evaluador, &solucion_inicial));
+    let mut mejor_solucion = solucion_actual.clone();
+    traza::registrar(evaluador, solucion_actual.1, mejor_solucion.1, None, None);
+
+    let mut tabu_hasta = vec![[0usize; 2]; n_caracteristicas];  // Iteración hasta la que es tabú cada atributo (característica, sentido)
+    let mut movimientos = vec![0usize; n_caracteristicas];      // Memoria a largo plazo: veces que se ha movido cada característica
+    let mut mejoras = vec![0usize; n_caracteristicas];          // y veces que ese movimiento ha mejorado la solución actual
+    let mut preferencias: Option<Vec<f64>> = None;  // Peso con el que se elige cada característica al intensificar
+    let mut intensificar = true;    // Estrategia que se aplica en el próximo estancamiento
+    let mut iteracion = 0;
+    let mut sin_mejora = 0;         // Iteraciones desde que se mejoró la mejor solución
+
+    while !evaluador.agotado() {
+        iteracion += 1;
+
+        // Muestreamos la lista de candidatos, quedándonos con el mejor admisible y el mejor tabú
+        let mut mejor_admisible: Option<(Vec<f64>, f64, usize, usize)> = None;  // (solución, evaluación, característica, sentido)
+        let mut mejor_tabu: Option<(Vec<f64>, f64, usize, usize)> = None;
+        for _j in 0..param.vecinos_tabu {
+            if evaluador.agotado() { break; }
+            let c = match preferencias {
+                Some(ref p) => ruleta(p, rng),
+                None => rng.gen_range(0, n_caracteristicas),
+            };
+            let candidato = vecino(&solucion_actual.0, c, rng);
+            if candidato[c] == solucion_actual.0[c] { continue; }  // Si el vecino no cambia la característica, no hay movimiento
+            let sentido = if candidato[c] > solucion_actual.0[c] { 0 } else { 1 };
+            let ev = problema.evaluar(evaluador, &candidato);
+            let admisible = tabu_hasta[c][sentido] < iteracion || ev > mejor_solucion.1;  // Criterio de aspiración
+            let mejor = if admisible { &mut mejor_admisible } else { &mut mejor_tabu };
+            if mejor.as_ref().is_none_or(|m| ev > m.1) {
+                *mejor = Some((candidato, ev, c, sentido));
+            }
+        }
+
+        // Si todos los candidatos son tabú, se toma el mejor de ellos para no quedarse sin movimiento
+        match mejor_admisible.or(mejor_tabu) {
+            Some((candidato, ev, c, sentido)) => {
+                tabu_hasta[c][1 - sentido] = iteracion + tenencia;  // Prohibimos deshacer el movimiento
+                movimientos[c] += 1;
+                if ev > solucion_actual.1 {
+                    mejoras[c] += 1;
+                }
+                solucion_actual = (candidato, ev);
+                if solucion_actual.1 > mejor_solucion.1 {
+                    mejor_solucion = solucion_actual.clone();
+                    sin_mejora = 0;
+                } else {
+                    sin_mejora += 1;
+                }
+                traza::registrar(evaluador, solucion_actual.1, mejor_solucion.1, None, None);
+            },
+            None => sin_mejora += 1,    // Ningún candidato se ha movido
+        }
+
+        // Si la búsqueda se ha estancado, intensificamos o diversificamos según corresponda
+        if sin_mejora >= param.estancamiento_tabu && !evaluador.agotado() {
+            if intensificar {
+                preferencias = Some(mejoras.iter().map(|m| (*m + 1) as f64).collect());
+                solucion_actual = mejor_solucion.clone();
+            } else {
+                let mut menos_movidas: Vec<usize> = (0..n_caracteristicas).collect();
+                menos_movidas.sort_by_key(|i| movimientos[*i]);
+                let mut nueva_solucion = mejor_solucion.0.clone();
+                for i in menos_movidas.into_iter().take(n_caracteristicas.div_ceil(2)) {
+                    let (inferior, superior) = problema.limites(i);
+                    nueva_solucion[i] = inferior + rng.gen::<f64>()*(superior - inferior);
+                }
+                problema.normalizar(&mut nueva_solucion);
+                solucion_actual = (nueva_solucion.clone(), problema.evaluar(evaluador, &nueva_solucion));
+                preferencias = None;
+            }
+            for t in tabu_hasta.iter_mut() {
+                *t = [0, 0];
+            }
+            intensificar = !intensificar;
+            sin_mejora = 0;
+        }
+    }
+
+    mejor_solucion.0
+}
+
+// Búsqueda tabú partiendo de una solución aleatoria con el operador de vecino de la práctica 1
+pub fn busqueda_tabu<Trng: Rng>(entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    tabu_search_general(&PonderacionCaracteristicas::nueva(entrenamiento), &pesos_aleatorios, &vecino_bl, evaluador, rng)
+}
+
+// Búsqueda tabú partiendo de una solución aleatoria con el operador de vecino alternativo
+pub fn busqueda_tabu_mut2<Trng: Rng>(entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Trng) -> Vec<f64> {
+    tabu_search_general(&PonderacionCaracteristicas::nueva(entrenamiento), &pesos_aleatorios, &vecino_bl_mut2, evaluador, rng)
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use json;
+    use parametros::Parametros;
+    use presupuesto::pruebas::evaluador;
+    use problema;
+    use problema::pruebas::Parabola;
+    use rand::{Isaac64Rng, SeedableRng};
+
+    #[test]
+    fn mejora_la_solucion_inicial_con_el_presupuesto() {
+        let e = evaluador(500);
+        let parabola = Parabola::nueva(3, (0.0, 1.0));
+        let mut rng = Isaac64Rng::from_seed(&[3]);
+        let inicial = problema::solucion_aleatoria(&parabola, &mut Isaac64Rng::from_seed(&[3]));
+        let vecino = |x: &[f64], i: usize, rng: &mut Isaac64Rng| problema::mutacion_normal(&parabola, x, i, 0.1, rng);
+        let x = tabu_search_general(&parabola, &problema::solucion_aleatoria, &vecino, &e, &mut rng);
+        assert_eq!(e.usadas(), 500);
+        assert!(parabola.funcion_objetivo(&x) > parabola.funcion_objetivo(&inicial));
+        assert!(parabola.funcion_objetivo(&x) > 0.99);
+    }
+
+    #[test]
+    fn termina_aunque_el_vecino_no_se_mueva() {
+        let e = evaluador(5);
+        let quieto = |x: &[f64], _: usize, _: &mut Isaac64Rng| x.to_vec();
+        let x = tabu_search_general(&Parabola::nueva(3, (0.0, 1.0)), &problema::solucion_aleatoria, &quieto, &e, &mut Isaac64Rng::from_seed(&[3]));
+        // Solo evalúan la solución inicial y las diversificaciones
+        assert_eq!(e.usadas(), 5);
+        assert_eq!(x.len(), 3);
+    }
+
+    #[test]
+    fn la_lista_de_candidatos_no_puede_estar_vacia() {
+        let mut p = Parametros::default();
+        assert!(p.actualizar(&json::analizar(r#"{"vecinos_tabu": 0}"#).unwrap()).is_err());
+        assert!(p.actualizar(&json::analizar(r#"{"vecinos_tabu": 1}"#).unwrap()).is_ok());
+    }
+}
@@ -0,0 +1,387 @@
+// Representación mínima de valores JSON para guardar resultados en archivos legibles por otros programas
+// Se implementa aquí para no añadir dependencias solo por esto: basta con construir los valores,
+//   escribirlos como texto y volver a leer los que escribimos
+
+use std::fmt;
+
+
+// Valor JSON
+#[derive(Clone, Debug, PartialEq)]
+pub enum Valor {
+    Nulo,
+    Booleano(bool),
+    Numero(f64),
+    Texto(String),
+    Lista(Vec<Valor>),
+    Objeto(Vec<(String, Valor)>), // Se conserva el orden de los campos
+}
+
+impl From<f64> for Valor {
+    fn from(x: f64) -> Valor { Valor::Numero(x) }
+}
+
+impl From<usize> for Valor {
+    fn from(x: usize) -> Valor { Valor::Numero(x as f64) }
+}
+
+impl From<i64> for Valor {
+    fn from(x: i64) -> Valor { Valor::Numero(x as f64) }
+}
+
+impl From<bool> for Valor {
+    fn from(x: bool) -> Valor { Valor::Booleano(x) }
+}
+
+impl From<&str> for Valor {
+    fn from(x: &str) -> Valor { Valor::Texto(x.to_string()) }
+}
+
+impl From<String> for Valor {
+    fn from(x: String) -> Valor { Valor::Texto(x) }
+}
+
+impl<T: Clone + Into<Valor>> From<&[T]> for Valor {
+    fn from(x: &[T]) -> Valor { Valor::Lista(x.iter().cloned().map(Into::into).collect()) }
+}
+
+impl<T: Into<Valor>> From<Vec<T>> for Valor {
+    fn from(x: Vec<T>) -> Valor { Valor::Lista(x.into_iter().map(Into::into).collect()) }
+}
+
+// Construye un objeto JSON a partir de pares (nombre, valor)
+// Uso: objeto_json!{ "nombre1" => valor1, "nombre2" => valor2, ... }
+#[macro_export]
+macro_rules! objeto_json {
+    ( $( $nombre:expr => $valor:expr ),* $(,)* ) => {
+        $crate::json::Valor::Objeto(vec![ $( ($nombre.to_string(), $crate::json::Valor::from($valor)) ),* ])
+    };
+}
+
+
+// Escribe un texto entre comillas escapando los caracteres que lo requieren
+fn escribir_texto(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+// Escribe el valor en una sola línea, como se requiere en el formato JSON Lines
+// Los números no finitos no existen en JSON, por lo que se escriben como null
+impl fmt::Display for Valor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Valor::Nulo => write!(f, "null"),
+            Valor::Booleano(b) => write!(f, "{}", b),
+            Valor::Numero(x) => if x.is_finite() { write!(f, "{}", x) } else { write!(f, "null") },
+            Valor::Texto(s) => escribir_texto(f, s),
+            Valor::Lista(v) => {
+                write!(f, "[")?;
+                for (i, x) in v.iter().enumerate() {
+                    if i > 0 { write!(f, ",")?; }
+                    write!(f, "{}", x)?;
+                }
+                write!(f, "]")
+            },
+            Valor::Objeto(campos) => {
+                write!(f, "{{")?;
+                for (i, (nombre, x)) in campos.iter().enumerate() {
+                    if i > 0 { write!(f, ",")?; }
+                    escribir_texto(f, nombre)?;
+                    write!(f, ":{}", x)?;
+                }
+                write!(f, "}}")
+            },
+        }
+    }
+}
+
+
+// Acceso a los campos de valores leídos de un archivo
+impl Valor {
+    // Devuelve el valor de un campo si el valor es un objeto que lo contiene
+    pub fn campo(&self, nombre: &str) -> Option<&Valor> {
+        match self {
+            Valor::Objeto(campos) => campos.iter().find(|c| c.0 == nombre).map(|c| &c.1),
+            _ => None,
+        }
+    }
+
+    pub fn como_numero(&self) -> Option<f64> {
+        match self {
+            Valor::Numero(x) => Some(*x),
+            Valor::Nulo => Some(f64::NAN),  // Así se escriben los números no finitos
+            _ => None,
+        }
+    }
+
+    pub fn como_texto(&self) -> Option<&str> {
+        match self {
+            Valor::Texto(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn como_booleano(&self) -> Option<bool> {
+        match self {
+            Valor::Booleano(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn como_lista(&self) -> Option<&[Valor]> {
+        match self {
+            Valor::Lista(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+
+// Lee un valor JSON de un texto, que no debe contener nada más que el valor y espacios
+pub fn analizar(texto: &str) -> Result<Valor, String> {
+    let mut lector = Lector { texto: texto.as_bytes(), pos: 0 };
+    let valor = lector.valor()?;
+    lector.saltar_espacios();
+    if lector.pos < lector.texto.len() {
+        return Err(format!("contenido inesperado en la posición {}", lector.pos));
+    }
+    Ok(valor)
+}
+
+// Analizador descendente recursivo sobre los bytes del texto
+struct Lector<'a> {
+    texto: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Lector<'a> {
+    fn saltar_espacios(&mut self) {
+        while self.pos < self.texto.len() && (self.texto[self.pos] as char).is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn siguiente(&self) -> Option<u8> {
+        self.texto.get(self.pos).cloned()
+    }
+
+    fn esperar(&mut self, c: u8) -> Result<(), String> {
+        self.saltar_espacios();
+        if self.siguiente() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!("se esperaba '{}' en la posición {}", c as char, self.pos))
+        }
+    }
+
+    fn palabra(&mut self, palabra: &str, valor: Valor) -> Result<Valor, String> {
+        if self.texto[self.pos..].starts_with(palabra.as_bytes()) {
+            self.pos += palabra.len();
+            Ok(valor)
+        } else {
+            Err(format!("valor no válido en la posición {}", self.pos))
+        }
+    }
+
+    fn valor(&mut self) -> Result<Valor, String> {
+        self.saltar_espacios();
+        match self.siguiente() {
+            Some(b'{') => self.objeto(),
+            Some(b'[') => self.lista(),
+            Some(b'"') => self.texto().map(Valor::Texto),
+            Some(b't') => self.palabra("true", Valor::Booleano(true)),
+            Some(b'f') => self.palabra("false", Valor::Booleano(false)),
+            Some(b'n') => self.palabra("null", Valor::Nulo),
+            Some(_) => self.numero(),
+            None => Err("fin del texto inesperado".to_string()),
+        }
+    }
+
+    fn objeto(&mut self) -> Result<Valor, String> {
+        self.esperar(b'{')?;
+        let mut campos = Vec::new();
+        self.saltar_espacios();
+        if self.siguiente() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Valor::Objeto(campos));
+        }
+        loop {
+            self.saltar_espacios();
+            let nombre = self.texto()?;
+            self.esperar(b':')?;
+            campos.push((nombre, self.valor()?));
+            self.saltar_espacios();
+            match self.siguiente() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => { self.pos += 1; return Ok(Valor::Objeto(campos)) },
+                _ => return Err(format!("se esperaba ',' o '}}' en la posición {}", self.pos)),
+            }
+        }
+    }
+
+    fn lista(&mut self) -> Result<Valor, String> {
+        self.esperar(b'[')?;
+        let mut elementos = Vec::new();
+        self.saltar_espacios();
+        if self.siguiente() == Some(b']') {
+            self.pos += 1;
+            return Ok(Valor::Lista(elementos));
+        }
+        loop {
+            elementos.push(self.valor()?);
+            self.saltar_espacios();
+            match self.siguiente() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => { self.pos += 1; return Ok(Valor::Lista(elementos)) },
+                _ => return Err(format!("se esperaba ',' o ']' en la posición {}", self.pos)),
+            }
+        }
+    }
+
+    fn texto(&mut self) -> Result<String, String> {
+        self.esperar(b'"')?;
+        let mut bytes = Vec::new();
+        loop {
+            match self.siguiente() {
+                None => return Err("texto sin cerrar".to_string()),
+                Some(b'"') => { self.pos += 1; break },
+                Some(b'\\') => {
+                    let escapado = self.texto.get(self.pos + 1).cloned();
+                    self.pos += 2;
+                    match escapado {
+                        Some(b'"') => bytes.push(b'"'),
+                        Some(b'\\') => bytes.push(b'\\'),
+                        Some(b'/') => bytes.push(b'/'),
+                        Some(b'n') => bytes.push(b'\n'),
+                        Some(b'r') => bytes.push(b'\r'),
+                        Some(b't') => bytes.push(b'\t'),
+                        Some(b'b') => bytes.push(8),
+                        Some(b'f') => bytes.push(12),
+                        Some(b'u') => {
+                            let mut codigo = self.codigo_unicode()?;
+                            // Los caracteres fuera del plano básico se escriben como un par de sustitutos
+                            if (0xd800..0xdc00).contains(&codigo) && self.texto[self.pos..].starts_with(b"\\u") {
+                                let inicio = self.pos;
+                                self.pos += 2;
+                                let bajo = self.codigo_unicode()?;
+                                if (0xdc00..0xe000).contains(&bajo) {
+                                    codigo = 0x10000 + ((codigo - 0xd800) << 10) + (bajo - 0xdc00);
+                                } else {
+                                    self.pos = inicio;  // No forma un par: se lee por separado
+                                }
+                            }
+                            let c = ::std::char::from_u32(codigo).unwrap_or('\u{fffd}');
+                            bytes.extend_from_slice(c.to_string().as_bytes());
+                        },
+                        _ => return Err(format!("secuencia de escape no válida en la posición {}", self.pos - 1)),
+                    }
+                },
+                Some(c) => { bytes.push(c); self.pos += 1 },
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| "texto no válido en UTF-8".to_string())
+    }
+
+    // Lee los cuatro dígitos hexadecimales de una secuencia \\u
+    fn codigo_unicode(&mut self) -> Result<u32, String> {
+        let codigo = self.texto.get(self.pos..self.pos + 4)
+            .and_then(|h| ::std::str::from_utf8(h).ok())
+            .and_then(|h| u32::from_str_radix(h, 16).ok())
+            .ok_or_else(|| format!("secuencia \\u no válida en la posición {}", self.pos))?;
+        self.pos += 4;
+        Ok(codigo)
+    }
+
+    fn numero(&mut self) -> Result<Valor, String> {
+        let inicio = self.pos;
+        while let Some(c) = self.siguiente() {
+            if c.is_ascii_digit() || c == b'-' || c == b'+' || c == b'.' || c == b'e' || c == b'E' {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        ::std::str::from_utf8(&self.texto[inicio..self.pos]).ok()
+            .and_then(|s| s.parse().ok())
+            .map(Valor::Numero)
+            .ok_or_else(|| format!("valor no válido en la posición {}", inicio))
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Escribe un valor y lo vuelve a leer
+    fn ida_y_vuelta(v: &Valor) -> Valor {
+        analizar(&v.to_string()).unwrap()
+    }
+
+    #[test]
+    fn textos_con_escapes() {
+        let texto = "comillas \" barra \\ / salto\nretorno\rtab\tcontrol\u{1}ñ€😀";
+        let v = Valor::from(texto);
+        assert_eq!(v.to_string(), "\"comillas \\\" barra \\\\ / salto\\nretorno\\rtab\\tcontrol\\u0001ñ€😀\"");
+        assert_eq!(ida_y_vuelta(&v), v);
+        assert_eq!(analizar(r#""\/\b\f""#), Ok(Valor::from("/\u{8}\u{c}")));
+    }
+
+    #[test]
+    fn secuencias_unicode() {
+        assert_eq!(analizar(r#""\u00f1\u20AC""#), Ok(Valor::from("ñ€")));
+        assert_eq!(analizar(r#""\ud83d\ude00""#), Ok(Valor::from("😀")));
+        // Un sustituto sin pareja no es un carácter válido
+        assert_eq!(analizar(r#""\ud83dx""#), Ok(Valor::from("\u{fffd}x")));
+        assert_eq!(analizar(r#""\ud83d\u0041""#), Ok(Valor::from("\u{fffd}A")));
+        assert!(analizar(r#""\u12""#).is_err());
+        assert!(analizar(r#""\u12g4""#).is_err());
+    }
+
+    #[test]
+    fn objetos_anidados() {
+        let v = objeto_json!{
+            "nombre" => "prueba",
+            "vacio" => Valor::Objeto(Vec::new()),
+            "lista" => vec![Valor::from(1.0), Valor::Nulo, Valor::from(true), Valor::Lista(Vec::new())],
+            "interior" => objeto_json!{ "a" => vec![0.5, -2.0], "b" => objeto_json!{ "c" => false } },
+        };
+        assert_eq!(ida_y_vuelta(&v), v);
+        assert_eq!(v.campo("interior").and_then(|i| i.campo("b")).and_then(|b| b.campo("c")), Some(&Valor::Booleano(false)));
+        let leido = analizar(" { \"z\" : [ 1 , { } ] ,\n\t\"a\":null } ").unwrap();
+        assert_eq!(leido, objeto_json!{ "z" => vec![Valor::from(1.0), Valor::Objeto(Vec::new())], "a" => Valor::Nulo });
+    }
+
+    #[test]
+    fn numeros() {
+        for x in &[0.0, -1.5, 1e300, 2.5e-8, 123456789.0, ::std::f64::consts::PI, f64::MAX, f64::MIN_POSITIVE] {
+            assert_eq!(ida_y_vuelta(&Valor::from(*x)), Valor::Numero(*x));
+        }
+        assert_eq!(analizar("1e3"), Ok(Valor::Numero(1000.0)));
+        assert_eq!(analizar("-2.5E-2"), Ok(Valor::Numero(-0.025)));
+        assert_eq!(analizar("4E+1"), Ok(Valor::Numero(40.0)));
+        // Los números no finitos se escriben como null, que se lee como NaN
+        assert_eq!(Valor::from(f64::INFINITY).to_string(), "null");
+        assert!(analizar("null").unwrap().como_numero().unwrap().is_nan());
+    }
+
+    #[test]
+    fn textos_mal_formados() {
+        for texto in &["", "   ", "{", "}", "[1,]", "[1 2]", "{\"a\":}", "{\"a\" 1}", "{\"a\":1,}", "{a:1}", "\"sin cerrar",
+                       "\"\\q\"", "tru", "nul", "1 2", "1e", "--1", "NaN", "Infinity", "[1]]", "{\"a\":1}x"] {
+            assert!(analizar(texto).is_err(), "{:?} no debería leerse", texto);
+        }
+    }
+}
@@ -3,6 +3,8 @@
 //   es importar el paquete completo, con muchas dependencias
 // Se ha cambiado el tipo de dato numérico de f32 a f64
 //   y la función que lee archivos para poder manejar posibles errores
+// También se ha flexibilizado la lectura de la cabecera y de los datos (mayúsculas, espacios,
+//   tipos real e integer) para admitir los archivos .dat de KEEL, cuyo formato es casi el mismo
 
 // El código apenas tenía documentación; para leer un archivo hay que usar:
 //   arff::ArffContent::new(path::Path::new([nombre de archivo .arff aquí]))
@@ -71,7 +73,7 @@ impl Value {
 
     pub fn string(&self) -> Option<&str> {
         match self {
-            &Value::String(ref s) => Some(s),
+            Value::String(s) => Some(s),
             _ => None,
         }
     }
@@ -87,10 +89,13 @@ pub enum AttributeType {
 impl AttributeType {
     // Parse an attribute type from the arff header
     fn parse(s: &str) -> Self {
-        if s == "numeric" {
+        let s = s.trim();
+        let lowercase = s.to_lowercase();
+        // KEEL declara los tipos real e integer seguidos de su rango, por ejemplo "real [0.0, 1.0]"
+        if lowercase == "numeric" || lowercase.starts_with("real") || lowercase.starts_with("integer") {
             return AttributeType::Numeric;
         }
-        if s == "string" {
+        if lowercase == "string" {
             return AttributeType::String;
         }
         if s.len() < 2 {
@@ -105,7 +110,7 @@ impl AttributeType {
 
         let tokens = {
             let len = s.len();
-            s[1..len - 1].split(',').map(|s| s.to_string()).collect()
+            s[1..len - 1].split(',').map(|s| s.trim().to_string()).collect()
         };
         AttributeType::Text(tokens)
     }
@@ -114,7 +119,7 @@ impl AttributeType {
     /// Returns None otherwise.
     pub fn tokens(&self) -> Option<&[String]> {
         match self {
-            &AttributeType::Text(ref tokens) => Some(tokens),
+            AttributeType::Text(tokens) => Some(tokens),
             _ => None,
         }
     }
@@ -143,7 +148,16 @@ fn parse_f64(s: &str) -> f64 {
     } else if s == "-Infinity" {
         f64::NEG_INFINITY
     } else {
-        f64::from_str(s).ok().expect(&format!("Reading {}", s))
+        f64::from_str(s).unwrap_or_else(|_| panic!("Reading {}", s))
+    }
+}
+
+// Separa la primera palabra de una línea del resto, sin tener en cuenta los espacios que las rodean
+fn first_word(line: &str) -> (&str, &str) {
+    let line = line.trim();
+    match line.find(char::is_whitespace) {
+        Some(i) => (&line[..i], line[i..].trim_start()),
+        None => (line, ""),
     }
 }
 
@@ -169,7 +183,7 @@ impl ArffContent {
             match value {
                 &Value::Numeric(f) => line.push_str(&format!("{}", f)),
                 &Value::Text(i) => line.push_str(&attr.att_type.tokens().unwrap()[i]),
-                &Value::String(ref s) => line.push_str(s),
+                Value::String(s) => line.push_str(s),
                 &Value::Missing => line.push('?'),
             };
             line.push(',');
@@ -180,6 +194,7 @@ impl ArffContent {
 
     fn load_data_line(&mut self, line: &str) {
         let values = line.split(',')
+                         .map(|token| token.trim())
                          .zip(self.attributes.iter())
                          .map(|(token, attr)| {
                              if token == "?" {
@@ -197,16 +212,15 @@ impl ArffContent {
                              }
                          })
                          .collect();
-        self.data.push(Instance { values: values });
+        self.data.push(Instance { values });
     }
 
     fn load_line(&mut self, line: &str) -> bool {
-        let mut tokens = line.split(' ');
-        match tokens.next() {
-            Some("@relation") => self.title = tokens.next().unwrap().to_string(),
-            Some("@attribute") => {
-                let name = tokens.next().unwrap();
-                let t = tokens.next().unwrap();
+        let (keyword, rest) = first_word(line);
+        match keyword.to_lowercase().as_str() {
+            "@relation" => self.title = first_word(rest).0.to_string(),
+            "@attribute" => {
+                let (name, t) = first_word(rest);
                 let attr = Attribute {
                     name: name.to_string(),
                     att_type: AttributeType::parse(t),
@@ -214,7 +228,7 @@ impl ArffContent {
                 self.samples.push(AttributeSamples::from_attr(&attr));
                 self.attributes.push(attr);
             }
-            Some("@data") => {
+            "@data" => {
                 // Consume the rest of the lines
                 return true;
             }
@@ -226,26 +240,18 @@ impl ArffContent {
     fn make_samples(&mut self) {
         for (id, instance) in self.data.iter().enumerate() {
             for (value, samples) in instance.values.iter().zip(self.samples.iter_mut()) {
-                match samples {
-                    &mut AttributeSamples::Numeric(ref mut list) => match value.num() {
-                        Some(f) => list.push((f, id)),
-                        None => (),
-                    },
-                    &mut AttributeSamples::Text(ref mut list) => match value.text() {
-                        Some(i) => list[i].0.push(id),
-                        None => (),
-                    },
-                    &mut AttributeSamples::BadType => (),
+                match *samples {
+                    AttributeSamples::Numeric(ref mut list) => if let Some(f) = value.num() { list.push((f, id)) },
+                    AttributeSamples::Text(ref mut list) => if let Some(i) = value.text() { list[i].0.push(id) },
+                    AttributeSamples::BadType => (),
                 }
             }
         }
 
         // Now sort it
         for samples in self.samples.iter_mut() {
-            match samples {
-                &mut AttributeSamples::Numeric(ref mut list) =>
-                    list.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal)),
-                _ => (),
+            if let AttributeSamples::Numeric(ref mut list) = *samples {
+                list.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
             }
         }
     }
@@ -253,10 +259,7 @@ impl ArffContent {
     /// Loads a arff file
     pub fn new(filename: &path::Path) -> Result<ArffContent, io::Error> {
         // Read the file line by line
-        let file = match fs::File::open(filename) {
-            Err(why) => return Err(why),
-            Ok(file) => file,
-        };
+        let file = fs::File::open(filename)?;
 
         let mut content = ArffContent {
             filename: filename.to_str().unwrap().to_string(),
@@ -276,6 +279,9 @@ impl ArffContent {
             }
 
             if reading_data {
+                if line.trim().is_empty() {
+                    continue;   // Las líneas en blanco no contienen datos
+                }
                 // We are loading the data!
                 content.load_data_line(&line);
             } else {
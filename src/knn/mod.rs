@@ -2,10 +2,9 @@ extern crate ordered_float;
 mod arff;
 
 use self::arff::ArffContent;
-use std::{path, io};
+use std::{fs, path, io};
 use std::collections::HashSet;
 use ordered_float::OrderedFloat;
-use std::f64::INFINITY;
 use std::ops::{Index, IndexMut};
 use std::ptr; // Comparación de punteros
 use std::hash::{Hash, Hasher};
@@ -67,7 +66,7 @@ impl Dato {
     }
 
     pub fn solo_flotantes(&self) -> bool {
-        self.atributos_c.len() == 0
+        self.atributos_c.is_empty()
     }
 
     pub fn id_categoria(&self) -> i32 {
@@ -79,14 +78,14 @@ impl Dato {
 impl Index<usize> for Dato {
     type Output = f64;
 
-    fn index<'a>(&'a self, index: usize) -> &'a f64 {
-        &self.atributos_f[index as usize]
+    fn index(&self, index: usize) -> &f64 {
+        &self.atributos_f[index]
     }
 }
 
 impl IndexMut<usize> for Dato {
-    fn index_mut<'a>(&'a mut self, index: usize) -> &'a mut f64 {
-        &mut self.atributos_f[index as usize]
+    fn index_mut(&mut self, index: usize) -> &mut f64 {
+        &mut self.atributos_f[index]
     }
 }
 
@@ -120,8 +119,8 @@ impl Hash for Dato {
 // Distancia (al cuadrado) entre las características de dos datos
 fn distancia_cuadrado(a: &Dato, b: &Dato, w: &[f64]) -> f64 {
     let num_flotantes = a.atributos_f.len();
-      (distancia_cuadrado_vf(&a.atributos_f, &b.atributos_f, &w[0..num_flotantes])
-     + distancia_cuadrado_vc(&a.atributos_c, &b.atributos_c, &w[num_flotantes..]))
+    distancia_cuadrado_vf(&a.atributos_f, &b.atributos_f, &w[0..num_flotantes])
+        + distancia_cuadrado_vc(&a.atributos_c, &b.atributos_c, &w[num_flotantes..])
 }
 
 // Obtiene la categoría del dato más cercano a uno dado
@@ -134,7 +133,7 @@ pub fn get_mas_cercano(vm: &[Dato], d: &Dato, w: &[f64]) -> i32 {
 // Obtiene la categoría del dato más cercano a uno dado que no es él mismo
 pub fn get_mas_cercano_distinto(vm: &[Dato], d: &Dato, w: &[f64]) -> i32 {
     vm.iter().min_by_key(
-        |x| OrderedFloat(if ptr::eq(*x, d) { INFINITY } else { distancia_cuadrado(x, d, w) })
+        |x| OrderedFloat(if ptr::eq(*x, d) { f64::INFINITY } else { distancia_cuadrado(x, d, w) })
       ).unwrap().id_categoria
 }
 
@@ -155,7 +154,7 @@ pub fn get_mas_cercano_f(vm: &[Dato], d: &Dato, w: &[f64]) -> i32 {
 // Obtiene la categoría del dato más cercano a uno dado que no es él mismo cuando los valores son flotantes
 pub fn get_mas_cercano_distinto_f(vm: &[Dato], d: &Dato, w: &[f64]) -> i32 {
     vm.iter().min_by_key(
-        |x| OrderedFloat(if ptr::eq(*x, d) { INFINITY } else { distancia_cuadrado_solo_f(x, d, w) })
+        |x| OrderedFloat(if ptr::eq(*x, d) { f64::INFINITY } else { distancia_cuadrado_solo_f(x, d, w) })
       ).unwrap().id_categoria
 }
 
@@ -164,7 +163,7 @@ pub fn get_mas_cercano_distinto_f(vm: &[Dato], d: &Dato, w: &[f64]) -> i32 {
 pub fn get_amigo_mas_cercano<'a>(vm: &'a [Dato], d: &Dato, w: &[f64]) -> &'a Dato {
     let cat = d.id_categoria;
     vm.iter().min_by_key(
-        |x| OrderedFloat(if x.id_categoria != cat || ptr::eq(*x, d) { INFINITY } else { distancia_cuadrado(x, d, w) })
+        |x| OrderedFloat(if x.id_categoria != cat || ptr::eq(*x, d) { f64::INFINITY } else { distancia_cuadrado(x, d, w) })
       ).unwrap_or_else(|| panic!("No se encontraron elementos de la misma categoría que cierto dato"))
 }
 
@@ -173,46 +172,77 @@ pub fn get_amigo_mas_cercano<'a>(vm: &'a [Dato], d: &Dato, w: &[f64]) -> &'a Dat
 pub fn get_enemigo_mas_cercano<'a>(vm: &'a [Dato], d: &Dato, w: &[f64]) -> &'a Dato {
     let cat = d.id_categoria;
     vm.iter().min_by_key(
-        |x| OrderedFloat(if x.id_categoria == cat { INFINITY } else { distancia_cuadrado(x, d, w) })
+        |x| OrderedFloat(if x.id_categoria == cat { f64::INFINITY } else { distancia_cuadrado(x, d, w) })
       ).unwrap_or_else(|| panic!("No se encontraron elementos de una categoría distinta a la de cierto dato"))
 }
 
 
-// Normaliza un vector de datos para que los valores de las características numéricas estén en [0, 1]
-fn normalizar(v: &mut Vec<Dato>) {
-    for c in 0..v[0].atributos_f.len() {
-        let min = v.iter().min_by_key(|x| OrderedFloat(x[c])).unwrap()[c];
-        let max = v.iter().max_by_key(|x| OrderedFloat(x[c])).unwrap()[c];
-        let dif = max-min;
-        for vi in v.iter_mut() {
-            vi[c] = (vi[c]-min)/dif;
-        };
+// Parámetros de una normalización de los valores de las características numéricas:
+//   el mínimo y el máximo de cada característica en los datos con los que se ajusta
+#[derive(Clone, Debug)]
+pub struct Normalizacion {
+    pub minimos: Vec<f64>,
+    pub maximos: Vec<f64>,
+}
+
+impl Normalizacion {
+    // Obtiene la normalización que lleva los valores de las características numéricas de unos datos a [0, 1]
+    pub fn ajustar(v: &[Dato]) -> Normalizacion {
+        let n_atributos = v.first().map_or(0, |d| d.atributos_f.len());
+        let minimos = (0..n_atributos).map(|c| v.iter().min_by_key(|x| OrderedFloat(x[c])).unwrap()[c]).collect();
+        let maximos = (0..n_atributos).map(|c| v.iter().max_by_key(|x| OrderedFloat(x[c])).unwrap()[c]).collect();
+        Normalizacion { minimos, maximos }
+    }
+
+    // Normaliza unos datos con los parámetros ajustados
+    // Los datos que no se usaron para ajustar la normalización pueden quedar fuera de [0, 1]
+    // Las características constantes en el ajuste pasan a valer 0, pues no distinguen a ningún dato
+    pub fn aplicar(&self, v: &mut [Dato]) {
+        for (c, (min, max)) in self.minimos.iter().zip(self.maximos.iter()).enumerate() {
+            let dif = max-min;
+            for vi in v.iter_mut() {
+                vi[c] = if dif > 0.0 { (vi[c]-min)/dif } else { 0.0 };
+            };
+        }
     }
 }
 
+// Normaliza un vector de datos para que los valores de las características numéricas estén en [0, 1]
+fn normalizar(v: &mut [Dato]) {
+    Normalizacion::ajustar(v).aplicar(v);
+}
+
 impl ArffContent {
-    // Obtiene los datos a partir del formato que se obtiene en arff.rs
+    // Obtiene los datos a partir del formato que se obtiene en arff.rs, sin normalizar
     // Asume que no hay variables categóricas que no sean la variable a estimar
-    fn get_datos(&self) -> Vec<Dato> {
+    // Si se indica, se descartan los datos repetidos
+    fn get_datos_sin_normalizar(&self, descartar_repetidos: bool) -> Vec<Dato> {
         let mut vm = vec![];
         let mut vm_set = HashSet::new();  // Controlaremos los elementos repetidos con un conjunto
         for d in &self.data {
-            let af: Vec<f64> = (&d.values[..d.values.len()-1]).iter().map(|x| x.num().unwrap()).collect();
+            let af: Vec<f64> = d.values[..d.values.len()-1].iter().map(|x| x.num().unwrap()).collect();
             let ac = vec![];
             let cat = d.values.last().unwrap().text().unwrap() as i32; // el formato en arff.rs asigna un identificador que empieza en 0
             let nuevo_dato = Dato::new(&af, &ac, cat);
-            if !vm_set.contains(&nuevo_dato) {  // Agregamos el dato si no había sido agregado ya
+            if !descartar_repetidos {
+                vm.push(nuevo_dato);
+            } else if !vm_set.contains(&nuevo_dato) {  // Agregamos el dato si no había sido agregado ya
                 vm.push(nuevo_dato.clone());
                 vm_set.insert(nuevo_dato);
             }
         }
+        vm
+    }
 
+    // Obtiene los datos normalizados y sin repeticiones a partir del formato que se obtiene en arff.rs
+    fn get_datos(&self) -> Vec<Dato> {
+        let mut vm = self.get_datos_sin_normalizar(true);
         normalizar(&mut vm);
         vm
     }
 }
 
-// Obtiene los datos de un archivo .arff
+// Obtiene los datos de un archivo .arff (o .dat con el formato de KEEL)
 pub fn leer_archivo(ruta: &str) -> Result<Vec<Dato>, io::Error> {
     let contenido = arff::ArffContent::new(path::Path::new(ruta));
     match contenido {
@@ -220,3 +250,209 @@ pub fn leer_archivo(ruta: &str) -> Result<Vec<Dato>, io::Error> {
         Err(e) => Err(e), // Si new devolvió un error, devolvemos el mismo error
     }
 }
+
+// Obtiene los mismos datos que leer_archivo junto a la normalización que se les ha aplicado,
+//   para poder normalizar igual otros datos más adelante
+pub fn leer_archivo_normalizacion(ruta: &str) -> Result<(Vec<Dato>, Normalizacion), io::Error> {
+    let mut datos = arff::ArffContent::new(path::Path::new(ruta))?.get_datos_sin_normalizar(true);
+    let normalizacion = Normalizacion::ajustar(&datos);
+    normalizacion.aplicar(&mut datos);
+    Ok((datos, normalizacion))
+}
+
+// Obtiene el nombre de cada atributo (incluida la clase) de un archivo .arff y los valores de cada dato como texto,
+//   tal como aparecen en el archivo: sin normalizar, sin descartar repetidos y con "?" en los valores desconocidos
+pub fn leer_tabla(ruta: &str) -> Result<(Vec<String>, Vec<Vec<String>>), io::Error> {
+    let contenido = arff::ArffContent::new(path::Path::new(ruta))?;
+    let nombres = contenido.attributes.iter().map(|a| a.name.clone()).collect();
+    let filas = contenido.data.iter().map(|d| d.values.iter().zip(&contenido.attributes).map(|(v, a)| match v {
+        arff::Value::Numeric(x) => x.to_string(),
+        arff::Value::Text(i) => a.att_type.tokens().map_or(String::new(), |t| t[*i].clone()),
+        arff::Value::String(s) => s.clone(),
+        arff::Value::Missing => "?".to_string(),
+    }).collect()).collect();
+    Ok((nombres, filas))
+}
+
+// Resumen de un archivo de datos
+pub struct Descripcion {
+    pub titulo: String,
+    pub atributos: Vec<String>,   // Nombres de los atributos, sin contar la clase
+    pub clase: String,            // Nombre del atributo de la clase
+    pub clases: Vec<String>,      // Nombres de las clases, en el orden de sus identificadores
+    pub instancias: usize,        // Número de datos del archivo
+    pub repetidos: usize,         // Número de datos descartados por estar repetidos
+    pub distribucion: Vec<usize>, // Número de datos (sin repetir) de cada clase
+}
+
+// Obtiene el resumen de un archivo .arff (o .dat con el formato de KEEL)
+pub fn describir_archivo(ruta: &str) -> Result<Descripcion, io::Error> {
+    let contenido = arff::ArffContent::new(path::Path::new(ruta))?;
+    let datos = contenido.get_datos_sin_normalizar(true);
+    let n_atributos = contenido.attributes.len().saturating_sub(1);
+    let clases = match contenido.attributes.last().map(|a| &a.att_type) {
+        Some(arff::AttributeType::Text(clases)) => clases.clone(),
+        _ => vec![],
+    };
+    let mut distribucion = vec![0; clases.len()];
+    for d in &datos {
+        distribucion[d.id_categoria() as usize] += 1;
+    }
+
+    Ok(Descripcion {
+        titulo: contenido.title.clone(),
+        atributos: contenido.attributes[..n_atributos].iter().map(|a| a.name.clone()).collect(),
+        clase: contenido.attributes.last().map_or(String::new(), |a| a.name.clone()),
+        clases,
+        instancias: contenido.data.len(),
+        repetidos: contenido.data.len() - datos.len(),
+        distribucion,
+    })
+}
+
+// Datos de entrenamiento y de test de una partición predefinida
+pub type Particion = (Vec<Dato>, Vec<Dato>);
+
+// Comprueba que los archivos de entrenamiento y test de una partición tienen el mismo número de atributos
+//   y declaran las mismas clases en el mismo orden, para que los identificadores de clase coincidan
+fn comprobar_cabeceras(entrenamiento: &ArffContent, test: &ArffContent) -> Result<(), io::Error> {
+    let error = |mensaje: String| Err(io::Error::new(io::ErrorKind::InvalidData, mensaje));
+    if entrenamiento.attributes.len() != test.attributes.len() {
+        return error(format!("el archivo de entrenamiento tiene {} atributos y el de test {}",
+                             entrenamiento.attributes.len(), test.attributes.len()));
+    }
+    let clases = |c: &ArffContent| c.attributes.last().and_then(|a| a.att_type.tokens()).map(|t| t.to_vec());
+    if clases(entrenamiento) != clases(test) {
+        return error(format!("los archivos de entrenamiento y test declaran clases distintas: {:?} y {:?}",
+                             clases(entrenamiento).unwrap_or_default(), clases(test).unwrap_or_default()));
+    }
+    Ok(())
+}
+
+// Obtiene los datos de entrenamiento y de test de una partición predefinida en dos archivos
+// Ambos se normalizan con la normalización ajustada en el de entrenamiento, de forma que el
+//   test no influye en el clasificador. Solo se descartan los datos repetidos del entrenamiento
+// Los dos archivos deben declarar las mismas clases en el mismo orden. Si no, se devuelve un error
+pub fn leer_entrenamiento_test(ruta_entrenamiento: &str, ruta_test: &str) -> Result<Particion, io::Error> {
+    let contenido_entrenamiento = arff::ArffContent::new(path::Path::new(ruta_entrenamiento))?;
+    let contenido_test = arff::ArffContent::new(path::Path::new(ruta_test))?;
+    comprobar_cabeceras(&contenido_entrenamiento, &contenido_test)?;
+    let mut entrenamiento = contenido_entrenamiento.get_datos_sin_normalizar(true);
+    let mut test = contenido_test.get_datos_sin_normalizar(false);
+
+    let normalizacion = Normalizacion::ajustar(&entrenamiento);
+    normalizacion.aplicar(&mut entrenamiento);
+    normalizacion.aplicar(&mut test);
+    Ok((entrenamiento, test))
+}
+
+// Busca en un directorio los pares de archivos de entrenamiento y test de unas particiones predefinidas
+// Se sigue la convención de KEEL: el nombre de cada archivo de entrenamiento termina en "tra"
+//   (antes de la extensión) y el de su test es el mismo terminado en "tst"
+//   (por ejemplo, iris-5-1tra.dat e iris-5-1tst.dat)
+// Los pares se devuelven ordenados por nombre, poniendo antes los más cortos para que la
+//   partición 10 vaya después de la 9
+fn buscar_particiones(directorio: &str) -> Result<Vec<(String, String)>, io::Error> {
+    let mut pares = vec![];
+    for entrada in fs::read_dir(directorio)? {
+        let ruta = entrada?.path();
+        let (nombre, extension) = match (ruta.file_stem().and_then(|n| n.to_str()), ruta.extension().and_then(|e| e.to_str())) {
+            (Some(n), Some(e)) => (n.to_string(), e.to_string()),
+            _ => continue,
+        };
+        if nombre.ends_with("tra") {
+            let ruta_test = ruta.with_file_name(format!("{}tst.{}", &nombre[..nombre.len()-3], extension));
+            if ruta_test.is_file() {
+                pares.push((ruta.to_string_lossy().into_owned(), ruta_test.to_string_lossy().into_owned()));
+            }
+        }
+    }
+
+    if pares.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "no hay pares de archivos de entrenamiento (*tra) y test (*tst)"));
+    }
+    pares.sort_by_key(|p| (p.0.len(), p.0.clone()));
+    Ok(pares)
+}
+
+// Obtiene los datos de entrenamiento y de test de todas las particiones predefinidas de un directorio
+// Véanse buscar_particiones y leer_entrenamiento_test
+pub fn leer_directorio_particiones(directorio: &str) -> Result<Vec<Particion>, io::Error> {
+    buscar_particiones(directorio)?.iter().map(|(e, t)| leer_entrenamiento_test(e, t)).collect()
+}
+
+
+// Utilidades de las pruebas que leen y escriben archivos de datos
+#[cfg(test)]
+pub mod pruebas {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    // Directorio temporal propio de una prueba, vacío
+    pub fn directorio(nombre: &str) -> PathBuf {
+        let d = env::temp_dir().join(format!("prueba-{}-{}", nombre, ::std::process::id()));
+        let _ = fs::remove_dir_all(&d);
+        fs::create_dir_all(&d).unwrap();
+        d
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::pruebas::directorio;
+    use std::fs::File;
+    use std::io::Write;
+
+    // Escribe un archivo .arff con un atributo numérico y las clases y los datos indicados
+    fn escribir(ruta: &path::Path, clases: &str, datos: &[(f64, &str)]) -> String {
+        let mut f = File::create(ruta).unwrap();
+        writeln!(f, "@relation prueba\n@attribute a numeric\n@attribute clase {{{}}}\n@data", clases).unwrap();
+        for (x, c) in datos {
+            writeln!(f, "{},{}", x, c).unwrap();
+        }
+        ruta.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn entrenamiento_test_normaliza_con_el_entrenamiento() {
+        let d = directorio("normaliza");
+        let e = escribir(&d.join("e.arff"), "x,y", &[(0.0, "x"), (10.0, "y"), (10.0, "y")]);
+        let t = escribir(&d.join("t.arff"), "x,y", &[(5.0, "y"), (20.0, "x"), (20.0, "x")]);
+        let (entrenamiento, test) = leer_entrenamiento_test(&e, &t).unwrap();
+        // Los repetidos solo se descartan en el entrenamiento
+        assert_eq!(entrenamiento.len(), 2);
+        assert_eq!(test.len(), 3);
+        assert_eq!((test[0][0], test[0].id_categoria()), (0.5, 1));
+        assert_eq!((test[1][0], test[1].id_categoria()), (2.0, 0));
+    }
+
+    #[test]
+    fn entrenamiento_test_rechaza_clases_distintas() {
+        let d = directorio("clases");
+        let e = escribir(&d.join("e.arff"), "x,y", &[(0.0, "x"), (1.0, "y")]);
+        let desordenado = escribir(&d.join("t1.arff"), "y,x", &[(0.0, "x"), (1.0, "y")]);
+        let otras = escribir(&d.join("t2.arff"), "x,y,z", &[(0.0, "x"), (1.0, "z")]);
+        for t in &[desordenado, otras] {
+            let error = leer_entrenamiento_test(&e, t).err().expect("las clases no coinciden");
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn particiones_en_orden_numerico() {
+        let d = directorio("particiones");
+        for i in &[1, 2, 10] {
+            escribir(&d.join(format!("datos-10-{}tra.dat", i)), "x,y", &[(0.0, "x"), (1.0, "y")]);
+            escribir(&d.join(format!("datos-10-{}tst.dat", i)), "x,y", &[(0.5, "x")]);
+        }
+        escribir(&d.join("suelto-tra.dat"), "x,y", &[(0.0, "x")]);  // Sin test, se ignora
+        let pares = buscar_particiones(&d.to_string_lossy()).unwrap();
+        let nombres: Vec<String> = pares.iter().map(|p| path::Path::new(&p.0).file_name().unwrap().to_string_lossy().into_owned()).collect();
+        assert_eq!(nombres, vec!["datos-10-1tra.dat", "datos-10-2tra.dat", "datos-10-10tra.dat"]);
+        assert_eq!(leer_directorio_particiones(&d.to_string_lossy()).unwrap().len(), 3);
+        assert!(buscar_particiones(&directorio("vacio").to_string_lossy()).is_err());
+    }
+}
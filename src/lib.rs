@@ -0,0 +1,40 @@
+// Biblioteca de aprendizaje de pesos con metaheurísticas
+// Reúne la lectura de datos, el clasificador k-NN, la evaluación de pesos y todas las metaheurísticas
+//   de las prácticas, para que los programas (practica1, practica2, practica3, multiobjetivo y mh)
+//   y otros crates las usen sin volver a declarar cada módulo. Los ejemplos de examples/ muestran
+//   cómo aprender pesos sobre datos propios
+
+extern crate clap;          // Gestor de argumentos de los programas
+#[macro_use] extern crate itertools;  // Iteradores avanzados
+extern crate rand;          // Generador de números aleatorios
+extern crate ordered_float; // Implementación de orden total en flotantes (tiene en cuenta la existencia de NaN)
+
+// Datos y evaluación
+#[macro_use] pub mod json;  // Representación de valores JSON para la salida de resultados
+pub mod knn;                // Lectura de datos e implementación del clasificador K-NN
+pub mod evaluacion_pesos;   // Función objetivo, validación y medida de los resultados de los algoritmos
+pub mod resultados;         // Escritura y lectura de los resultados en archivos JSON Lines o CSV
+pub mod parametros;         // Parámetros de los algoritmos, que pueden leerse de un archivo de experimento
+pub mod presupuesto;        // Presupuesto de evaluaciones común a todos los algoritmos
+pub mod cache;              // Caché opcional de evaluaciones de pesos repetidos
+
+// Algoritmos
+#[macro_use] pub mod aprendiz;    // Interfaz común de los algoritmos de aprendizaje de pesos
+pub mod problema;                 // Problemas de optimización continua que resuelven las metaheurísticas
+pub mod traza;                    // Trazas de convergencia de las metaheurísticas
+pub mod punto_control;            // Guarda los tests terminados para reanudar los lotes interrumpidos
+pub mod funciones_practica1;      // Algoritmos greedy y de búsqueda local
+pub mod funciones_practica2;      // Algoritmos genéticos y meméticos
+pub mod funciones_practica3;      // Enfriamiento simulado, búsqueda local reiterada y evolución diferencial
+pub mod funciones_multiobjetivo;  // NSGA-II
+pub mod funciones_tabu;           // Búsqueda tabú
+pub mod catalogo;                 // Catálogo de todos los algoritmos
+pub mod banco_pruebas;            // Funciones de prueba continuas con las que validar los algoritmos generales
+
+// Ejecución y análisis de experimentos
+pub mod planificador;       // Reparte los tests de los algoritmos entre varios hilos
+pub mod cli;                // Argumentos y procedimientos comunes a los programas
+pub mod experimento;        // Experimentos descritos en archivos JSON
+pub mod modelo;             // Modelos entrenados que se guardan para clasificar datos nuevos
+pub mod estadistica;        // Tests estadísticos para comparar algoritmos
+pub mod tablas;             // Generación de las tablas de resultados
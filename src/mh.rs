@@ -0,0 +1,407 @@
+#[macro_use] extern crate clap;       // Gestor de argumentos del programa
+extern crate rand;          // Generador de números aleatorios
+extern crate aprendizaje_pesos;  // Algoritmos y evaluación de pesos, comunes a todos los programas
+
+use aprendizaje_pesos::{banco_pruebas, catalogo, cli, estadistica, evaluacion_pesos, experimento, knn, modelo, parametros, resultados, tablas};
+use clap::{Arg, ArgMatches};
+use aprendizaje_pesos::aprendiz::{Aprendiz, PesosFijos};
+use aprendizaje_pesos::presupuesto::Presupuesto;
+use rand::Isaac64Rng;
+use knn::Dato;
+
+
+
+
+// Lee los algoritmos seleccionados por nombre con el argumento --algorithms
+fn leer_algoritmos(matches: &ArgMatches) -> Vec<Box<dyn Aprendiz>> {
+    let lista = matches.value_of("algoritmos").unwrap();
+    match catalogo::seleccionar(lista) {
+        Ok(seleccion) => seleccion.iter().map(|a| Box::new(a.aprendiz()) as Box<dyn Aprendiz>).collect(),
+        Err(e) => cli::salir(&e),
+    }
+}
+
+// Lee los archivos de datos indicados con el argumento --data. Si no se indica ninguno, se usan los de las prácticas
+fn leer_datos<'a>(matches: &'a ArgMatches) -> Vec<&'a str> {
+    match matches.values_of("datos") {
+        Some(archivos) => archivos.collect(),
+        None => cli::INSTANCIAS.to_vec(),
+    }
+}
+
+// Lee los resultados guardados en los archivos indicados y los agrupa por algoritmo y conjunto de datos
+fn leer_resultados(matches: &ArgMatches) -> Vec<(String, Vec<(String, evaluacion_pesos::ResultadosCV)>)> {
+    let archivos: Vec<&str> = matches.values_of("INPUT").unwrap().collect();
+    let registros = resultados::leer_varios(&archivos).unwrap_or_else(|e| {
+        cli::salir(&format!("No se pudieron leer los resultados: {}", e))
+    });
+    resultados::agrupar(&registros)
+}
+
+
+
+// Subcomandos
+
+
+// Ejecuta los algoritmos una vez sobre todos los datos de cada archivo, evaluándolos en esos mismos datos
+//   con leave-one-out o, si se indica, en un archivo de test
+fn run(matches: &ArgMatches) {
+    let algoritmos = leer_algoritmos(matches);
+    let mut opciones = cli::OpcionesValidacion::leer(matches);
+    let mostrar_pesos = matches.is_present("pesos");
+
+    for archivo in leer_datos(matches) {
+        let datos = match matches.value_of("test") {
+            Some(test) => knn::leer_entrenamiento_test(archivo, test).map(|(e, t)| (e, Some(t))),
+            None => knn::leer_archivo(archivo).map(|d| (d, None)),
+        };
+        let (entrenamiento, test) = match datos {
+            Ok(d) => d,
+            Err(e) => { println!("No se pudo abrir el archivo {}: {}", archivo, e); continue },
+        };
+
+        for algoritmo in &algoritmos {
+            println!("\n{} sobre los datos en {}...", algoritmo.nombre(), archivo);
+            let funcion = |d: &[Dato], rng: &mut Isaac64Rng| algoritmo.aprender(d, opciones.presupuesto, rng);
+            let resultados = match test {
+                Some(ref test) => evaluacion_pesos::evaluar_particiones(&funcion, &[(entrenamiento.clone(), test.clone())], opciones.semilla, 1),
+                None => evaluacion_pesos::evaluar_entrenamiento(&funcion, &entrenamiento, opciones.semilla),
+            };
+            cli::mostrar(algoritmo.nombre(), archivo, &resultados, &mut opciones);
+            if mostrar_pesos {
+                let pesos: Vec<String> = resultados.tests[0].pesos.iter().map(|w| format!("{:.4}", w)).collect();
+                println!("Pesos: {}", pesos.join(" "));
+            }
+        }
+    }
+}
+
+// Ejecuta un algoritmo sobre todos los datos de un archivo y guarda el modelo obtenido
+fn train(matches: &ArgMatches) {
+    let mut algoritmos = leer_algoritmos(matches);
+    if algoritmos.len() != 1 {
+        cli::salir("Para entrenar un modelo hay que indicar un solo algoritmo");
+    }
+    let algoritmo = algoritmos.remove(0);
+    cli::leer_medida(matches);
+    cli::leer_config(matches);
+    let presupuesto = cli::leer_limite_tiempo(matches);
+    cli::leer_cache(matches);
+    let archivo = matches.value_of("datos").unwrap();
+    let semilla = cli::leer_semilla(matches);
+
+    println!("\n{} sobre los datos en {}...", algoritmo.nombre(), archivo);
+    let (modelo, resultados) = modelo::Modelo::entrenar(algoritmo.as_ref(), archivo, semilla, presupuesto).unwrap_or_else(|e| {
+        cli::salir(&format!("No se pudo abrir el archivo {}: {}", archivo, e))
+    });
+    evaluacion_pesos::mostrar_resultados(&resultados, presupuesto);
+
+    let ruta = matches.value_of("MODELO").unwrap();
+    match modelo.guardar(ruta) {
+        Ok(()) => println!("Modelo guardado en {}", ruta),
+        Err(e) => cli::salir(&format!("No se pudo guardar el modelo en {}: {}", ruta, e)),
+    }
+}
+
+// Clasifica los datos de un archivo con un modelo guardado
+fn predict(matches: &ArgMatches) {
+    let modelo = modelo::Modelo::leer(matches.value_of("MODELO").unwrap()).unwrap_or_else(|e| {
+        cli::salir(&format!("No se pudo leer el modelo {}", e))
+    });
+    let predicciones = modelo.predecir(matches.value_of("INPUT").unwrap()).unwrap_or_else(|e| {
+        cli::salir(&format!("No se pudieron clasificar los datos de {}", e))
+    });
+
+    match matches.value_of("salida") {
+        Some(ruta) => if let Err(e) = modelo::escribir_predicciones(&predicciones, ruta) {
+            cli::salir(&format!("No se pudo crear el archivo {}: {}", ruta, e));
+        },
+        None => for p in &predicciones {
+            println!("{}", p.prediccion);
+        },
+    }
+    if let Some((tasa, n)) = modelo::tasa_acierto(&predicciones) {
+        println!("{:.2}% aciertos en los {} datos con clase conocida", tasa, n);
+    }
+}
+
+// Evalúa unos pesos indicados por el usuario con la misma función objetivo que usan los algoritmos: sobre todos
+//   los datos de cada archivo con leave-one-out y, como si fuesen los aprendidos en cada entrenamiento, en la validación
+fn evaluate(matches: &ArgMatches) {
+    let pesos = cli::leer_pesos(matches);
+    let nombre = match matches.value_of("archivo_pesos") {
+        Some(ruta) => format!("Pesos de {}", ruta),
+        None => "Pesos fijos".to_string(),
+    };
+    let archivos = leer_datos(matches);
+
+    if !matches.is_present("particiones") && !matches.is_present("entrenamiento") {
+        let semilla = cli::leer_semilla(matches);
+        cli::leer_medida(matches);
+        for archivo in &archivos {
+            let datos = match knn::leer_archivo(archivo) {
+                Ok(d) => d,
+                Err(e) => cli::salir(&format!("No se pudo abrir el archivo {}: {}", archivo, e)),
+            };
+            let algoritmo = evaluacion_pesos::pesos_fijos(&pesos, &datos).unwrap_or_else(|e| {
+                cli::salir(&format!("No se pueden evaluar los pesos con los datos de {}: {}", archivo, e))
+            });
+            println!("\n{} sobre todos los datos en {} (leave-one-out)...", nombre, archivo);
+            let resultados = evaluacion_pesos::evaluar_entrenamiento(&algoritmo, &datos, semilla);
+            evaluacion_pesos::mostrar_resultados(&resultados, Presupuesto::default());
+        }
+    }
+
+    let aprendiz = PesosFijos::nuevos(&nombre, pesos).unwrap_or_else(|e| cli::salir(&e));
+    cli::validar(&[Box::new(aprendiz) as Box<dyn Aprendiz>], &archivos, matches);
+}
+
+// Valida los algoritmos con cada archivo de datos o con las particiones predefinidas indicadas
+fn cv(matches: &ArgMatches) {
+    cli::validar(&leer_algoritmos(matches), &leer_datos(matches), matches);
+}
+
+// Ejecuta los experimentos descritos en archivos JSON o imprime una plantilla de experimento
+fn experiment(matches: &ArgMatches) {
+    if matches.is_present("plantilla") {
+        println!("{}", experimento::plantilla());
+        return;
+    }
+    for archivo in matches.values_of("INPUT").unwrap() {
+        match experimento::Experimento::leer(archivo) {
+            Ok(e) => e.ejecutar(),
+            Err(e) => cli::salir(&format!("No se pudo leer el experimento {}", e)),
+        }
+    }
+}
+
+// Muestra los algoritmos del catálogo y las mejoras con las que se pueden combinar en los experimentos,
+//   o, si se pide, la representación en JSON de cada algoritmo
+fn list_algorithms(matches: &ArgMatches) {
+    if matches.is_present("json") {
+        for a in catalogo::algoritmos() {
+            println!("{}", a.aprendiz().a_json());
+        }
+        return;
+    }
+    println!("{:<28} {:<9} Nombre", "Clave", "Práctica");
+    for a in catalogo::algoritmos() {
+        println!("{:<28} {:<9} {}", a.clave, a.practica, a.nombre);
+    }
+    println!("\nTambién se pueden indicar por su nombre, y \"todos\", \"p1\", \"p2\" o \"p3\" seleccionan varios a la vez");
+    println!("\n{:<32} Mejora (\"mejoras\" de los algoritmos de un experimento)", "Clave");
+    for m in catalogo::mejoras() {
+        println!("{:<32} {}", m.clave, m.nombre);
+    }
+}
+
+// Ejecuta los algoritmos generales sobre las funciones de prueba continuas y muestra el error que alcanzan
+fn benchmark(matches: &ArgMatches) {
+    let optimizadores = banco_pruebas::seleccionar_optimizadores(matches.value_of("algoritmos").unwrap_or("todos"))
+        .unwrap_or_else(|e| cli::salir(&e));
+    let funciones = banco_pruebas::seleccionar_funciones(matches.value_of("funciones").unwrap_or("todas"))
+        .unwrap_or_else(|e| cli::salir(&e));
+    let dimensiones: Vec<usize> = match matches.values_of("dimension") {
+        Some(d) => d.map(|d| match d.parse() {
+            Ok(n) if n >= 2 => n,
+            _ => cli::salir(&format!("Dimensión no válida: {} (debe ser al menos 2)", d)),
+        }).collect(),
+        None => vec![10],
+    };
+    let variantes: Vec<banco_pruebas::Variante> = match matches.values_of("variante") {
+        Some(v) => v.map(|v| v.parse().unwrap_or_else(|e: String| cli::salir(&e))).collect(),
+        None => vec![banco_pruebas::Variante::Normal],
+    };
+    let ejecuciones = match matches.value_of("ejecuciones") {
+        Some(n) => match n.parse() {
+            Ok(n) if n > 0 => n,
+            _ => cli::salir(&format!("Número de ejecuciones no válido: {}", n)),
+        },
+        None => 5,
+    };
+    cli::leer_config(matches);
+    if let Some(e) = matches.value_of("evaluaciones") {
+        parametros::fijar_parametros(cli::interpretar_evaluaciones(e).unwrap_or_else(|e| cli::salir(&e)));
+    }
+    let presupuesto = cli::leer_limite_tiempo(matches);
+    let semilla = cli::leer_semilla(matches);
+
+    let mut problemas = Vec::new();
+    for f in &funciones {
+        for d in &dimensiones {
+            for v in &variantes {
+                problemas.push(banco_pruebas::ProblemaPrueba::nuevo(*f, *d, *v, semilla));
+            }
+        }
+    }
+    match (presupuesto.tiempo, presupuesto.max_evaluaciones()) {
+        (None, e) => println!("{} ejecuciones de cada algoritmo con un máximo de {} evaluaciones", ejecuciones, e),
+        (Some(t), usize::MAX) => println!("{} ejecuciones de cada algoritmo con un máximo de {:?}", ejecuciones, t),
+        (Some(t), e) => println!("{} ejecuciones de cada algoritmo con un máximo de {} evaluaciones y {:?}", ejecuciones, e, t),
+    }
+    banco_pruebas::ejecutar_pruebas(&optimizadores, &problemas, presupuesto, ejecuciones, semilla);
+}
+
+fn describe_dataset(matches: &ArgMatches) {
+    for archivo in matches.values_of("INPUT").unwrap() {
+        let d = match knn::describir_archivo(archivo) {
+            Ok(d) => d,
+            Err(e) => { println!("No se pudo abrir el archivo {}: {}\n", archivo, e); continue },
+        };
+        let distintos: usize = d.distribucion.iter().sum();
+        println!("{} ({})", archivo, d.titulo);
+        println!("  Instancias: {} ({} repetidas, que se descartan)", d.instancias, d.repetidos);
+        println!("  Atributos: {} ({})", d.atributos.len(), d.atributos.join(", "));
+        println!("  Clases: {}", d.clases.len());
+        for (clase, n) in d.clases.iter().zip(&d.distribucion) {
+            println!("    {}: {} ({:.2}%)", clase, n, 100.0 * *n as f64 / distintos as f64);
+        }
+        let maximo = d.distribucion.iter().max().cloned().unwrap_or(0);
+        let minimo = d.distribucion.iter().min().cloned().unwrap_or(0);
+        if minimo > 0 {
+            println!("  Razón de desequilibrio: {:.2}", maximo as f64 / minimo as f64);
+        }
+        println!();
+    }
+}
+
+fn compare(matches: &ArgMatches) {
+    let columna = match matches.value_of("columna") {
+        Some(c) => c.parse().unwrap_or_else(|e: String| cli::salir(&e)),
+        None => resultados::Columna::Agregado,
+    };
+    let alpha: f64 = match matches.value_of("alpha") {
+        Some(a) => match a.parse() {
+            Ok(a) if a > 0.0 && a < 1.0 => a,
+            _ => cli::salir(&format!("Nivel de significación no válido: {}", a)),
+        },
+        None => 0.05,
+    };
+
+    estadistica::mostrar_comparacion(&leer_resultados(matches), columna, matches.is_present("particiones"), alpha,
+                                     matches.value_of("control"), matches.value_of("cd"));
+}
+
+fn tables(matches: &ArgMatches) {
+    let mut opciones = tablas::OpcionesTabla::default();
+    if let Some(f) = matches.value_of("formato") {
+        opciones.formato = f.parse().unwrap_or_else(|e: String| cli::salir(&e));
+    }
+    if let Some(p) = matches.value_of("precision") {
+        opciones.precision = p.parse().unwrap_or_else(|_| cli::salir(&format!("Precisión no válida: {}", p)));
+    }
+    opciones.resaltar = !matches.is_present("sin_resaltar");
+
+    let algoritmos = leer_resultados(matches);
+    match matches.value_of("directorio") {
+        Some(directorio) => tablas::guardar_tablas(&algoritmos, &opciones, directorio),
+        None => tablas::imprimir_tablas(&algoritmos, &opciones),
+    }
+}
+
+
+
+fn main() {
+    let algoritmos = || Arg::with_name("algoritmos").short("a").long("algorithms").takes_value(true).required(true)
+        .help("Algoritmos que se ejecutan, separados por comas (por ejemplo, relief,ils,de_ctb_1). list-algorithms muestra los disponibles");
+    let datos = || Arg::with_name("datos").short("d").long("data").takes_value(true).multiple(true).require_delimiter(true)
+        .help("Archivos de datos separados por comas. Si no se indican se usan los tres archivos de las prácticas");
+
+    // Gestor de argumentos
+    // Los subcomandos que ejecutan algoritmos comparten los argumentos de las prácticas
+    let subcomando_run = clap_app!(run =>
+            (about: "Ejecuta los algoritmos sobre todos los datos de cada archivo y evalúa los pesos obtenidos en esos datos (con leave-one-out) o en un archivo de test")
+            (@arg test: -t --test +takes_value "Archivo de test con el que evaluar los pesos aprendidos con cada archivo de datos")
+            (@arg pesos: -w --pesos "Muestra los pesos aprendidos")
+        ).arg(algoritmos()).arg(datos())
+        .args(&[cli::argumento_semilla(), cli::argumento_medida(), cli::argumento_metricas(), cli::argumento_salida(), cli::argumento_config(), cli::argumento_cache()])
+        .args(&cli::argumentos_limite_tiempo());
+    let subcomando_evaluate = clap_app!(evaluate =>
+            (about: "Evalúa unos pesos indicados por el usuario con la misma función objetivo que usan los algoritmos: muestra su puntuación sobre todos los datos de cada archivo (con leave-one-out) y los resultados de validarlos como si fuesen los aprendidos en cada partición")
+            (@group pesos_indicados =>
+                (@attributes +required)
+                (@arg pesos: -w --pesos +takes_value +allow_hyphen_values "Pesos separados por comas (por ejemplo, \"1,0,0.5\"), uno por atributo")
+                (@arg archivo_pesos: -f --("archivo-pesos") +takes_value "Archivo con los pesos: números separados por comas o espacios, una lista JSON o un objeto JSON con un campo \"pesos\", como los modelos guardados por train")
+            )
+        ).arg(datos()).args(&cli::argumentos_validacion());
+    let subcomando_train = clap_app!(train =>
+            (about: "Ejecuta un algoritmo sobre todos los datos de un archivo y guarda los pesos aprendidos junto a la normalización, los atributos, las clases y los datos de entrenamiento, para clasificar después datos nuevos con predict")
+            (@arg datos: -d --data +takes_value +required "Archivo de datos de entrenamiento")
+            (@arg MODELO: +required "Archivo JSON en el que guardar el modelo")
+        ).arg(algoritmos()).args(&[cli::argumento_semilla(), cli::argumento_medida(), cli::argumento_config(), cli::argumento_cache()])
+        .args(&cli::argumentos_limite_tiempo());
+    let subcomando_cv = clap_app!(cv =>
+            (about: "Valida los algoritmos con cada archivo de datos o con particiones predefinidas")
+        ).arg(algoritmos()).arg(datos()).args(&cli::argumentos_validacion());
+    let subcomando_benchmark = clap_app!(benchmark =>
+            (about: "Valida los algoritmos generales (búsqueda local, enfriamiento simulado, ILS, evolución diferencial, genéticos y búsqueda tabú) con funciones de prueba continuas clásicas de mínimo conocido, y muestra el error que alcanzan con el presupuesto de evaluaciones")
+            (@arg algoritmos: -a --algorithms +takes_value "Algoritmos separados por comas: busqueda_local, es, ils, de_rand_1, de_ctb_1, agg_blx, age_blx y busqueda_tabu. Por defecto, todos")
+            (@arg funciones: -f --funciones +takes_value "Funciones de prueba separadas por comas: esfera, rastrigin, rosenbrock, ackley, griewank y schwefel. Por defecto, todas")
+            (@arg dimension: -D --dimension +takes_value +multiple +require_delimiter "Dimensiones de las funciones, separadas por comas. Por defecto, 10")
+            (@arg variante: --variante +takes_value +multiple +require_delimiter "Variantes de las funciones, separadas por comas: normal (por defecto), desplazada (el mínimo se mueve a un punto aleatorio) o rotada (desplazada y con las variables rotadas), como en las competiciones del CEC")
+            (@arg ejecuciones: -n --ejecuciones +takes_value "Número de ejecuciones de cada algoritmo sobre cada función, con distintas semillas. Por defecto, 5")
+            (@arg evaluaciones: -e --evaluaciones +takes_value "Máximo de evaluaciones de cada ejecución. Por defecto, el parámetro max_evaluaciones")
+        ).args(&[cli::argumento_semilla(), cli::argumento_config()]).args(&cli::argumentos_limite_tiempo());
+
+    let matches = clap_app!(mh =>
+        (author: crate_authors!())
+        (about: "Aprendizaje de pesos con metaheurísticas\n\nReúne en un solo programa todos los algoritmos de las prácticas, que se eligen por su nombre, y las herramientas para analizar sus resultados.")
+        (set_term_width: 79)
+        (@setting SubcommandRequiredElseHelp)
+        (@subcommand experiment =>
+            (about: "Ejecuta experimentos descritos en archivos JSON con los conjuntos de datos, los algoritmos y sus parámetros, la semilla y el esquema de validación")
+            (@arg INPUT: +multiple required_unless[plantilla] "Archivos de experimento")
+            (@arg plantilla: --plantilla "Imprime un experimento de ejemplo con todos los parámetros a su valor por defecto")
+        )
+        (@subcommand predict =>
+            (about: "Clasifica con un modelo guardado por train los datos de un archivo .arff o .csv (con cabecera). Si los datos tienen clase, muestra también la tasa de aciertos")
+            (alias: "apply")
+            (@arg MODELO: +required "Archivo del modelo")
+            (@arg INPUT: +required "Archivo con los datos a clasificar")
+            (@arg salida: -o --output +takes_value "Archivo CSV en el que escribir la predicción de cada dato. Si no se indica, se imprimen")
+        )
+        (@subcommand list_algorithms =>
+            (name: "list-algorithms")
+            (about: "Muestra los algoritmos disponibles y las mejoras con las que se pueden combinar")
+            (@arg json: --json "Muestra cada algoritmo en JSON, con el formato de la lista de algoritmos de los experimentos")
+        )
+        (@subcommand describe_dataset =>
+            (name: "describe-dataset")
+            (about: "Muestra el número de instancias, los atributos y la distribución de clases de archivos de datos")
+            (@arg INPUT: +required +multiple "Archivos .arff (o .dat de KEEL)")
+        )
+        (@subcommand compare =>
+            (about: "Compara los algoritmos de resultados guardados con --output: los ordena por su rango medio y aplica los tests de Friedman (con la corrección de Iman y Davenport), Holm y Nemenyi a todos ellos, y el de Wilcoxon a cada par")
+            (@arg INPUT: +required +multiple "Archivos de resultados (JSON Lines, o CSV si su extensión es .csv)")
+            (@arg columna: -c --columna +takes_value "Valor con el que se comparan los algoritmos: clas, red, agregado (por defecto) o tiempo")
+            (@arg alpha: --alpha +takes_value "Nivel de significación. Por defecto, 0.05")
+            (@arg particiones: --particiones "Usa cada partición de cada conjunto de datos como bloque en lugar de la media de cada conjunto")
+            (@arg control: --control +takes_value "Algoritmo de control en el test de Holm. Por defecto, el de mejor rango medio")
+            (@arg cd: --cd +takes_value "Archivo CSV en el que escribir los rangos medios y la diferencia crítica")
+        )
+        (@subcommand tables =>
+            (about: "Genera a partir de resultados guardados con --output una tabla por algoritmo con los resultados de cada partición y su media en cada conjunto de datos, y la tabla global que compara las medias de todos los algoritmos")
+            (@arg INPUT: +required +multiple "Archivos de resultados (JSON Lines, o CSV si su extensión es .csv)")
+            (@arg formato: -f --formato +takes_value "Formato de las tablas: latex (por defecto), markdown o html")
+            (@arg precision: -p --precision +takes_value "Número de decimales de las tasas y el agregado. Por defecto, 2")
+            (@arg directorio: -d --directorio +takes_value "Directorio en el que guardar cada tabla en un archivo. Si no se indica, se imprimen todas")
+            (@arg sin_resaltar: --("sin-resaltar") "No resalta el mejor valor de cada columna de la tabla global")
+        )
+    ).subcommand(subcomando_run).subcommand(subcomando_cv).subcommand(subcomando_train).subcommand(subcomando_evaluate)
+      .subcommand(subcomando_benchmark).get_matches();
+
+    match matches.subcommand() {
+        ("run", Some(m)) => run(m),
+        ("cv", Some(m)) => cv(m),
+        ("train", Some(m)) => train(m),
+        ("evaluate", Some(m)) => evaluate(m),
+        ("predict", Some(m)) => predict(m),
+        ("experiment", Some(m)) => experiment(m),
+        ("list-algorithms", Some(m)) => list_algorithms(m),
+        ("benchmark", Some(m)) => benchmark(m),
+        ("describe-dataset", Some(m)) => describe_dataset(m),
+        ("compare", Some(m)) => compare(m),
+        ("tables", Some(m)) => tables(m),
+        _ => unreachable!(),
+    }
+}
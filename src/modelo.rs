@@ -0,0 +1,332 @@
+// Modelos entrenados: los pesos aprendidos por un algoritmo sobre un conjunto de datos completo junto a
+//   todo lo necesario para clasificar datos nuevos con ellos (la normalización, los nombres de los
+//   atributos y las clases y los datos de entrenamiento, que son los prototipos del clasificador 1-NN)
+// Se guardan en archivos JSON, de forma que se pueden reutilizar sin volver a ejecutar el algoritmo
+
+use aprendiz::Aprendiz;
+use evaluacion_pesos;
+use evaluacion_pesos::ResultadosCV;
+use json::Valor;
+use knn;
+use knn::{Dato, Normalizacion};
+use parametros;
+use parametros::Parametros;
+use presupuesto::Presupuesto;
+use rand::Isaac64Rng;
+use resultados::{Formato, campo_csv, dividir_csv};
+use std::fs::File;
+use std::io;
+use std::io::{Read, Write};
+
+
+pub struct Modelo {
+    pub algoritmo: String,
+    pub conjunto: String,          // Archivo con el que se entrenó
+    pub semilla: u64,
+    pub parametros: Parametros,    // Parámetros con los que se ejecutó el algoritmo
+    pub atributos: Vec<String>,    // Nombres de los atributos, en el orden de los pesos
+    pub clase: String,             // Nombre del atributo de la clase
+    pub clases: Vec<String>,       // Nombres de las clases, en el orden de sus identificadores
+    pub pesos: Vec<f64>,
+    pub normalizacion: Normalizacion,
+    pub prototipos: Vec<Dato>,     // Datos de entrenamiento normalizados y sin repetidos
+}
+
+// Clasificación de un dato nuevo, junto a su clase real si aparece en el archivo
+pub struct Prediccion {
+    pub prediccion: String,
+    pub clase: Option<String>,
+}
+
+
+// Lee un campo obligatorio de un objeto JSON
+fn campo<'a>(v: &'a Valor, nombre: &str) -> Result<&'a Valor, String> {
+    v.campo(nombre).ok_or_else(|| format!("falta el campo {}", nombre))
+}
+
+fn texto(v: &Valor, nombre: &str) -> Result<String, String> {
+    campo(v, nombre)?.como_texto().map(|s| s.to_string()).ok_or_else(|| format!("{} debe ser un texto", nombre))
+}
+
+fn lista<'a>(v: &'a Valor, nombre: &str) -> Result<&'a [Valor], String> {
+    campo(v, nombre)?.como_lista().ok_or_else(|| format!("{} debe ser una lista", nombre))
+}
+
+fn numeros(v: &Valor, nombre: &str) -> Result<Vec<f64>, String> {
+    lista(v, nombre)?.iter().map(|x| x.como_numero().ok_or_else(|| format!("{} debe contener números", nombre))).collect()
+}
+
+fn textos(v: &Valor, nombre: &str) -> Result<Vec<String>, String> {
+    lista(v, nombre)?.iter().map(|x| x.como_texto().map(|s| s.to_string()).ok_or_else(|| format!("{} debe contener textos", nombre))).collect()
+}
+
+
+// Lee los nombres de las columnas y los valores como texto de un archivo CSV con cabecera
+fn leer_csv(ruta: &str) -> Result<(Vec<String>, Vec<Vec<String>>), io::Error> {
+    let mut texto = String::new();
+    File::open(ruta)?.read_to_string(&mut texto)?;
+    let mut lineas = texto.lines().filter(|l| !l.trim().is_empty());
+    let cabecera = lineas.next().map(dividir_csv).unwrap_or_default();
+    let filas = lineas.map(|l| dividir_csv(l).iter().map(|x| x.trim().to_string()).collect()).collect();
+    Ok((cabecera.iter().map(|x| x.trim().to_string()).collect(), filas))
+}
+
+
+impl Modelo {
+    // Ejecuta un algoritmo sobre todos los datos de un archivo y construye el modelo con los pesos obtenidos
+    // Devuelve también los resultados de evaluar esos pesos con leave-one-out en los mismos datos
+    pub fn entrenar(algoritmo: &dyn Aprendiz, ruta: &str, semilla: u64, presupuesto: Presupuesto) -> Result<(Modelo, ResultadosCV), io::Error> {
+        let descripcion = knn::describir_archivo(ruta)?;
+        let (datos, normalizacion) = knn::leer_archivo_normalizacion(ruta)?;
+        let resultados = evaluacion_pesos::evaluar_entrenamiento(&|d: &[Dato], rng: &mut Isaac64Rng| algoritmo.aprender(d, presupuesto, rng), &datos, semilla);
+
+        let modelo = Modelo {
+            algoritmo: algoritmo.nombre().to_string(),
+            conjunto: ruta.to_string(),
+            semilla,
+            parametros: algoritmo.parametros().unwrap_or_else(parametros::parametros),
+            atributos: descripcion.atributos,
+            clase: descripcion.clase,
+            clases: descripcion.clases,
+            pesos: resultados.tests[0].pesos.clone(),
+            normalizacion,
+            prototipos: datos,
+        };
+        Ok((modelo, resultados))
+    }
+
+    pub fn a_json(&self) -> Valor {
+        let prototipos: Vec<Valor> = self.prototipos.iter().map(|d| objeto_json!{
+            "clase" => d.id_categoria() as usize,
+            "valores" => (0..d.num_atributos()).map(|i| d[i]).collect::<Vec<f64>>(),
+        }).collect();
+        objeto_json!{
+            "algoritmo" => self.algoritmo.as_str(),
+            "conjunto" => self.conjunto.as_str(),
+            "semilla" => self.semilla.to_string(),
+            "parametros" => self.parametros.a_json(),
+            "atributos" => &self.atributos[..],
+            "clase" => self.clase.as_str(),
+            "clases" => &self.clases[..],
+            "pesos" => &self.pesos[..],
+            "normalizacion" => objeto_json!{
+                "minimos" => &self.normalizacion.minimos[..],
+                "maximos" => &self.normalizacion.maximos[..],
+            },
+            "prototipos" => prototipos,
+        }
+    }
+
+    pub fn de_json(v: &Valor) -> Result<Modelo, String> {
+        let atributos = textos(v, "atributos")?;
+        let clases = textos(v, "clases")?;
+        let n = atributos.len();
+
+        let mut parametros = Parametros::default();
+        if let Some(p) = v.campo("parametros") {
+            parametros.actualizar(p)?;
+        }
+        let normalizacion = campo(v, "normalizacion")?;
+        let normalizacion = Normalizacion { minimos: numeros(normalizacion, "minimos")?, maximos: numeros(normalizacion, "maximos")? };
+        let pesos = numeros(v, "pesos")?;
+        if pesos.len() != n || normalizacion.minimos.len() != n || normalizacion.maximos.len() != n {
+            return Err(format!("los pesos y la normalización deben tener un valor por cada uno de los {} atributos", n));
+        }
+
+        let prototipos = lista(v, "prototipos")?.iter().map(|p| {
+            let valores = numeros(p, "valores")?;
+            let clase = campo(p, "clase")?.como_numero().unwrap_or(-1.0);
+            if valores.len() != n || clase < 0.0 || clase as usize >= clases.len() {
+                return Err("hay un prototipo con un número de valores o una clase no válidos".to_string());
+            }
+            Ok(Dato::new(&valores, &[], clase as i32))
+        }).collect::<Result<Vec<Dato>, String>>()?;
+        if prototipos.is_empty() {
+            return Err("el modelo no tiene prototipos".to_string());
+        }
+
+        Ok(Modelo {
+            algoritmo: texto(v, "algoritmo")?,
+            conjunto: texto(v, "conjunto")?,
+            semilla: texto(v, "semilla")?.parse().map_err(|_| "la semilla debe ser un entero de 64 bits".to_string())?,
+            parametros,
+            atributos,
+            clase: texto(v, "clase")?,
+            clases,
+            pesos,
+            normalizacion,
+            prototipos,
+        })
+    }
+
+    pub fn guardar(&self, ruta: &str) -> Result<(), io::Error> {
+        writeln!(File::create(ruta)?, "{}", self.a_json())
+    }
+
+    pub fn leer(ruta: &str) -> Result<Modelo, String> {
+        Modelo::de_json(&parametros::leer_json(ruta)?).map_err(|e| format!("{}: {}", ruta, e))
+    }
+
+    // Clasifica unos datos sin normalizar
+    pub fn clasificar(&self, datos: &[Vec<f64>]) -> Vec<&str> {
+        let mut normalizados: Vec<Dato> = datos.iter().map(|d| Dato::new(d, &[], -1)).collect();
+        self.normalizacion.aplicar(&mut normalizados);
+        normalizados.iter().map(|d| self.clases[knn::get_mas_cercano(&self.prototipos, d, &self.pesos) as usize].as_str()).collect()
+    }
+
+    // Clasifica los datos de un archivo .arff o .csv (según su extensión)
+    // Las columnas se buscan por el nombre de los atributos del modelo. Si no aparecen todos, se toman en
+    //   orden, con la clase en la última columna si hay una más que atributos
+    pub fn predecir(&self, ruta: &str) -> Result<Vec<Prediccion>, String> {
+        let tabla = if Formato::de_ruta(ruta) == Formato::Csv { leer_csv(ruta) } else { knn::leer_tabla(ruta) };
+        let (columnas, filas) = tabla.map_err(|e| format!("{}: {}", ruta, e))?;
+
+        let n = self.atributos.len();
+        let por_nombre: Option<Vec<usize>> = self.atributos.iter().map(|a| columnas.iter().position(|c| c == a)).collect();
+        let (indices, indice_clase) = match por_nombre {
+            Some(indices) => (indices, columnas.iter().position(|c| *c == self.clase)),
+            None if columnas.len() == n => ((0..n).collect(), None),
+            None if columnas.len() == n + 1 => ((0..n).collect(), Some(n)),
+            None => return Err(format!("{}: las columnas no se corresponden con los {} atributos del modelo", ruta, n)),
+        };
+
+        let mut datos = Vec::with_capacity(filas.len());
+        for (i, fila) in filas.iter().enumerate() {
+            let valores = indices.iter().map(|c| fila.get(*c).and_then(|x| x.parse::<f64>().ok()))
+                                 .collect::<Option<Vec<f64>>>()
+                                 .ok_or_else(|| format!("{}: el dato {} tiene valores que faltan o no son números", ruta, i + 1))?;
+            datos.push(valores);
+        }
+
+        Ok(self.clasificar(&datos).into_iter().zip(&filas).map(|(prediccion, fila)| Prediccion {
+            prediccion: prediccion.to_string(),
+            clase: indice_clase.and_then(|c| fila.get(c)).filter(|c| *c != "?").cloned(),
+        }).collect())
+    }
+}
+
+
+// Escribe las predicciones en un archivo CSV con el número de cada dato, su predicción y su clase si se conoce
+pub fn escribir_predicciones(predicciones: &[Prediccion], ruta: &str) -> Result<(), io::Error> {
+    let mut archivo = File::create(ruta)?;
+    writeln!(archivo, "dato,prediccion,clase")?;
+    for (i, p) in predicciones.iter().enumerate() {
+        writeln!(archivo, "{},{},{}", i + 1, campo_csv(&p.prediccion), campo_csv(p.clase.as_ref().map_or("", |c| c.as_str())))?;
+    }
+    Ok(())
+}
+
+// Porcentaje de aciertos en los datos cuya clase se conoce, junto al número de esos datos
+pub fn tasa_acierto(predicciones: &[Prediccion]) -> Option<(f64, usize)> {
+    let conocidas: Vec<&Prediccion> = predicciones.iter().filter(|p| p.clase.is_some()).collect();
+    if conocidas.is_empty() { return None }
+    let aciertos = conocidas.iter().filter(|p| p.clase.as_ref() == Some(&p.prediccion)).count();
+    Some((100.0 * aciertos as f64 / conocidas.len() as f64, conocidas.len()))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aprendiz::Funcion;
+    use funciones_practica1::relief;
+    use json;
+    use knn::pruebas::directorio;
+    use std::fs;
+    use std::path::Path;
+
+    fn escribir(ruta: &Path, texto: &str) -> String {
+        fs::write(ruta, texto).unwrap();
+        ruta.to_string_lossy().into_owned()
+    }
+
+    // La clase depende solo del atributo a (x si a < 5); b es ruido
+    fn entrenar(d: &Path) -> Modelo {
+        let mut arff = String::from("@relation prueba\n@attribute a numeric\n@attribute b numeric\n@attribute clase {x,y}\n@data\n");
+        for i in 0..10 {
+            arff += &format!("{},{},{}\n", i, (i * 7) % 10 * 10, if i < 5 { "x" } else { "y" });
+        }
+        let ruta = escribir(&d.join("entrenamiento.arff"), &arff);
+        Modelo::entrenar(&Funcion::nueva("relief", "RELIEF", relief), &ruta, 7, Presupuesto::default()).unwrap().0
+    }
+
+    #[test]
+    fn entrena_con_la_descripcion_del_archivo() {
+        let m = entrenar(&directorio("entrena"));
+        assert_eq!((m.algoritmo.as_str(), m.semilla, m.clase.as_str()), ("RELIEF", 7, "clase"));
+        assert_eq!((m.atributos.clone(), m.clases.clone()), (vec!["a".to_string(), "b".to_string()], vec!["x".to_string(), "y".to_string()]));
+        assert_eq!((m.normalizacion.minimos.clone(), m.normalizacion.maximos.clone()), (vec![0.0, 0.0], vec![9.0, 90.0]));
+        assert_eq!(m.prototipos.len(), 10);
+        assert!(m.pesos[0] > m.pesos[1]);
+    }
+
+    #[test]
+    fn se_guarda_y_se_lee() {
+        let d = directorio("guarda");
+        let m = entrenar(&d);
+        let ruta = d.join("modelo.json").to_string_lossy().into_owned();
+        m.guardar(&ruta).unwrap();
+        let leido = Modelo::leer(&ruta).unwrap();
+        assert_eq!((&leido.algoritmo, &leido.conjunto, leido.semilla), (&m.algoritmo, &m.conjunto, m.semilla));
+        assert_eq!((&leido.atributos, &leido.clases, &leido.pesos), (&m.atributos, &m.clases, &m.pesos));
+        assert_eq!(leido.parametros, m.parametros);
+        assert!(leido.prototipos.iter().zip(&m.prototipos).all(|(a, b)| a[0] == b[0] && a[1] == b[1] && a.id_categoria() == b.id_categoria()));
+        assert_eq!(m.clasificar(&[vec![1.0, 90.0], vec![8.0, 0.0]]), vec!["x", "y"]);
+        assert_eq!(leido.clasificar(&[vec![1.0, 90.0], vec![8.0, 0.0]]), vec!["x", "y"]);
+    }
+
+    #[test]
+    fn rechaza_modelos_incoherentes() {
+        let m = entrenar(&directorio("incoherente"));
+        let modificado = |cambio: &str, valor: Valor| -> Result<Modelo, String> {
+            let mut v = json::analizar(&m.a_json().to_string()).unwrap();
+            if let Valor::Objeto(ref mut campos) = v {
+                campos.iter_mut().find(|c| c.0 == cambio).unwrap().1 = valor;
+            }
+            Modelo::de_json(&v)
+        };
+        assert!(modificado("pesos", Valor::from(&[1.0][..])).is_err());
+        assert!(modificado("prototipos", Valor::Lista(Vec::new())).is_err());
+        assert!(modificado("prototipos", Valor::Lista(vec![objeto_json!{ "clase" => 2.0, "valores" => vec![0.0, 0.0] }])).is_err());
+        assert!(modificado("semilla", Valor::from("-1")).is_err());
+        assert!(modificado("parametros", objeto_json!{ "tamano_de" => 1.0 }).is_err());
+        assert!(modificado("clases", Valor::from(1.0)).is_err());
+    }
+
+    #[test]
+    fn predice_archivos_csv_y_arff() {
+        let d = directorio("predice");
+        let m = entrenar(&d);
+        // Columnas por nombre, en otro orden, con una clase desconocida
+        let csv = escribir(&d.join("nombres.csv"), "b,clase,a\n90,x,1\n0,x,8\n50,?,2\n");
+        let p = m.predecir(&csv).unwrap();
+        assert_eq!(p.iter().map(|p| p.prediccion.as_str()).collect::<Vec<_>>(), vec!["x", "y", "x"]);
+        assert_eq!(p.iter().map(|p| p.clase.as_deref()).collect::<Vec<_>>(), vec![Some("x"), Some("x"), None]);
+        assert_eq!(tasa_acierto(&p), Some((50.0, 2)));
+
+        // Sin nombres, en orden y con la clase en la última columna
+        let csv = escribir(&d.join("orden.csv"), "u,v,w\n1,90,x\n");
+        assert_eq!(m.predecir(&csv).unwrap()[0].clase.as_deref(), Some("x"));
+        let csv = escribir(&d.join("sin_clase.csv"), "u,v\n8,0\n");
+        let p = m.predecir(&csv).unwrap();
+        assert_eq!((p[0].prediccion.as_str(), p[0].clase.clone()), ("y", None));
+        assert_eq!(tasa_acierto(&p), None);
+
+        let arff = escribir(&d.join("test.arff"), "@relation t\n@attribute a numeric\n@attribute b numeric\n@attribute clase {x,y}\n@data\n9,10,y\n");
+        assert_eq!(m.predecir(&arff).unwrap()[0].prediccion, "y");
+
+        assert!(m.predecir(&escribir(&d.join("columnas.csv"), "u,v,w,z\n1,2,x,y\n")).is_err());
+        assert!(m.predecir(&escribir(&d.join("texto.csv"), "a,b\n1,uno\n")).is_err());
+        assert!(m.predecir(&d.join("no_existe.csv").to_string_lossy()).is_err());
+    }
+
+    #[test]
+    fn escribe_las_predicciones() {
+        let d = directorio("escribe");
+        let predicciones = vec![Prediccion { prediccion: "x".to_string(), clase: Some("y, z".to_string()) },
+                                Prediccion { prediccion: "y".to_string(), clase: None }];
+        let ruta = d.join("predicciones.csv").to_string_lossy().into_owned();
+        escribir_predicciones(&predicciones, &ruta).unwrap();
+        assert_eq!(fs::read_to_string(&ruta).unwrap(), "dato,prediccion,clase\n1,x,\"y, z\"\n2,y,\n");
+    }
+}
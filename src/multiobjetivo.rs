@@ -0,0 +1,62 @@
+#[macro_use] extern crate clap;       // Gestor de argumentos del programa
+extern crate aprendizaje_pesos;  // Algoritmos y evaluación de pesos, comunes a todos los programas
+
+use aprendizaje_pesos::{cli, evaluacion_pesos, knn};
+use aprendizaje_pesos::funciones_multiobjetivo::*;
+use aprendizaje_pesos::presupuesto::Presupuesto;
+
+
+
+
+// Prueba un conjunto de datos con los algoritmos multiobjetivo implementados e imprime los resultados
+// Si se indica un prefijo, los frentes obtenidos en cada test se exportan a archivos CSV
+fn test(archivo: &str, presupuesto: Presupuesto, semilla: u64, validacion: evaluacion_pesos::Validacion, prefijo_frentes: Option<&str>) {
+    // Abrimos el archivo manejando posibles errores
+    let datos = knn::leer_archivo(archivo).unwrap_or_else(|e| {
+          println!("No se pudo abrir el archivo {}: {}", archivo, e); Vec::new()
+        });
+    if datos.is_empty() { return }
+
+    let lista_algoritmos: Vec<(AlgoritmoMO, &str)> = vec![
+            (nsga2_blx, "NSGA-II_BLX"),
+            (nsga2_ca, "NSGA-II_CA")
+        ];
+    for algoritmo in &lista_algoritmos {
+        println!("\n{} sobre los datos en {}...", algoritmo.1, archivo);
+        // Los frentes de cada algoritmo y archivo se distinguen añadiendo ambos nombres al prefijo
+        let prefijo = prefijo_frentes.map(|p| {
+            let nombre_archivo = std::path::Path::new(archivo).file_stem().map_or(archivo.into(), |n| n.to_string_lossy());
+            format!("{}-{}-{}", p, algoritmo.1, nombre_archivo)
+        });
+        evaluacion_pesos::ffcv_multiobjetivo(&algoritmo.0, &datos, presupuesto, semilla, validacion, prefijo.as_deref());
+    }
+}
+
+
+
+fn main() {
+    // Gestor de argumentos
+    let matches = clap_app!(multiobjetivo =>
+        (author: crate_authors!())
+        (about: "Aprendizaje de pesos multiobjetivo\n\nObtiene con NSGA-II el frente de Pareto de pesos que maximizan por separado la tasa de clasificación y la de reducción, utilizando una semilla maestra que puede ser un número o una cadena de texto.\n\nDe ella se derivan las semillas de cada test, que son las mismas para todos los algoritmos.")
+        (set_term_width: 79)
+        (@arg INPUT: "Archivo .arff con los datos de entrada. Si no se indica se efectúa con los tres archivos indicados en la práctica")
+        (@arg frentes: -f --frentes +takes_value "Prefijo de los archivos CSV en los que exportar el frente obtenido en cada partición")
+    ).args(&[cli::argumento_semilla(), cli::argumento_validacion(), cli::argumento_medida(), cli::argumento_config()])
+     .args(&cli::argumentos_limite_tiempo()).get_matches();
+
+    let semilla = cli::leer_semilla(&matches);
+    cli::leer_medida(&matches);
+    cli::leer_config(&matches);
+    let presupuesto = cli::leer_limite_tiempo(&matches);
+    let validacion = cli::leer_validacion(&matches);
+    let prefijo_frentes = matches.value_of("frentes");
+
+    if matches.is_present("INPUT") {
+        test(matches.value_of("INPUT").unwrap(), presupuesto, semilla, validacion, prefijo_frentes);
+    } else {
+        for archivo in &cli::INSTANCIAS {
+            test(archivo, presupuesto, semilla, validacion, prefijo_frentes);  // Si no se indica archivo, se usan los tres ofrecidos en la práctica
+        }
+    }
+}
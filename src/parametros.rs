@@ -0,0 +1,220 @@
+// Parámetros de los algoritmos, que antes eran constantes repartidas por los módulos de cada práctica
+// Se guardan por hilo de ejecución, como la medida de clasificación, para que los algoritmos los consulten
+//   sin cambiar su interfaz. Pueden leerse de la sección "parametros" de un archivo de experimento JSON
+
+use json;
+use json::Valor;
+use std::cell::Cell;
+use std::fs::File;
+use std::io::Read;
+
+
+// Conversión de los números de JSON al tipo de cada parámetro
+trait DesdeNumero: Sized {
+    fn desde_numero(x: f64) -> Option<Self>;
+}
+
+impl DesdeNumero for usize {
+    fn desde_numero(x: f64) -> Option<usize> {
+        if x >= 0.0 && x.fract() == 0.0 && x <= usize::MAX as f64 { Some(x as usize) } else { None }
+    }
+}
+
+impl DesdeNumero for f64 {
+    fn desde_numero(x: f64) -> Option<f64> {
+        if x.is_finite() { Some(x) } else { None }
+    }
+}
+
+// Valores admitidos por cada parámetro
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Rango {
+    Minimo(f64),  // Mayor o igual que el valor indicado
+    Positivo,     // Estrictamente mayor que 0
+    Unidad,       // Entre 0 y 1, ambos incluidos
+}
+
+impl Rango {
+    fn contiene(&self, x: f64) -> bool {
+        match *self {
+            Rango::Minimo(m) => x >= m,
+            Rango::Positivo => x > 0.0,
+            Rango::Unidad => (0.0..=1.0).contains(&x),
+        }
+    }
+
+    fn describir(&self) -> String {
+        match *self {
+            Rango::Minimo(m) => format!("debe ser al menos {}", m),
+            Rango::Positivo => "debe ser mayor que 0".to_string(),
+            Rango::Unidad => "debe estar entre 0 y 1".to_string(),
+        }
+    }
+}
+
+// Declara la estructura de parámetros con sus valores por defecto y rangos admitidos,
+//   y la lectura de cada uno por su nombre
+macro_rules! declarar_parametros {
+    ( $( $campo:ident : $tipo:ty = $defecto:expr ; $rango:expr ),* $(,)* ) => {
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        pub struct Parametros {
+            $( pub $campo: $tipo ),*
+        }
+
+        impl Default for Parametros {
+            fn default() -> Parametros {
+                Parametros::POR_DEFECTO
+            }
+        }
+
+        impl Parametros {
+            // Valores con los que se diseñaron los algoritmos en las prácticas
+            pub const POR_DEFECTO: Parametros = Parametros { $( $campo: $defecto ),* };
+
+            // Cambia el valor de un parámetro a partir de su nombre, comprobando que esté en su rango
+            fn asignar(&mut self, nombre: &str, valor: f64) -> Result<(), String> {
+                match nombre {
+                    $( stringify!($campo) => {
+                        let rango: Rango = $rango;
+                        if !rango.contiene(valor) {
+                            return Err(format!("valor no válido para el parámetro {}: {} ({})", nombre, valor, rango.describir()));
+                        }
+                        self.$campo = <$tipo as DesdeNumero>::desde_numero(valor)
+                            .ok_or_else(|| format!("valor no válido para el parámetro {}: {}", nombre, valor))?;
+                    }, )*
+                    _ => return Err(format!("parámetro desconocido: {}", nombre)),
+                }
+                Ok(())
+            }
+
+            // Representa los parámetros como objeto JSON, con el mismo formato con el que se leen
+            pub fn a_json(&self) -> Valor {
+                objeto_json!{ $( stringify!($campo) => self.$campo ),* }
+            }
+        }
+    };
+}
+
+declarar_parametros! {
+    max_evaluaciones: usize = 15000; Rango::Minimo(1.0),        // Máximo de evaluaciones de la función objetivo en cada algoritmo
+    max_ciclos_bl: usize = 20; Rango::Minimo(1.0),              // Tope de veces que la búsqueda local explora cada atributo sin que haya mejora
+    sigma_vecino: f64 = 0.3; Rango::Positivo,                   // Desviación típica de la mutación normal del operador de vecino de la búsqueda local
+    tamano_generacional: usize = 30; Rango::Minimo(4.0),        // Tamaño de la población de los algoritmos genéticos generacionales
+    tamano_estacionario: usize = 30; Rango::Minimo(4.0),        // Tamaño de la población de los algoritmos genéticos estacionarios
+    tasa_cruce_generacional: f64 = 0.7; Rango::Unidad,          // Probabilidad de cruce en los algoritmos genéticos generacionales
+    tasa_mutacion_gen: f64 = 0.001; Rango::Unidad,              // Probabilidad de mutación de cada gen en los algoritmos genéticos
+    alfa_blx: f64 = 0.3; Rango::Minimo(0.0),                    // Alfa del operador de cruce BLX
+    max_evaluaciones_bl_ils: usize = 1000; Rango::Minimo(1.0),  // Evaluaciones de cada búsqueda local en ILS
+    sigma_ils: f64 = 0.4; Rango::Positivo,                      // Desviación típica de la mutación brusca de ILS
+    factor_vecinos_es: usize = 10; Rango::Minimo(1.0),          // Factor del número de vecinos a explorar por iteración de ES
+    tamano_de: usize = 50; Rango::Minimo(4.0),                  // Tamaño de la población en evolución diferencial
+    de_cr: f64 = 0.5; Rango::Unidad,                            // Tasa de cruce por gen de evolución diferencial
+    de_f: f64 = 0.5; Rango::Positivo,                           // Factor de evolución diferencial
+    tamano_nsga2: usize = 50; Rango::Minimo(4.0),               // Tamaño de la población en NSGA-II
+    tasa_cruce_nsga2: f64 = 0.9; Rango::Unidad,                 // Probabilidad de cruzar cada pareja de padres seleccionada en NSGA-II
+    diversidad_reinicio: f64 = 0.0; Rango::Minimo(0.0),         // Distancia media entre cromosomas (normalizada, ver traza::diversidad) por debajo de la cual los genéticos reinician la población (0 para no reiniciarla nunca)
+    elite_reinicio: usize = 1; Rango::Minimo(0.0),              // Mejores cromosomas que se conservan al reiniciar la población
+    generaciones_control: usize = 10; Rango::Minimo(1.0),       // Generaciones entre cada vez que los genéticos guardan su población en el punto de control
+    vecinos_tabu: usize = 20; Rango::Minimo(1.0),               // Vecinos de la lista de candidatos de cada iteración de la búsqueda tabú
+    tenencia_tabu: f64 = 0.25; Rango::Unidad,                   // Iteraciones que un movimiento es tabú, como proporción del número de características
+    estancamiento_tabu: usize = 40; Rango::Minimo(1.0),         // Iteraciones sin mejorar la mejor solución tras las que la búsqueda tabú intensifica o diversifica
+    aciertos_cache: usize = 10; Rango::Minimo(0.0),             // Aciertos de la caché de evaluaciones por cada evaluación del presupuesto a partir de los cuales deja de responder (ver cache.rs)
+}
+
+impl Parametros {
+    // Cambia los parámetros que aparecen en un objeto JSON, dejando los demás como estaban
+    pub fn actualizar(&mut self, valor: &Valor) -> Result<(), String> {
+        match valor {
+            Valor::Objeto(campos) => {
+                for (nombre, v) in campos {
+                    let numero = v.como_numero().ok_or_else(|| format!("el parámetro {} no es un número", nombre))?;
+                    self.asignar(nombre, numero)?;
+                }
+                Ok(())
+            },
+            _ => Err("los parámetros deben ser un objeto JSON".to_string()),
+        }
+    }
+}
+
+
+// Parámetros con los que se ejecutan los algoritmos en cada hilo
+thread_local! {
+    static PARAMETROS: Cell<Parametros> = const { Cell::new(Parametros::POR_DEFECTO) };
+}
+
+// Obtiene los parámetros con los que se ejecutan los algoritmos en el hilo actual
+pub fn parametros() -> Parametros {
+    PARAMETROS.with(|p| p.get())
+}
+
+// Cambia los parámetros con los que se ejecutan los algoritmos en el hilo actual
+pub fn fijar_parametros(p: Parametros) {
+    PARAMETROS.with(|actual| actual.set(p));
+}
+
+
+// Lee un archivo JSON completo
+pub fn leer_json(ruta: &str) -> Result<Valor, String> {
+    let mut texto = String::new();
+    File::open(ruta).and_then(|mut f| f.read_to_string(&mut texto)).map_err(|e| format!("{}: {}", ruta, e))?;
+    json::analizar(&texto).map_err(|e| format!("{}: {}", ruta, e))
+}
+
+// Lee los parámetros de la sección "parametros" de un archivo de experimento,
+//   tomando los valores por defecto para los que no aparecen
+pub fn leer_parametros(ruta: &str) -> Result<Parametros, String> {
+    let mut p = Parametros::default();
+    if let Some(seccion) = leer_json(ruta)?.campo("parametros") {
+        p.actualizar(seccion).map_err(|e| format!("{}: {}", ruta, e))?;
+    }
+    Ok(p)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn con(texto: &str) -> Result<Parametros, String> {
+        let mut p = Parametros::default();
+        p.actualizar(&json::analizar(texto).unwrap()).map(|_| p)
+    }
+
+    #[test]
+    fn los_valores_por_defecto_son_validos() {
+        assert_eq!(con(&Parametros::default().a_json().to_string()), Ok(Parametros::default()));
+    }
+
+    #[test]
+    fn actualiza_solo_los_indicados() {
+        let p = con(r#"{"tamano_generacional": 60, "sigma_ils": 0.1}"#).unwrap();
+        assert_eq!((p.tamano_generacional, p.sigma_ils), (60, 0.1));
+        assert_eq!(Parametros { tamano_generacional: 30, sigma_ils: 0.4, ..p }, Parametros::default());
+        // Los extremos de los rangos se admiten
+        // (los algoritmos terminan también con tasas de cruce y mutación 0, ver los tests de funciones_practica2 y funciones_practica3)
+        let p = con(r#"{"tasa_cruce_generacional": 1, "tasa_mutacion_gen": 0, "de_cr": 1, "tamano_de": 4, "elite_reinicio": 0, "alfa_blx": 0}"#).unwrap();
+        assert_eq!((p.tasa_cruce_generacional, p.tasa_mutacion_gen, p.de_cr, p.tamano_de), (1.0, 0.0, 1.0, 4));
+    }
+
+    #[test]
+    fn rechaza_valores_fuera_de_rango() {
+        for texto in &[r#"{"tasa_cruce_generacional": 1.5}"#, r#"{"tasa_mutacion_gen": -0.1}"#, r#"{"tenencia_tabu": 2}"#,
+                       r#"{"tamano_generacional": 0}"#, r#"{"tamano_estacionario": 3}"#, r#"{"tamano_de": 3}"#, r#"{"tamano_nsga2": 1}"#,
+                       r#"{"sigma_vecino": 0}"#, r#"{"sigma_ils": -0.4}"#, r#"{"de_f": 0}"#, r#"{"alfa_blx": -0.1}"#,
+                       r#"{"vecinos_tabu": 0}"#, r#"{"max_evaluaciones": 0}"#, r#"{"generaciones_control": 0}"#,
+                       r#"{"diversidad_reinicio": -1}"#] {
+            assert!(con(texto).is_err(), "{} no debería admitirse", texto);
+        }
+        assert!(con(r#"{"tamano_de": 3}"#).unwrap_err().contains("debe ser al menos 4"));
+    }
+
+    #[test]
+    fn rechaza_parametros_mal_escritos() {
+        assert!(con(r#"{"tamano_generacional": 30.5}"#).is_err());
+        assert!(con(r#"{"tamano_generacional": "30"}"#).is_err());
+        assert!(con(r#"{"tamaño_generacional": 30}"#).unwrap_err().contains("desconocido"));
+        assert!(con("[30]").is_err());
+        assert!(con(r#"{"sigma_ils": null}"#).is_err());
+    }
+}
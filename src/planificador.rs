@@ -0,0 +1,337 @@
+// Planificador de experimentos
+// Divide la prueba de varios algoritmos en varios conjuntos de datos en tareas independientes, una por cada
+//   (conjunto de datos, algoritmo, test, ejecución), y las reparte entre varios hilos. Como cada tarea usa
+//   su propia semilla derivada de la maestra, los resultados no dependen del número de hilos ni del orden
+//   en que terminen las tareas, y se entregan en el mismo orden en que se obtendrían sin paralelizar
+// Si se indica un punto de control, se guarda en él cada tarea que termina y no se ejecutan las que ya contiene
+// Si se indica un archivo de trazas, se escribe en él la traza de convergencia de cada tarea que se ejecuta
+
+use aprendiz::Aprendiz;
+use cache;
+use evaluacion_pesos;
+use evaluacion_pesos::{Division, ResultadoTest, ResultadosCV, Validacion};
+use json::Valor;
+use knn::Dato;
+use parametros;
+use presupuesto::Presupuesto;
+use punto_control;
+use punto_control::PuntoControl;
+use rand::Isaac64Rng;
+use traza;
+use traza::{ArchivoTraza, PuntoTraza};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+
+// Datos de un conjunto: un archivo que se valida o particiones predefinidas
+pub enum Datos {
+    Archivo(Vec<Dato>),
+    Particiones(Vec<(Vec<Dato>, Vec<Dato>)>),
+}
+
+// Opciones con las que se generan y se reparten las tareas
+#[derive(Clone, Copy, Debug)]
+pub struct Planificacion {
+    pub semilla: u64,                                    // Semilla maestra
+    pub validacion: Validacion,
+    pub ejecuciones: usize,                              // Ejecuciones independientes de cada algoritmo en cada test
+    pub repetir: Option<(usize, usize, Option<usize>)>,  // Único test que se ejecuta, si se indica
+    pub hilos: usize,                                    // Hilos entre los que se reparten las tareas (0 para usar todos los núcleos)
+    pub presupuesto: Presupuesto,                        // Presupuesto de cada ejecución
+}
+
+// Tarea: una ejecución de un algoritmo en uno de los tests de un conjunto de datos
+struct Tarea {
+    conjunto: usize,
+    algoritmo: usize,
+    test: usize,        // Posición del test entre las divisiones del conjunto
+    ejecucion: usize,
+}
+
+
+// Número de hilos que se usan si se indica 0: uno por cada núcleo disponible
+pub fn hilos_disponibles() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+// Obtiene los tests de un conjunto de datos: las divisiones de la validación o las particiones predefinidas
+// Si se quiere repetir un solo test, se devuelve solo ese
+// Devuelve un error si la validación no se puede aplicar a los datos (ver evaluacion_pesos::divisiones)
+fn tests<'a>(datos: &'a Datos, plan: &Planificacion) -> Result<Vec<Division<'a>>, String> {
+    let mut tests = match datos {
+        Datos::Archivo(d) => evaluacion_pesos::divisiones(d, plan.validacion, plan.semilla)?,
+        Datos::Particiones(p) => p.iter().enumerate().map(|(i, (entrenamiento, test))| Division {
+            repeticion: 0,
+            particion: i,
+            entrenamiento: entrenamiento.iter().collect(),
+            test: test.iter().collect(),
+        }).collect(),
+    };
+    if let Some((r, p, _)) = plan.repetir {
+        // Las particiones predefinidas solo tienen una repetición, así que en ellas se ignora
+        let r = if let Datos::Particiones(_) = datos { 0 } else { r };
+        tests.retain(|t| t.repeticion == r && t.particion == p);
+    }
+    Ok(tests)
+}
+
+// Comprueba que un algoritmo se puede aplicar a todos los datos de un conjunto (ver Aprendiz::comprobar)
+fn comprobar(aprendiz: &dyn Aprendiz, datos: &Datos) -> Result<(), String> {
+    match datos {
+        Datos::Archivo(d) => aprendiz.comprobar(d),
+        Datos::Particiones(p) => p.iter().try_for_each(|(entrenamiento, test)| {
+            aprendiz.comprobar(entrenamiento).and_then(|_| aprendiz.comprobar(test))
+        }),
+    }
+}
+
+// Ejecuta cada algoritmo en cada conjunto de datos según la planificación, repartiendo las tareas
+// Los algoritmos se identifican en el punto de control por su descripción (ver Aprendiz::a_json) y en las trazas
+//   por su nombre, y los conjuntos siempre por su nombre
+// Cada vez que terminan todos los tests de un algoritmo en un conjunto, y los de los anteriores, se llama
+//   a al_terminar con el índice del conjunto, el del algoritmo y sus resultados, siempre en orden
+// Devuelve los conjuntos en los que no hay ningún test que ejecutar (por ejemplo, si el test que se
+//   quiere repetir no existe en ellos), que se omiten. Los conjuntos en los que no se puede aplicar la
+//   validación también se omiten, avisando del motivo, así como los algoritmos que no se pueden aplicar
+//   a un conjunto (como unos pesos fijos con otro número de atributos)
+pub fn ejecutar<F>(algoritmos: &[&dyn Aprendiz], conjuntos: &[(&str, Datos)], plan: &Planificacion,
+                   mut control: Option<&mut PuntoControl>, mut trazas: Option<&mut ArchivoTraza>, mut al_terminar: F) -> Vec<usize>
+    where F: FnMut(usize, usize, ResultadosCV) {
+    let tests: Vec<Result<Vec<Division>, String>> = conjuntos.iter().map(|d| tests(&d.1, plan)).collect();
+    let vacios: Vec<usize> = (0..conjuntos.len()).filter(|c| tests[*c].as_ref().is_ok_and(|t| t.is_empty())).collect();
+    let tests: Vec<Vec<Division>> = tests.into_iter().zip(conjuntos).map(|(t, d)| t.unwrap_or_else(|e| {
+        println!("No se puede validar con los datos de {}: {}", d.0, e);
+        Vec::new()
+    })).collect();
+    let ejecuciones = match plan.repetir {
+        Some((_, _, Some(e))) => e..e+1,
+        _ => 0..plan.ejecuciones,
+    };
+
+    // Las tareas se ordenan por conjunto, algoritmo, test y ejecución, como si se hiciesen en secuencia,
+    //   y se agrupan por conjunto y algoritmo para entregar juntos sus resultados
+    let mut tareas = Vec::new();
+    let mut grupos = Vec::new();
+    for conjunto in (0..conjuntos.len()).filter(|c| !tests[*c].is_empty()) {
+        for (algoritmo, aprendiz) in algoritmos.iter().enumerate() {
+            if let Err(e) = comprobar(*aprendiz, &conjuntos[conjunto].1) {
+                println!("No se puede ejecutar {} con los datos de {}: {}", aprendiz.nombre(), conjuntos[conjunto].0, e);
+                continue;
+            }
+            let inicio = tareas.len();
+            for test in 0..tests[conjunto].len() {
+                for ejecucion in ejecuciones.clone() {
+                    tareas.push(Tarea { conjunto, algoritmo, test, ejecucion });
+                }
+            }
+            grupos.push(inicio..tareas.len());
+        }
+    }
+
+    // Las tareas que ya están en el punto de control se recuperan de él y solo se ejecutan las demás
+    // Se identifican por la descripción completa de su algoritmo, ya que dos algoritmos con el mismo nombre
+    //   pueden tener distintos parámetros
+    let claves: Vec<Valor> = algoritmos.iter().map(|a| a.a_json()).collect();
+    let mut resultados: Vec<Option<ResultadoTest>> = tareas.iter().map(|t| {
+        let division = &tests[t.conjunto][t.test];
+        control.as_ref().and_then(|c| c.buscar(&claves[t.algoritmo], conjuntos[t.conjunto].0, plan.semilla,
+                                                division.repeticion, division.particion, t.ejecucion))
+    }).collect();
+    let pendientes: Vec<usize> = (0..tareas.len()).filter(|i| resultados[*i].is_none()).collect();
+    if pendientes.len() < tareas.len() {
+        println!("Se recuperan {} tests del punto de control", tareas.len() - pendientes.len());
+    }
+    let prefijo_poblaciones = control.as_ref().and_then(|c| c.prefijo_poblaciones());
+    let trazar = trazas.is_some();
+    let mut puntos: Vec<Vec<PuntoTraza>> = vec![Vec::new(); tareas.len()];
+
+    // La medida de clasificación, los parámetros y el modo de la caché se guardan por hilo,
+    //   así que cada hilo toma los del que lo lanza
+    let medida = evaluacion_pesos::medida_clasificacion();
+    let parametros = parametros::parametros();
+    let modo_cache = cache::modo_cache();
+    let hilos = if plan.hilos == 0 { hilos_disponibles() } else { plan.hilos };
+    let siguiente = AtomicUsize::new(0);
+    let (emisor, receptor) = mpsc::channel::<(usize, ResultadoTest, Vec<PuntoTraza>)>();
+
+    thread::scope(|s| {
+        for _ in 0..hilos.min(pendientes.len()) {
+            let emisor = emisor.clone();
+            let (tareas, tests, pendientes, siguiente, prefijo, claves) = (&tareas, &tests, &pendientes, &siguiente, &prefijo_poblaciones, &claves);
+            s.spawn(move || {
+                evaluacion_pesos::fijar_medida_clasificacion(medida);
+                parametros::fijar_parametros(parametros);
+                cache::fijar_modo_cache(modo_cache);
+                while let Some(&i) = pendientes.get(siguiente.fetch_add(1, Ordering::SeqCst)) {
+                    let tarea = &tareas[i];
+                    let aprendiz = algoritmos[tarea.algoritmo];
+                    let division = &tests[tarea.conjunto][tarea.test];
+                    let entrenamiento: Vec<Dato> = division.entrenamiento.iter().map(|d| (*d).clone()).collect();
+                    let test: Vec<Dato> = division.test.iter().map(|d| (*d).clone()).collect();
+                    punto_control::fijar_archivo_poblacion(prefijo.as_ref().map(|p| {
+                        let semilla_test = evaluacion_pesos::semilla_test(plan.semilla, division.repeticion, division.particion, tarea.ejecucion);
+                        punto_control::archivo_poblacion(p, &claves[tarea.algoritmo], conjuntos[tarea.conjunto].0, semilla_test)
+                    }));
+                    let (resultado, traza) = traza::grabar(trazar, || {
+                        evaluacion_pesos::evaluar_test(&|d: &[Dato], rng: &mut Isaac64Rng| aprendiz.aprender(d, plan.presupuesto, rng), &entrenamiento, &test, division.repeticion,
+                                                       division.particion, tarea.ejecucion, plan.semilla)
+                    });
+                    punto_control::borrar_poblacion();
+                    if emisor.send((i, resultado, traza)).is_err() { break }
+                }
+            });
+        }
+        drop(emisor);
+
+        // Se guardan los resultados según llegan y se entregan los grupos completos en orden,
+        //   empezando por los que se han recuperado enteros del punto de control
+        let mut grupo = 0;
+        let mut entregar = |resultados: &mut Vec<Option<ResultadoTest>>, puntos: &mut Vec<Vec<PuntoTraza>>| {
+            while grupo < grupos.len() && resultados[grupos[grupo].clone()].iter().all(|r| r.is_some()) {
+                let tests: Vec<ResultadoTest> = resultados[grupos[grupo].clone()].iter_mut().map(|r| r.take().unwrap()).collect();
+                let primera = &tareas[grupos[grupo].start];
+                let (algoritmo, conjunto) = (algoritmos[primera.algoritmo].nombre(), conjuntos[primera.conjunto].0);
+                if let Some(t) = trazas.as_mut() {
+                    for (test, i) in tests.iter().zip(grupos[grupo].clone()) {
+                        if let Err(e) = t.escribir(algoritmo, conjunto, test, &puntos[i]) {
+                            println!("No se pudo escribir la traza: {}", e);
+                        }
+                        puntos[i] = Vec::new();
+                    }
+                }
+                al_terminar(primera.conjunto, primera.algoritmo, ResultadosCV { tests });
+                grupo += 1;
+            }
+        };
+        entregar(&mut resultados, &mut puntos);
+        for (i, resultado, traza) in receptor {
+            if let Some(c) = control.as_mut() {
+                let tarea = &tareas[i];
+                if let Err(e) = c.guardar(&claves[tarea.algoritmo], algoritmos[tarea.algoritmo].nombre(), conjuntos[tarea.conjunto].0, &resultado) {
+                    println!("No se pudo guardar el test en el punto de control: {}", e);
+                }
+            }
+            resultados[i] = Some(resultado);
+            puntos[i] = traza;
+            entregar(&mut resultados, &mut puntos);
+        }
+    });
+    vacios
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aprendiz::{Funcion, PesosFijos};
+    use presupuesto::Evaluador;
+    use rand::Rng;
+
+    fn plan() -> Planificacion {
+        Planificacion { semilla: 1, validacion: Validacion::KFold(2, 1), ejecuciones: 1, repetir: None, hilos: 2, presupuesto: Presupuesto::default() }
+    }
+
+    fn datos(n_atributos: usize) -> Datos {
+        Datos::Archivo((0..8).map(|i| Dato::new(&vec![i as f64 / 8.0; n_atributos], &[], i % 2)).collect())
+    }
+
+    // Aprendiz que devuelve pesos aleatorios, para comprobar con qué semilla se ejecuta
+    fn aleatorio(_: &[Dato], _: &Evaluador, rng: &mut Isaac64Rng) -> Vec<f64> {
+        vec![rng.gen()]
+    }
+
+    #[test]
+    fn un_test_repetido_coincide_con_el_de_la_validacion() {
+        let algoritmo = Funcion::nueva("aleatorio", "Aleatorio", aleatorio);
+        let conjuntos = [("datos", datos(1))];
+        let validar = |plan: &Planificacion| -> Vec<ResultadoTest> {
+            let mut tests = Vec::new();
+            ejecutar(&[&algoritmo], &conjuntos, plan, None, None, |_, _, r| tests.extend(r.tests));
+            tests
+        };
+        let todos = validar(&Planificacion { validacion: Validacion::KFold(2, 2), ejecuciones: 2, ..plan() });
+        assert_eq!(todos.len(), 8);
+        let uno = validar(&Planificacion { validacion: Validacion::KFold(2, 2), ejecuciones: 2, repetir: Some((1, 0, Some(1))), hilos: 1, ..plan() });
+        assert_eq!(uno.len(), 1);
+        let mismo = todos.iter().find(|t| (t.repeticion, t.particion, t.ejecucion) == (1, 0, 1)).unwrap();
+        assert_eq!((uno[0].semilla_test, &uno[0].pesos), (mismo.semilla_test, &mismo.pesos));
+        assert_eq!(uno[0].semilla_test, evaluacion_pesos::semilla_test(1, 1, 0, 1));
+        // Cada ejecución de cada test tiene su propia semilla
+        assert!(todos.iter().enumerate().all(|(i, t)| todos[i+1..].iter().all(|u| u.semilla_test != t.semilla_test)));
+    }
+
+    #[test]
+    fn omite_los_algoritmos_que_no_se_pueden_aplicar() {
+        let fijos = PesosFijos::nuevos("fijos", vec![1.0, 1.0]).unwrap();
+        let conjuntos = [("uno", datos(1)), ("dos", datos(2))];
+        let mut terminados = Vec::new();
+        let vacios = ejecutar(&[&fijos], &conjuntos, &plan(), None, None, |c, a, r| terminados.push((c, a, r.tests.len())));
+        assert!(vacios.is_empty());
+        assert_eq!(terminados, vec![(1, 0, 2)]);
+    }
+
+    // Aprendiz que devuelve como peso un parámetro del hilo en el que se ejecuta
+    fn parametro_del_hilo(_: &[Dato], _: &Evaluador, _: &mut Isaac64Rng) -> Vec<f64> {
+        vec![parametros::parametros().sigma_ils]
+    }
+
+    // Ejecuta los algoritmos y devuelve los resultados en el orden en que se entregan
+    fn resultados(algoritmos: &[&dyn Aprendiz], conjuntos: &[(&str, Datos)], plan: &Planificacion) -> Vec<(usize, usize, Vec<ResultadoTest>)> {
+        let mut entregados = Vec::new();
+        ejecutar(algoritmos, conjuntos, plan, None, None, |c, a, r| entregados.push((c, a, r.tests)));
+        entregados
+    }
+
+    #[test]
+    fn resultados_en_orden_e_independientes_de_los_hilos() {
+        let (a, b) = (Funcion::nueva("aleatorio", "A", aleatorio), Funcion::nueva("aleatorio", "B", aleatorio));
+        let conjuntos = [("uno", datos(1)), ("dos", datos(1))];
+        let plan = Planificacion { validacion: Validacion::KFold(2, 2), ejecuciones: 3, ..plan() };
+        let secuencial = resultados(&[&a, &b], &conjuntos, &Planificacion { hilos: 1, ..plan });
+        assert_eq!(secuencial.iter().map(|r| (r.0, r.1, r.2.len())).collect::<Vec<_>>(), vec![(0, 0, 12), (0, 1, 12), (1, 0, 12), (1, 1, 12)]);
+        let posiciones: Vec<(usize, usize, usize)> = secuencial[0].2.iter().map(|t| (t.repeticion, t.particion, t.ejecucion)).collect();
+        assert_eq!(posiciones, iproduct!(0..2, 0..2, 0..3).collect::<Vec<_>>());
+        // Los algoritmos con la misma clave usan las mismas semillas, y los conjuntos con los mismos datos también
+        assert_eq!(secuencial[0].2.iter().map(|t| &t.pesos).collect::<Vec<_>>(), secuencial[3].2.iter().map(|t| &t.pesos).collect::<Vec<_>>());
+
+        for hilos in &[0, 3, 16] {
+            let paralelo = resultados(&[&a, &b], &conjuntos, &Planificacion { hilos: *hilos, ..plan });
+            assert_eq!(paralelo.len(), secuencial.len());
+            for (p, s) in paralelo.iter().zip(&secuencial) {
+                assert_eq!((p.0, p.1), (s.0, s.1));
+                assert_eq!(p.2.iter().map(|t| (t.semilla_test, &t.pesos)).collect::<Vec<_>>(), s.2.iter().map(|t| (t.semilla_test, &t.pesos)).collect::<Vec<_>>());
+            }
+        }
+    }
+
+    #[test]
+    fn los_hilos_usan_los_parametros_del_que_los_lanza() {
+        parametros::fijar_parametros(parametros::Parametros { sigma_ils: 0.125, ..Default::default() });
+        let algoritmo = Funcion::nueva("parametro", "Parámetro", parametro_del_hilo);
+        let r = resultados(&[&algoritmo], &[("datos", datos(1))], &Planificacion { hilos: 4, ..plan() });
+        assert!(r[0].2.iter().all(|t| t.pesos == vec![0.125]));
+    }
+
+    #[test]
+    fn particiones_predefinidas_y_conjuntos_vacios() {
+        let algoritmo = Funcion::nueva("aleatorio", "Aleatorio", aleatorio);
+        let particion = |desde: usize| -> (Vec<Dato>, Vec<Dato>) {
+            ((desde..desde + 4).map(|i| Dato::new(&[i as f64], &[], (i % 2) as i32)).collect(), vec![Dato::new(&[0.5], &[], 0)])
+        };
+        let conjuntos = [("particiones", Datos::Particiones(vec![particion(0), particion(4), particion(8)])),
+                         ("pocos", Datos::Archivo(vec![Dato::new(&[0.0], &[], 0), Dato::new(&[1.0], &[], 1)])),
+                         ("datos", datos(1))];
+        // Con dos datos no se pueden hacer 4 particiones: ese conjunto se omite sin contarse como vacío
+        let plan = Planificacion { validacion: Validacion::KFold(4, 1), ..plan() };
+        let r = resultados(&[&algoritmo], &conjuntos, &plan);
+        assert_eq!(r.iter().map(|r| (r.0, r.2.len())).collect::<Vec<_>>(), vec![(0, 3), (2, 4)]);
+
+        // Al repetir un test que solo existe en la validación, las particiones quedan vacías
+        let mut entregados = Vec::new();
+        let vacios = ejecutar(&[&algoritmo], &conjuntos[..], &Planificacion { validacion: Validacion::KFold(4, 2), repetir: Some((1, 3, None)), ..plan }, None, None,
+                              |c, _, r| entregados.push((c, r.tests.len())));
+        assert_eq!(vacios, vec![0]);
+        assert_eq!(entregados, vec![(2, 1)]);
+    }
+}
@@ -0,0 +1,224 @@
+// Presupuesto de evaluaciones de la función objetivo, común a todos los algoritmos
+// Antes cada algoritmo controlaba el suyo a su manera: la búsqueda local contaba las iteraciones de su bucle,
+//   los meméticos calculaban con una fórmula las evaluaciones de sus búsquedas locales, ILS hacía un número
+//   fijo de iteraciones y el generacional terminaba la generación aunque se pasase del máximo
+// Ahora cada ejecución de un algoritmo recibe un Evaluador, por el que pasa toda evaluación (ver Problema::evaluar).
+//   El evaluador la cuenta y, una vez agotado el presupuesto, ya no llama a la función objetivo sino que devuelve
+//   menos infinito, de forma que ninguna solución evaluada de más puede sustituir a las anteriores. Los algoritmos
+//   consultan agotado para terminar, y el número de evaluaciones usadas es siempre el que se ha contado, sin
+//   pasarse del máximo
+// El presupuesto se agota al llegar al máximo de evaluaciones, al pasar un tiempo límite o al alcanzar una
+//   puntuación objetivo, y los procedimientos internos (como la búsqueda local de un memético) pueden limitarse
+//   a una parte del presupuesto con con_limite
+
+use parametros::parametros;
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+
+// Criterios de parada de los algoritmos
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Presupuesto {
+    pub evaluaciones: Option<usize>,  // Máximo de evaluaciones. Si no se indica, el parámetro max_evaluaciones
+    pub tiempo: Option<Duration>,     // Tiempo máximo desde el inicio del algoritmo
+    pub objetivo: Option<f64>,        // Puntuación a partir de la cual se da el problema por resuelto
+}
+
+impl Presupuesto {
+    // Máximo de evaluaciones con el que se ejecutan los algoritmos
+    pub fn max_evaluaciones(&self) -> usize {
+        self.evaluaciones.unwrap_or_else(|| parametros().max_evaluaciones)
+    }
+}
+
+// Estado del presupuesto de una ejecución
+struct Consumo {
+    usadas: usize,          // Evaluaciones hechas desde el inicio
+    inicio: Instant,
+    mejor: f64,             // Mejor puntuación obtenida
+    limites: Vec<usize>,    // Evaluaciones a las que terminan los procedimientos internos en curso (ver con_limite)
+}
+
+// Cuenta las evaluaciones de una ejecución de un algoritmo y decide cuándo se agota su presupuesto
+// Se crea al empezar cada ejecución, con el reloj en marcha, y los algoritmos lo pasan a todo lo que evalúa
+pub struct Evaluador {
+    presupuesto: Presupuesto,
+    consumo: RefCell<Consumo>,
+}
+
+impl Evaluador {
+    // Empieza a consumir un presupuesto desde cero: sin evaluaciones, sin mejor puntuación y con el reloj en marcha
+    pub fn nuevo(presupuesto: Presupuesto) -> Evaluador {
+        let consumo = Consumo { usadas: 0, inicio: Instant::now(), mejor: f64::NEG_INFINITY, limites: Vec::new() };
+        Evaluador { presupuesto, consumo: RefCell::new(consumo) }
+    }
+
+    // Presupuesto que consume
+    pub fn presupuesto(&self) -> Presupuesto {
+        self.presupuesto
+    }
+
+    // Número de evaluaciones hechas desde el inicio
+    pub fn usadas(&self) -> usize {
+        self.consumo.borrow().usadas
+    }
+
+    // Suma evaluaciones hechas antes, como las de un algoritmo que se reanuda
+    pub fn anadir(&self, n: usize) {
+        self.consumo.borrow_mut().usadas += n;
+    }
+
+    // Tiempo transcurrido desde el inicio
+    pub fn transcurrido(&self) -> Duration {
+        self.consumo.borrow().inicio.elapsed()
+    }
+
+    // Fracción del tiempo límite que ha transcurrido, si lo hay
+    pub fn fraccion_tiempo(&self) -> Option<f64> {
+        self.presupuesto.tiempo.map(|t| self.transcurrido().as_secs_f64() / t.as_secs_f64())
+    }
+
+    // Evaluaciones que quedan hasta el máximo, o hasta el límite del procedimiento interno en curso si es menor
+    pub fn restantes(&self) -> usize {
+        let maximo = self.presupuesto.max_evaluaciones();
+        let c = self.consumo.borrow();
+        let limite = c.limites.iter().cloned().fold(maximo, usize::min);
+        limite.saturating_sub(c.usadas)
+    }
+
+    // Indica si se ha agotado el presupuesto (o el límite del procedimiento interno en curso)
+    pub fn agotado(&self) -> bool {
+        self.restantes() == 0
+            || self.presupuesto.tiempo.is_some_and(|t| self.transcurrido() >= t)
+            || self.presupuesto.objetivo.is_some_and(|o| self.consumo.borrow().mejor >= o)
+    }
+
+    // Evalúa una solución con la función indicada si queda presupuesto, y cuenta la evaluación
+    // Si no queda, devuelve None sin llamar a la función
+    pub fn evaluar_con<T, F: FnOnce() -> T>(&self, funcion: F) -> Option<T> {
+        if self.agotado() {
+            return None;
+        }
+        self.consumo.borrow_mut().usadas += 1;
+        Some(funcion())
+    }
+
+    // Evalúa una solución como evaluar_con, devolviendo menos infinito si no queda presupuesto,
+    //   y registra la puntuación para el criterio de parada de la puntuación objetivo
+    pub fn evaluar<F: FnOnce() -> f64>(&self, funcion: F) -> f64 {
+        match self.evaluar_con(funcion) {
+            Some(v) => {
+                let mut c = self.consumo.borrow_mut();
+                if v > c.mejor { c.mejor = v }
+                v
+            },
+            None => f64::NEG_INFINITY,
+        }
+    }
+
+    // Ejecuta un procedimiento que solo puede hacer como mucho n evaluaciones más, sin salirse del presupuesto
+    // Uso: evaluador.con_limite(2*n, || busqueda_local(...))
+    pub fn con_limite<T, F: FnOnce() -> T>(&self, n: usize, procedimiento: F) -> T {
+        {
+            let mut c = self.consumo.borrow_mut();
+            let limite = c.usadas.saturating_add(n);
+            c.limites.push(limite);
+        }
+        let resultado = procedimiento();
+        self.consumo.borrow_mut().limites.pop();
+        resultado
+    }
+}
+
+
+
+// Evaluador con un máximo de evaluaciones, con el que se prueban los algoritmos
+#[cfg(test)]
+pub mod pruebas {
+    use super::*;
+
+    pub fn evaluador(evaluaciones: usize) -> Evaluador {
+        Evaluador::nuevo(Presupuesto { evaluaciones: Some(evaluaciones), ..Default::default() })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn con_evaluaciones(n: usize) -> Presupuesto {
+        Presupuesto { evaluaciones: Some(n), ..Default::default() }
+    }
+
+    #[test]
+    fn no_evalua_despues_de_agotarse() {
+        let evaluador = Evaluador::nuevo(con_evaluaciones(3));
+        let puntuaciones: Vec<f64> = (0..5).map(|i| evaluador.evaluar(|| i as f64)).collect();
+        assert_eq!(puntuaciones, vec![0.0, 1.0, 2.0, f64::NEG_INFINITY, f64::NEG_INFINITY]);
+        assert_eq!((evaluador.usadas(), evaluador.restantes()), (3, 0));
+        assert!(evaluador.agotado());
+        assert_eq!(evaluador.evaluar_con(|| panic!("no se debe llamar a la función")), None::<()>);
+    }
+
+    #[test]
+    fn los_limites_internos_se_anidan() {
+        let evaluador = Evaluador::nuevo(con_evaluaciones(10));
+        evaluador.evaluar(|| 0.0);
+        let interna = evaluador.con_limite(5, || {
+            assert_eq!(evaluador.restantes(), 5);
+            // Un límite interno mayor que el del procedimiento que lo contiene no lo amplía
+            evaluador.con_limite(20, || assert_eq!(evaluador.restantes(), 5));
+            evaluador.con_limite(2, || {
+                while !evaluador.agotado() { evaluador.evaluar(|| 0.0); }
+            });
+            assert_eq!(evaluador.restantes(), 3);
+            while !evaluador.agotado() { evaluador.evaluar(|| 0.0); }
+            evaluador.usadas()
+        });
+        assert_eq!(interna, 6);
+        assert_eq!(evaluador.restantes(), 4);
+        // Un límite mayor que lo que queda no se sale del presupuesto
+        evaluador.con_limite(100, || while !evaluador.agotado() { evaluador.evaluar(|| 0.0); });
+        assert_eq!(evaluador.usadas(), 10);
+    }
+
+    #[test]
+    fn se_detiene_al_alcanzar_el_objetivo() {
+        let evaluador = Evaluador::nuevo(Presupuesto { objetivo: Some(0.5), ..con_evaluaciones(100) });
+        let mut i = 0;
+        while !evaluador.agotado() {
+            evaluador.evaluar(|| i as f64 / 10.0);
+            i += 1;
+        }
+        assert_eq!(evaluador.usadas(), 6);
+        assert!(evaluador.restantes() > 0);
+    }
+
+    #[test]
+    fn se_detiene_al_pasar_el_tiempo() {
+        let evaluador = Evaluador::nuevo(Presupuesto { tiempo: Some(Duration::from_millis(20)), ..con_evaluaciones(100) });
+        assert_eq!(evaluador.evaluar(|| 1.0), 1.0);
+        thread::sleep(Duration::from_millis(30));
+        assert!(evaluador.agotado());
+        assert_eq!(evaluador.evaluar(|| 1.0), f64::NEG_INFINITY);
+        assert_eq!(evaluador.usadas(), 1);
+    }
+
+    #[test]
+    fn fraccion_del_tiempo_limite() {
+        assert_eq!(Evaluador::nuevo(con_evaluaciones(10)).fraccion_tiempo(), None);
+        let evaluador = Evaluador::nuevo(Presupuesto { tiempo: Some(Duration::from_millis(20)), ..Default::default() });
+        assert!(evaluador.fraccion_tiempo().unwrap() < 1.0);
+        thread::sleep(Duration::from_millis(30));
+        assert!(evaluador.fraccion_tiempo().unwrap() >= 1.0);
+    }
+
+    #[test]
+    fn reanuda_con_las_evaluaciones_anteriores() {
+        let evaluador = Evaluador::nuevo(con_evaluaciones(10));
+        evaluador.anadir(8);
+        assert_eq!(evaluador.restantes(), 2);
+    }
+}
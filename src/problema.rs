@@ -0,0 +1,172 @@
+// Problemas de optimización continua que resuelven las metaheurísticas
+// Los algoritmos generales (búsqueda local, genéticos y meméticos, enfriamiento simulado, ILS y evolución
+//   diferencial) no dependen de los datos ni de la función objetivo del aprendizaje de pesos: solo necesitan
+//   la dimensión de las soluciones, sus límites y una función que las puntúe, que se maximiza
+// El aprendizaje de pesos es uno de estos problemas (PonderacionCaracteristicas), y otros pueden
+//   implementar el rasgo Problema para optimizarse con los mismos algoritmos
+
+use evaluacion_pesos;
+use funciones_practica1;
+use knn::Dato;
+use presupuesto::Evaluador;
+use rand::Rng;
+use rand::distributions::{Sample, Normal};
+
+
+// Problema de optimización continua: maximizar una función sobre vectores de números reales acotados
+pub trait Problema {
+    // Número de componentes de las soluciones
+    fn dimension(&self) -> usize;
+
+    // Límites inferior y superior de la componente i-ésima. Por defecto, [0, 1]
+    fn limites(&self, _i: usize) -> (f64, f64) {
+        (0.0, 1.0)
+    }
+
+    // Puntuación de una solución, que los algoritmos maximizan
+    fn funcion_objetivo(&self, x: &[f64]) -> f64;
+
+    // Puntúa una solución con cargo al presupuesto del evaluador, devolviendo menos infinito si se ha agotado (ver presupuesto.rs)
+    // Es la que usan los algoritmos, para que el presupuesto y las trazas cuenten igual en todos los problemas
+    fn evaluar(&self, evaluador: &Evaluador, x: &[f64]) -> f64 {
+        evaluador.evaluar(|| self.funcion_objetivo(x))
+    }
+
+    // Lleva una solución a los límites del problema. Por defecto, trunca cada componente a sus límites
+    fn reparar(&self, x: &mut [f64]) {
+        for (i, xi) in x.iter_mut().enumerate() {
+            *xi = acotar(*xi, self.limites(i));
+        }
+    }
+
+    // Transforma una solución en otra equivalente con una forma canónica, si el problema la tiene
+    // Por defecto no hace nada
+    fn normalizar(&self, _x: &mut [f64]) {}
+}
+
+// Tipos de dato de los procedimientos con los que se configuran los algoritmos generales
+pub type GenInicial<'a, Trng> = dyn Fn(&dyn Problema, &mut Trng) -> Vec<f64> + 'a;                       // Genera una solución inicial del problema
+pub type Vecino<'a, Trng> = dyn Fn(&[f64], usize, &mut Trng) -> Vec<f64> + 'a;                           // Mueve la componente indicada de una solución
+pub type Cruce<'a, Trng> = dyn Fn(&[f64], &[f64], &mut Trng) -> Vec<Vec<f64>> + 'a;                      // Cruza dos padres y devuelve sus hijos
+pub type BusquedaLocal<'a, Trng> = dyn Fn(&dyn Problema, &[f64], &Evaluador, &mut Trng) -> Vec<f64> + 'a;  // Mejora una solución del problema
+
+// Trunca un valor a unos límites
+pub fn acotar(valor: f64, (inferior, superior): (f64, f64)) -> f64 {
+    if valor < inferior { inferior } else if valor > superior { superior } else { valor }
+}
+
+
+
+// Aprendizaje de pesos: maximizar la puntuación de unos pesos en [0, 1] sobre unos datos de entrenamiento,
+//   que agrega la tasa de clasificación 1-NN con leave-one-out y la de reducción (ver evaluacion_pesos::evaluar)
+// Las soluciones se normalizan para que el mayor peso sea 1
+pub struct PonderacionCaracteristicas<'a> {
+    datos: &'a [Dato],
+}
+
+impl<'a> PonderacionCaracteristicas<'a> {
+    pub fn nueva(datos: &'a [Dato]) -> PonderacionCaracteristicas<'a> {
+        PonderacionCaracteristicas { datos }
+    }
+}
+
+impl<'a> Problema for PonderacionCaracteristicas<'a> {
+    fn dimension(&self) -> usize {
+        self.datos[0].num_atributos()
+    }
+
+    fn funcion_objetivo(&self, w: &[f64]) -> f64 {
+        evaluacion_pesos::puntuar(self.datos, w)
+    }
+
+    // Se evalúa como en el resto de algoritmos de aprendizaje de pesos, para que use la caché si está activa
+    fn evaluar(&self, evaluador: &Evaluador, w: &[f64]) -> f64 {
+        evaluacion_pesos::evaluar(evaluador, self.datos, w)
+    }
+
+    fn normalizar(&self, w: &mut [f64]) {
+        funciones_practica1::normalizar(w);
+    }
+}
+
+
+
+// Operadores para cualquier problema, que respetan sus límites
+// Los del aprendizaje de pesos (vector_au, vecino_bl, blx_03...) son los de las prácticas, que
+//   asumen que las soluciones están en [0, 1] y normalizadas
+
+
+// Genera una solución aleatoria con cada componente uniforme entre sus límites
+pub fn solucion_aleatoria<Trng: Rng>(problema: &dyn Problema, rng: &mut Trng) -> Vec<f64> {
+    let mut x: Vec<f64> = (0..problema.dimension()).map(|i| {
+        let (inferior, superior) = problema.limites(i);
+        if inferior < superior { rng.gen_range(inferior, superior) } else { inferior }
+    }).collect();
+    problema.normalizar(&mut x);
+    x
+}
+
+// Muta la componente i-ésima de una solución sumándole una normal de media 0 y desviación típica
+//   sigma por la amplitud de sus límites, y repara el resultado
+pub fn mutacion_normal<Trng: Rng>(problema: &dyn Problema, x: &[f64], i: usize, sigma: f64, rng: &mut Trng) -> Vec<f64> {
+    let (inferior, superior) = problema.limites(i);
+    let mut nx = x.to_vec();
+    nx[i] += Normal::new(0.0, sigma*(superior - inferior)).sample(rng);
+    problema.reparar(&mut nx);
+    problema.normalizar(&mut nx);
+    nx
+}
+
+// Operador de cruce BLX-alfa: devuelve dos hijos con cada componente uniforme en el intervalo que forman
+//   las de los padres, ampliado en alfa veces su amplitud por cada lado, y reparados
+pub fn cruce_blx<Trng: Rng>(problema: &dyn Problema, padre1: &[f64], padre2: &[f64], alfa: f64, rng: &mut Trng) -> Vec<Vec<f64>> {
+    (0..2).map(|_| {
+        let mut hijo: Vec<f64> = padre1.iter().zip(padre2.iter()).map(|(x, y)| {
+            let (cmin, cmax) = if x < y { (*x, *y) } else { (*y, *x) };
+            let ai = (cmax - cmin)*alfa;
+            if ai == 0.0 { cmin } else { rng.gen_range(cmin - ai, cmax + ai) }
+        }).collect();
+        problema.reparar(&mut hijo);
+        problema.normalizar(&mut hijo);
+        hijo
+    }).collect()
+}
+
+// Mutación brusca, como la de ILS: muta con mutacion_normal una proporción de las componentes elegidas al azar
+pub fn perturbacion<Trng: Rng>(problema: &dyn Problema, x: &[f64], proporcion: f64, sigma: f64, rng: &mut Trng) -> Vec<f64> {
+    let mut componentes: Vec<usize> = (0..x.len()).collect();
+    rng.shuffle(&mut componentes);
+    let n_mutadas = ((x.len() as f64 * proporcion).round() as usize).max(1);
+    componentes.iter().take(n_mutadas).fold(x.to_vec(), |nx, i| mutacion_normal(problema, &nx, *i, sigma, rng))
+}
+
+
+
+// Problemas sencillos con los que se prueban los algoritmos generales
+#[cfg(test)]
+pub mod pruebas {
+    use super::*;
+
+    // Máximo de 1 - |x - c|² en la caja [inferior, superior]^dimension, donde c es su centro
+    pub struct Parabola {
+        dimension: usize,
+        limites: (f64, f64),
+    }
+
+    impl Parabola {
+        pub fn nueva(dimension: usize, limites: (f64, f64)) -> Parabola {
+            Parabola { dimension, limites }
+        }
+    }
+
+    impl Problema for Parabola {
+        fn dimension(&self) -> usize { self.dimension }
+
+        fn limites(&self, _i: usize) -> (f64, f64) { self.limites }
+
+        fn funcion_objetivo(&self, x: &[f64]) -> f64 {
+            let centro = (self.limites.0 + self.limites.1) / 2.0;
+            1.0 - x.iter().map(|xi| (xi - centro)*(xi - centro)).sum::<f64>()
+        }
+    }
+}
@@ -0,0 +1,376 @@
+// Puntos de control de los lotes de experimentos
+// Cada test que termina se añade a un archivo JSON Lines con el mismo formato que la salida de resultados,
+//   junto a la descripción completa de su algoritmo (ver Aprendiz::a_json). Si el lote se interrumpe, al
+//   repetirlo con el mismo archivo se recuperan de él los tests ya hechos (con la misma semilla maestra,
+//   algoritmo, conjunto de datos, repetición, partición y ejecución) en lugar de volver a ejecutarlos
+// La primera línea del archivo guarda la configuración del lote (ver configuracion), y no se reanuda un lote
+//   con otra configuración, ya que sus tests no serían comparables con los ya hechos
+// Si se indica, los algoritmos genéticos guardan además su población cada cierto número de generaciones
+//   en un archivo junto al punto de control, y al reanudar un test interrumpido continúan desde ella.
+//   El estado del RNG no se guarda, así que un test reanudado no da los mismos resultados que si no se
+//   hubiese interrumpido, y su tiempo solo cuenta la parte ejecutada tras reanudarlo
+
+use cache;
+use evaluacion_pesos;
+use evaluacion_pesos::{ResultadoTest, Validacion};
+use json;
+use json::Valor;
+use parametros;
+use presupuesto::{Evaluador, Presupuesto};
+use resultados;
+use resultados::{Formato, Registro};
+use std::cell::RefCell;
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{Read, Write};
+
+
+// Configuración con la que se ejecutan los tests de un lote en el hilo actual: el esquema de validación,
+//   la medida de clasificación, los parámetros de los algoritmos que no tienen los suyos, el presupuesto
+//   de cada test y el modo de la caché
+pub fn configuracion(validacion: Validacion, p: Presupuesto) -> Valor {
+    objeto_json!{
+        "validacion" => format!("{:?}", validacion),
+        "medida" => format!("{:?}", evaluacion_pesos::medida_clasificacion()),
+        "parametros" => parametros::parametros().a_json(),
+        "evaluaciones" => p.max_evaluaciones(),
+        "tiempo_ms" => p.tiempo.map_or(Valor::Nulo, |t| Valor::from(t.as_millis() as f64)),
+        "objetivo" => p.objetivo.map_or(Valor::Nulo, Valor::from),
+        "cache" => format!("{:?}", cache::modo_cache()),
+    }
+}
+
+// Describe el primer campo en el que difieren dos configuraciones
+fn diferencia(anterior: &Valor, actual: &Valor) -> String {
+    let campos = match actual { Valor::Objeto(c) => c.as_slice(), _ => &[] };
+    campos.iter().find(|(nombre, v)| anterior.campo(nombre) != Some(v))
+        .map_or_else(|| "otros campos".to_string(), |(nombre, v)| {
+            format!("{} era {} y ahora es {}", nombre, anterior.campo(nombre).map_or("nada".to_string(), |a| a.to_string()), v)
+        })
+}
+
+
+// Archivo en el que se guardan los tests terminados, junto a los que ya tenía al abrirlo
+// Cada test se guarda con la descripción de su algoritmo, que es con lo que se identifica al buscarlo
+pub struct PuntoControl {
+    ruta: String,
+    archivo: File,
+    hechos: Vec<(Valor, Registro)>,
+    poblaciones: bool,     // Si los algoritmos genéticos guardan también su población
+}
+
+impl PuntoControl {
+    // Abre el punto de control indicado, leyendo los tests que ya contenga, o lo crea con la configuración
+    //   del lote si no existe. Si existe pero se creó con otra configuración, devuelve un error
+    pub fn abrir(ruta: &str, poblaciones: bool, configuracion: &Valor) -> Result<PuntoControl, io::Error> {
+        if Formato::de_ruta(ruta) != Formato::JsonLines {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "el punto de control debe ser un archivo JSON Lines"));
+        }
+        let error = |mensaje: String| io::Error::new(io::ErrorKind::InvalidData, mensaje);
+
+        let contenido = match fs::read_to_string(ruta) {
+            Ok(c) => c,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        // Número, posición en el archivo y texto de cada línea no vacía
+        let mut posicion = 0;
+        let mut lineas = contenido.split_inclusive('\n').enumerate()
+            .map(|(n, l)| { posicion += l.len(); (n, posicion - l.len(), l) })
+            .filter(|(_, _, l)| !l.trim().is_empty()).peekable();
+        let mut hechos = Vec::new();
+        let mut truncar = None;  // Posición de la última línea si quedó a medio escribir
+        let nuevo = match lineas.next() {
+            None => true,
+            Some((_, _, cabecera)) => {
+                let anterior = json::analizar(cabecera).ok().and_then(|v| v.campo("configuracion").cloned())
+                    .ok_or_else(|| error("la primera línea no contiene la configuración del lote".to_string()))?;
+                if anterior != *configuracion {
+                    return Err(error(format!("se creó con otra configuración ({}); usa otro archivo o bórralo para empezar de nuevo",
+                                             diferencia(&anterior, configuracion))));
+                }
+                while let Some((n, inicio, linea)) = lineas.next() {
+                    let v = match json::analizar(linea) {
+                        Ok(v) => v,
+                        // Si el lote se interrumpió mientras se guardaba un test, la última línea queda incompleta:
+                        //   la descartamos y ese test se vuelve a ejecutar
+                        Err(e) if lineas.peek().is_none() => {
+                            println!("Aviso: se descarta la última línea de {}, que está incompleta ({})", ruta, e);
+                            truncar = Some(inicio);
+                            break;
+                        },
+                        Err(e) => return Err(error(format!("línea {}: {}", n+1, e))),
+                    };
+                    let aprendiz = v.campo("aprendiz").cloned().ok_or_else(|| error(format!("línea {}: falta el campo aprendiz", n+1)))?;
+                    hechos.push((aprendiz, resultados::registro_de_json(&v).map_err(|e| error(format!("línea {}: {}", n+1, e)))?));
+                }
+                false
+            },
+        };
+
+        if let Some(inicio) = truncar {
+            OpenOptions::new().write(true).open(ruta)?.set_len(inicio as u64)?;
+        }
+        let mut archivo = OpenOptions::new().create(true).append(true).open(ruta)?;
+        if nuevo {
+            writeln!(archivo, "{}", objeto_json!{ "configuracion" => configuracion.clone() })?;
+            archivo.flush()?;
+        } else if truncar.is_none() && !contenido.ends_with('\n') {
+            // La última línea está completa pero sin su salto de línea: lo añadimos para no pegarle el siguiente test
+            writeln!(archivo)?;
+            archivo.flush()?;
+        }
+        Ok(PuntoControl { ruta: ruta.to_string(), archivo, hechos, poblaciones })
+    }
+
+    // Número de tests que había en el archivo al abrirlo
+    pub fn num_hechos(&self) -> usize {
+        self.hechos.len()
+    }
+
+    // Busca un test ya hecho de un algoritmo, dado por su descripción en JSON, en un conjunto de datos
+    pub fn buscar(&self, aprendiz: &Valor, conjunto: &str, semilla: u64, repeticion: usize, particion: usize, ejecucion: usize) -> Option<ResultadoTest> {
+        self.hechos.iter().find(|(a, r)| a == aprendiz && r.conjunto == conjunto && r.test.semilla == semilla
+                                         && r.test.repeticion == repeticion && r.test.particion == particion && r.test.ejecucion == ejecucion)
+            .map(|(_, r)| r.test.clone())
+    }
+
+    // Añade al archivo un test terminado de un algoritmo, dado por su descripción en JSON y su nombre
+    pub fn guardar(&mut self, aprendiz: &Valor, nombre: &str, conjunto: &str, test: &ResultadoTest) -> Result<(), io::Error> {
+        let mut registro = resultados::registro_json(nombre, conjunto, test);
+        if let Valor::Objeto(ref mut campos) = registro {
+            campos.push(("aprendiz".to_string(), aprendiz.clone()));
+        }
+        writeln!(self.archivo, "{}", registro)?;
+        self.archivo.flush()
+    }
+
+    // Prefijo de los archivos de población de los tests, si los algoritmos genéticos deben guardarla
+    pub fn prefijo_poblaciones(&self) -> Option<String> {
+        if self.poblaciones { Some(format!("{}.poblacion", self.ruta)) } else { None }
+    }
+}
+
+// Archivo en el que se guarda la población de un test, identificado por su semilla, la descripción
+//   de su algoritmo y el nombre de su conjunto de datos
+pub fn archivo_poblacion(prefijo: &str, aprendiz: &Valor, conjunto: &str, semilla_test: u64) -> String {
+    let id = evaluacion_pesos::derivar_semilla(semilla_test, &[evaluacion_pesos::hash_texto(&aprendiz.to_string()), evaluacion_pesos::hash_texto(conjunto)]);
+    format!("{}-{:016x}.json", prefijo, id)
+}
+
+
+
+// Estado de un algoritmo genético en mitad de una ejecución
+#[derive(Clone, Debug)]
+pub struct EstadoPoblacion {
+    pub evaluaciones: usize,                     // Evaluaciones que lleva contadas el propio algoritmo
+    pub generacion: usize,                       // Generación del algoritmo, o lo que este quiera contar
+    pub individuos: Vec<(f64, usize, Vec<f64>)>, // Evaluación, identificador y pesos de cada individuo
+}
+
+// Archivo en el que los algoritmos del hilo actual guardan su población, si se debe guardar
+thread_local! {
+    static ARCHIVO_POBLACION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+// Cambia el archivo en el que guardan su población los algoritmos del hilo actual
+pub fn fijar_archivo_poblacion(ruta: Option<String>) {
+    ARCHIVO_POBLACION.with(|a| *a.borrow_mut() = ruta);
+}
+
+// Indica si los algoritmos del hilo actual deben guardar su población
+pub fn guardar_poblaciones() -> bool {
+    ARCHIVO_POBLACION.with(|a| a.borrow().is_some())
+}
+
+// Guarda la población de un algoritmo, si se debe guardar, junto a las evaluaciones de la función objetivo
+//   contadas hasta ahora por su evaluador
+// Se escribe en un archivo temporal que luego se renombra, para no dejar una población a medias si se interrumpe
+pub fn guardar_poblacion(evaluador: &Evaluador, estado: &EstadoPoblacion) {
+    let ruta = match ARCHIVO_POBLACION.with(|a| a.borrow().clone()) {
+        Some(r) => r,
+        None => return,
+    };
+    let individuos: Vec<Valor> = estado.individuos.iter().map(|(evaluacion, id, pesos)| objeto_json!{
+        "evaluacion" => *evaluacion,
+        "id" => *id,
+        "pesos" => pesos.as_slice(),
+    }).collect();
+    let valor = objeto_json!{
+        "evaluaciones" => estado.evaluaciones,
+        "evaluaciones_evaluador" => evaluador.usadas(),
+        "generacion" => estado.generacion,
+        "individuos" => individuos,
+    };
+    let temporal = format!("{}.tmp", ruta);
+    let escrito = File::create(&temporal).and_then(|mut f| writeln!(f, "{}", valor)).and_then(|_| fs::rename(&temporal, &ruta));
+    if let Err(e) = escrito {
+        println!("No se pudo guardar la población en {}: {}", ruta, e);
+    }
+}
+
+// Recupera la población guardada por un algoritmo en el hilo actual, si la hay, y suma a su evaluador
+//   las evaluaciones que se hicieron antes de guardarla
+pub fn cargar_poblacion(evaluador: &Evaluador) -> Option<EstadoPoblacion> {
+    let ruta = ARCHIVO_POBLACION.with(|a| a.borrow().clone())?;
+    let mut texto = String::new();
+    File::open(&ruta).and_then(|mut f| f.read_to_string(&mut texto)).ok()?;
+    let estado = json::analizar(&texto).and_then(|v| estado_de_json(&v));
+    match estado {
+        Ok((estado, evaluaciones_evaluador)) => {
+            evaluador.anadir(evaluaciones_evaluador);
+            Some(estado)
+        },
+        Err(e) => { println!("No se pudo leer la población de {}: {}", ruta, e); None },
+    }
+}
+
+// Borra la población guardada en el hilo actual, una vez que el algoritmo ha terminado
+pub fn borrar_poblacion() {
+    if let Some(ruta) = ARCHIVO_POBLACION.with(|a| a.borrow().clone()) {
+        let _ = fs::remove_file(ruta);
+    }
+}
+
+// Construye un estado a partir de un objeto JSON como los que escribe guardar_poblacion
+fn estado_de_json(v: &Valor) -> Result<(EstadoPoblacion, usize), String> {
+    let entero = |v: &Valor, nombre: &str| v.campo(nombre).and_then(|x| x.como_numero()).map(|x| x as usize)
+        .ok_or_else(|| format!("falta el campo {}", nombre));
+    let individuos = v.campo("individuos").and_then(|x| x.como_lista()).ok_or("falta el campo individuos")?
+        .iter().map(|i| {
+            let evaluacion = i.campo("evaluacion").and_then(|x| x.como_numero()).ok_or("falta el campo evaluacion")?;
+            let pesos = i.campo("pesos").and_then(|x| x.como_lista())
+                .and_then(|l| l.iter().map(|w| w.como_numero()).collect::<Option<Vec<f64>>>())
+                .ok_or("el campo pesos no es una lista de números")?;
+            Ok((evaluacion, entero(i, "id")?, pesos))
+        }).collect::<Result<Vec<_>, String>>()?;
+    if individuos.is_empty() {
+        return Err("la población está vacía".to_string());
+    }
+    let estado = EstadoPoblacion { evaluaciones: entero(v, "evaluaciones")?, generacion: entero(v, "generacion")?, individuos };
+    Ok((estado, entero(v, "evaluaciones_evaluador")?))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use evaluacion_pesos::MatrizConfusion;
+    use std::env;
+    use std::process;
+
+    fn ruta(nombre: &str) -> String {
+        let ruta = env::temp_dir().join(format!("punto_control-{}-{}.jsonl", nombre, process::id()));
+        let _ = fs::remove_file(&ruta);
+        ruta.to_str().unwrap().to_string()
+    }
+
+    fn test(particion: usize, tasa_clas: f64) -> ResultadoTest {
+        ResultadoTest {
+            repeticion: 0, particion, ejecucion: 0,
+            tasa_clas, tasa_red: 50.0, agregado: (tasa_clas + 50.0) / 2.0, tiempo_ms: 3,
+            evaluaciones: 100, aciertos_cache: 0,
+            semilla: 7, semilla_test: 11,
+            pesos: vec![0.5, 1.0],
+            matriz: MatrizConfusion(vec![vec![1, 0], vec![0, 1]]),
+        }
+    }
+
+    #[test]
+    fn reanuda_con_la_misma_configuracion() {
+        let ruta = ruta("reanuda");
+        let configuracion = configuracion(Validacion::KFold(5, 1), Presupuesto::default());
+        let aprendiz = objeto_json!{ "nombre" => "BL", "tasa_mutacion" => 0.1 };
+        {
+            let mut c = PuntoControl::abrir(&ruta, false, &configuracion).unwrap();
+            assert_eq!(c.num_hechos(), 0);
+            c.guardar(&aprendiz, "BL", "iris", &test(2, 80.0)).unwrap();
+        }
+        let c = PuntoControl::abrir(&ruta, false, &configuracion).unwrap();
+        assert_eq!(c.num_hechos(), 1);
+        assert_eq!(c.buscar(&aprendiz, "iris", 7, 0, 2, 0).map(|t| t.tasa_clas), Some(80.0));
+        assert!(c.buscar(&aprendiz, "iris", 7, 0, 1, 0).is_none());
+        assert!(c.buscar(&aprendiz, "wine", 7, 0, 2, 0).is_none());
+        assert!(c.buscar(&aprendiz, "iris", 8, 0, 2, 0).is_none());
+        fs::remove_file(&ruta).unwrap();
+    }
+
+    #[test]
+    fn rechaza_otra_configuracion() {
+        let ruta = ruta("configuracion");
+        PuntoControl::abrir(&ruta, false, &configuracion(Validacion::KFold(5, 1), Presupuesto::default())).unwrap();
+
+        let error = PuntoControl::abrir(&ruta, false, &configuracion(Validacion::KFold(10, 1), Presupuesto::default())).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("validacion"));
+
+        let anterior = parametros::parametros();
+        let mut p = anterior;
+        p.max_evaluaciones += 1;
+        parametros::fijar_parametros(p);
+        let error = PuntoControl::abrir(&ruta, false, &configuracion(Validacion::KFold(5, 1), Presupuesto::default())).err();
+        parametros::fijar_parametros(anterior);
+        assert!(error.is_some_and(|e| e.to_string().contains("parametros")));
+
+        let objetivo = Presupuesto { objetivo: Some(0.5), ..Default::default() };
+        let error = PuntoControl::abrir(&ruta, false, &configuracion(Validacion::KFold(5, 1), objetivo)).err();
+        assert!(error.is_some_and(|e| e.to_string().contains("objetivo")));
+
+        assert!(PuntoControl::abrir(&ruta, false, &configuracion(Validacion::KFold(5, 1), Presupuesto::default())).is_ok());
+        fs::remove_file(&ruta).unwrap();
+    }
+
+    #[test]
+    fn descarta_un_test_a_medio_guardar() {
+        let ruta = ruta("a_medio_guardar");
+        let configuracion = configuracion(Validacion::KFold(5, 1), Presupuesto::default());
+        let aprendiz = objeto_json!{ "nombre" => "BL", "tasa_mutacion" => 0.1 };
+        {
+            let mut c = PuntoControl::abrir(&ruta, false, &configuracion).unwrap();
+            c.guardar(&aprendiz, "BL", "iris", &test(0, 80.0)).unwrap();
+        }
+        let mut registro = resultados::registro_json("BL", "iris", &test(1, 90.0)).to_string();
+        registro.truncate(registro.len() / 2);
+        OpenOptions::new().append(true).open(&ruta).unwrap().write_all(registro.as_bytes()).unwrap();
+
+        {
+            let mut c = PuntoControl::abrir(&ruta, false, &configuracion).unwrap();
+            assert_eq!(c.num_hechos(), 1);
+            c.guardar(&aprendiz, "BL", "iris", &test(1, 90.0)).unwrap();
+        }
+        let c = PuntoControl::abrir(&ruta, false, &configuracion).unwrap();
+        assert_eq!(c.num_hechos(), 2);
+        assert_eq!(c.buscar(&aprendiz, "iris", 7, 0, 0, 0).map(|t| t.tasa_clas), Some(80.0));
+        assert_eq!(c.buscar(&aprendiz, "iris", 7, 0, 1, 0).map(|t| t.tasa_clas), Some(90.0));
+        fs::remove_file(&ruta).unwrap();
+    }
+
+    #[test]
+    fn rechaza_archivos_sin_configuracion() {
+        let ruta = ruta("sin_configuracion");
+        let registro = resultados::registro_json("BL", "iris", &test(0, 80.0));
+        fs::write(&ruta, format!("{}\n", registro)).unwrap();
+        let error = PuntoControl::abrir(&ruta, false, &configuracion(Validacion::KFold(5, 1), Presupuesto::default())).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        fs::remove_file(&ruta).unwrap();
+    }
+
+    #[test]
+    fn distingue_algoritmos_con_el_mismo_nombre() {
+        let ruta = ruta("mismo_nombre");
+        let configuracion = configuracion(Validacion::KFold(5, 1), Presupuesto::default());
+        let lento = objeto_json!{ "nombre" => "AGG", "tasa_cruce" => 0.7 };
+        let rapido = objeto_json!{ "nombre" => "AGG", "tasa_cruce" => 0.9 };
+        {
+            let mut c = PuntoControl::abrir(&ruta, false, &configuracion).unwrap();
+            c.guardar(&lento, "AGG", "iris", &test(0, 70.0)).unwrap();
+            c.guardar(&rapido, "AGG", "iris", &test(0, 90.0)).unwrap();
+        }
+        let c = PuntoControl::abrir(&ruta, false, &configuracion).unwrap();
+        assert_eq!(c.buscar(&lento, "iris", 7, 0, 0, 0).map(|t| t.tasa_clas), Some(70.0));
+        assert_eq!(c.buscar(&rapido, "iris", 7, 0, 0, 0).map(|t| t.tasa_clas), Some(90.0));
+        assert_ne!(archivo_poblacion("p", &lento, "iris", 11), archivo_poblacion("p", &rapido, "iris", 11));
+        fs::remove_file(&ruta).unwrap();
+    }
+}
@@ -0,0 +1,392 @@
+// Escritura de los resultados de las validaciones en archivos para analizarlos después, y lectura de esos archivos
+// Cada test se escribe como un registro: una línea JSON (formato JSON Lines) o una fila CSV
+
+use evaluacion_pesos::{MatrizConfusion, ResultadoTest, ResultadosCV};
+use json;
+use json::Valor;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+
+// Formatos de salida admitidos
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Formato {
+    JsonLines,
+    Csv,
+}
+
+impl Formato {
+    // Deduce el formato de la extensión del archivo: CSV si es .csv, JSON Lines en cualquier otro caso
+    pub fn de_ruta(ruta: &str) -> Formato {
+        match Path::new(ruta).extension().and_then(|e| e.to_str()) {
+            Some(e) if e.eq_ignore_ascii_case("csv") => Formato::Csv,
+            _ => Formato::JsonLines,
+        }
+    }
+}
+
+// Columnas de los archivos CSV. Los pesos se escriben en una sola columna separados por ';'
+const COLUMNAS_CSV: &str = "algoritmo,conjunto,repeticion,particion,ejecucion,tasa_clas,tasa_red,agregado,tiempo_ms,evaluaciones,aciertos_cache,semilla,semilla_test,pesos";
+
+
+// Archivo en el que se van escribiendo los resultados de todos los algoritmos y conjuntos de datos
+pub struct Salida {
+    archivo: File,
+    formato: Formato,
+    cabecera_escrita: bool,
+}
+
+impl Salida {
+    // Crea (o vacía) el archivo indicado, eligiendo el formato según su extensión
+    pub fn crear(ruta: &str) -> Result<Salida, io::Error> {
+        Ok(Salida { archivo: File::create(ruta)?, formato: Formato::de_ruta(ruta), cabecera_escrita: false })
+    }
+
+    // Escribe un registro por cada test de los resultados de un algoritmo sobre un conjunto de datos
+    pub fn escribir(&mut self, algoritmo: &str, conjunto: &str, resultados: &ResultadosCV) -> Result<(), io::Error> {
+        for r in &resultados.tests {
+            match self.formato {
+                Formato::JsonLines => writeln!(self.archivo, "{}", registro_json(algoritmo, conjunto, r))?,
+                Formato::Csv => {
+                    if !self.cabecera_escrita {
+                        writeln!(self.archivo, "{}", COLUMNAS_CSV)?;
+                        self.cabecera_escrita = true;
+                    }
+                    writeln!(self.archivo, "{}", registro_csv(algoritmo, conjunto, r))?
+                },
+            }
+        }
+        self.archivo.flush()
+    }
+}
+
+
+// Representa como objeto JSON el resultado de un test, incluyendo su matriz de confusión
+pub fn registro_json(algoritmo: &str, conjunto: &str, r: &ResultadoTest) -> Valor {
+    let matriz: Vec<Valor> = r.matriz.0.iter().map(|fila| Valor::from(fila.as_slice())).collect();
+    objeto_json!{
+        "algoritmo" => algoritmo,
+        "conjunto" => conjunto,
+        "repeticion" => r.repeticion + 1,
+        "particion" => r.particion + 1,
+        "ejecucion" => r.ejecucion + 1,
+        "tasa_clas" => r.tasa_clas,
+        "tasa_red" => r.tasa_red,
+        "agregado" => r.agregado,
+        "tiempo_ms" => r.tiempo_ms,
+        "evaluaciones" => r.evaluaciones,
+        "aciertos_cache" => r.aciertos_cache,
+        // Las semillas se escriben como texto, ya que los números de JSON no representan todos los enteros de 64 bits
+        "semilla" => r.semilla.to_string(),
+        "semilla_test" => r.semilla_test.to_string(),
+        "pesos" => r.pesos.as_slice(),
+        "matriz_confusion" => matriz,
+    }
+}
+
+// Representa como fila CSV el resultado de un test, en el orden de COLUMNAS_CSV
+pub fn registro_csv(algoritmo: &str, conjunto: &str, r: &ResultadoTest) -> String {
+    let pesos: Vec<String> = r.pesos.iter().map(|w| w.to_string()).collect();
+    format!("{},{},{},{},{},{},{},{},{},{},{},{},{},{}", campo_csv(algoritmo), campo_csv(conjunto), r.repeticion + 1, r.particion + 1, r.ejecucion + 1,
+            r.tasa_clas, r.tasa_red, r.agregado, r.tiempo_ms, r.evaluaciones, r.aciertos_cache, r.semilla, r.semilla_test, pesos.join(";"))
+}
+
+// Entrecomilla un campo de texto si contiene separadores, comillas o saltos de línea
+pub fn campo_csv(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+
+
+// Resultado de un test leído de un archivo, junto al algoritmo y el conjunto de datos a los que corresponde
+#[derive(Clone, Debug)]
+pub struct Registro {
+    pub algoritmo: String,
+    pub conjunto: String,
+    pub test: ResultadoTest,  // En los archivos CSV no se guarda la matriz de confusión, por lo que queda vacía
+}
+
+// Lee todos los registros de un archivo de resultados, deduciendo su formato de la extensión
+pub fn leer(ruta: &str) -> Result<Vec<Registro>, io::Error> {
+    let lineas = BufReader::new(File::open(ruta)?).lines().collect::<Result<Vec<String>, io::Error>>()?;
+    let error = |n: usize, e: String| io::Error::new(io::ErrorKind::InvalidData, format!("línea {}: {}", n+1, e));
+
+    match Formato::de_ruta(ruta) {
+        Formato::JsonLines => lineas.iter().enumerate()
+            .filter(|(_, l)| !l.trim().is_empty())
+            .map(|(n, l)| json::analizar(l).and_then(|v| registro_de_json(&v)).map_err(|e| error(n, e)))
+            .collect(),
+        Formato::Csv => {
+            let cabecera = match lineas.first() {
+                Some(c) => dividir_csv(c),
+                None => return Ok(Vec::new()),
+            };
+            lineas.iter().enumerate().skip(1)
+                .filter(|(_, l)| !l.trim().is_empty())
+                .map(|(n, l)| registro_de_csv(&cabecera, &dividir_csv(l)).map_err(|e| error(n, e)))
+                .collect()
+        },
+    }
+}
+
+// Lee los registros de varios archivos de resultados, uno tras otro
+pub fn leer_varios(rutas: &[&str]) -> Result<Vec<Registro>, io::Error> {
+    let mut registros = Vec::new();
+    for ruta in rutas {
+        let leidos = leer(ruta).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", ruta, e)))?;
+        registros.extend(leidos);
+    }
+    Ok(registros)
+}
+
+// Agrupa los registros por algoritmo y, dentro de cada algoritmo, por conjunto de datos,
+//   conservando el orden en el que aparecen por primera vez
+pub fn agrupar(registros: &[Registro]) -> Vec<(String, Vec<(String, ResultadosCV)>)> {
+    let mut grupos: Vec<(String, Vec<(String, ResultadosCV)>)> = Vec::new();
+    for r in registros {
+        let i = match grupos.iter().position(|g| g.0 == r.algoritmo) {
+            Some(i) => i,
+            None => { grupos.push((r.algoritmo.clone(), Vec::new())); grupos.len()-1 },
+        };
+        let conjuntos = &mut grupos[i].1;
+        let j = match conjuntos.iter().position(|c| c.0 == r.conjunto) {
+            Some(j) => j,
+            None => { conjuntos.push((r.conjunto.clone(), ResultadosCV::default())); conjuntos.len()-1 },
+        };
+        conjuntos[j].1.tests.push(r.test.clone());
+    }
+    grupos
+}
+
+// Construye un registro a partir de un objeto JSON como los que escribe registro_json
+pub fn registro_de_json(v: &Valor) -> Result<Registro, String> {
+    let texto = |nombre: &str| v.campo(nombre).and_then(|x| x.como_texto()).map(|x| x.to_string())
+        .ok_or_else(|| format!("falta el campo {}", nombre));
+    let numero = |nombre: &str| v.campo(nombre).and_then(|x| x.como_numero())
+        .ok_or_else(|| format!("falta el campo {}", nombre));
+    let lista_numeros = |x: &Valor| -> Option<Vec<f64>> { x.como_lista()?.iter().map(|y| y.como_numero()).collect() };
+    let semilla = |nombre: &str| v.campo(nombre).and_then(|x| x.como_texto()).and_then(|x| x.parse().ok()).unwrap_or(0);
+
+    let pesos = match v.campo("pesos") {
+        Some(x) => lista_numeros(x).ok_or("el campo pesos no es una lista de números")?,
+        None => Vec::new(),
+    };
+    let matriz = match v.campo("matriz_confusion").and_then(|x| x.como_lista()) {
+        Some(filas) => filas.iter().map(|f| lista_numeros(f).map(|f| f.iter().map(|x| *x as usize).collect()))
+            .collect::<Option<Vec<Vec<usize>>>>().ok_or("el campo matriz_confusion no es una matriz")?,
+        None => Vec::new(),
+    };
+
+    Ok(Registro {
+        algoritmo: texto("algoritmo")?,
+        conjunto: texto("conjunto")?,
+        test: ResultadoTest {
+            repeticion: (numero("repeticion")? as usize).saturating_sub(1),
+            particion: (numero("particion")? as usize).saturating_sub(1),
+            ejecucion: (numero("ejecucion").unwrap_or(1.0) as usize).saturating_sub(1),
+            tasa_clas: numero("tasa_clas")?,
+            tasa_red: numero("tasa_red")?,
+            agregado: numero("agregado")?,
+            tiempo_ms: numero("tiempo_ms")? as i64,
+            evaluaciones: numero("evaluaciones").unwrap_or(0.0) as usize,
+            aciertos_cache: numero("aciertos_cache").unwrap_or(0.0) as usize,
+            semilla: semilla("semilla"),
+            semilla_test: semilla("semilla_test"),
+            pesos,
+            matriz: MatrizConfusion(matriz),
+        },
+    })
+}
+
+// Construye un registro a partir de una fila CSV, buscando cada columna por su nombre en la cabecera
+fn registro_de_csv(cabecera: &[String], fila: &[String]) -> Result<Registro, String> {
+    let campo = |nombre: &str| cabecera.iter().position(|c| c == nombre).and_then(|i| fila.get(i))
+        .ok_or_else(|| format!("falta la columna {}", nombre));
+    let numero = |nombre: &str| campo(nombre).and_then(|x| x.parse::<f64>()
+        .map_err(|_| format!("valor no numérico en la columna {}: {}", nombre, x)));
+    let semilla = |nombre: &str| campo(nombre).ok().and_then(|x| x.parse().ok()).unwrap_or(0);
+
+    let pesos = match campo("pesos") {
+        Ok(x) if !x.is_empty() => x.split(';').map(|w| w.parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>().map_err(|_| format!("pesos no válidos: {}", x))?,
+        _ => Vec::new(),
+    };
+
+    Ok(Registro {
+        algoritmo: campo("algoritmo")?.clone(),
+        conjunto: campo("conjunto")?.clone(),
+        test: ResultadoTest {
+            repeticion: (numero("repeticion")? as usize).saturating_sub(1),
+            particion: (numero("particion")? as usize).saturating_sub(1),
+            ejecucion: (numero("ejecucion").unwrap_or(1.0) as usize).saturating_sub(1),
+            tasa_clas: numero("tasa_clas")?,
+            tasa_red: numero("tasa_red")?,
+            agregado: numero("agregado")?,
+            tiempo_ms: numero("tiempo_ms")? as i64,
+            evaluaciones: numero("evaluaciones").unwrap_or(0.0) as usize,
+            aciertos_cache: numero("aciertos_cache").unwrap_or(0.0) as usize,
+            semilla: semilla("semilla"),
+            semilla_test: semilla("semilla_test"),
+            pesos,
+            matriz: MatrizConfusion(Vec::new()),
+        },
+    })
+}
+
+// Divide una fila CSV en sus campos, teniendo en cuenta los campos entrecomillados
+pub fn dividir_csv(linea: &str) -> Vec<String> {
+    let mut campos = Vec::new();
+    let mut actual = String::new();
+    let mut entre_comillas = false;
+    let mut caracteres = linea.trim_end_matches('\r').chars().peekable();
+    while let Some(c) = caracteres.next() {
+        match c {
+            '"' if entre_comillas && caracteres.peek() == Some(&'"') => { actual.push('"'); caracteres.next(); },
+            '"' => entre_comillas = !entre_comillas,
+            ',' if !entre_comillas => campos.push(::std::mem::take(&mut actual)),
+            c => actual.push(c),
+        }
+    }
+    campos.push(actual);
+    campos
+}
+
+
+// Valor de los resultados de cada test con el que se comparan los algoritmos
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Columna {
+    TasaClas,
+    TasaRed,
+    Agregado,
+    Tiempo,
+}
+
+impl FromStr for Columna {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Columna, String> {
+        match s.trim().to_lowercase().as_str() {
+            "clas" | "tasa_clas" => Ok(Columna::TasaClas),
+            "red" | "tasa_red" => Ok(Columna::TasaRed),
+            "agregado" => Ok(Columna::Agregado),
+            "tiempo" | "tiempo_ms" => Ok(Columna::Tiempo),
+            _ => Err(format!("columna desconocida: {} (se admiten clas, red, agregado y tiempo)", s)),
+        }
+    }
+}
+
+impl Columna {
+    pub fn valor(&self, r: &ResultadoTest) -> f64 {
+        match self {
+            Columna::TasaClas => r.tasa_clas,
+            Columna::TasaRed => r.tasa_red,
+            Columna::Agregado => r.agregado,
+            Columna::Tiempo => r.tiempo_ms as f64,
+        }
+    }
+
+    // Solo en el tiempo es mejor un valor menor
+    pub fn mayor_es_mejor(&self) -> bool {
+        *self != Columna::Tiempo
+    }
+}
+
+// Construye la matriz de valores de los algoritmos agrupados con una fila por bloque y una columna por algoritmo
+// Los bloques son los conjuntos de datos (usando la media de sus tests) o, si se indica, cada test de cada conjunto
+// Solo se usan los bloques que tienen todos los algoritmos; se devuelven sus nombres junto a la matriz
+pub fn matriz_bloques(algoritmos: &[(String, Vec<(String, ResultadosCV)>)], columna: Columna, por_particion: bool) -> (Vec<String>, Vec<Vec<f64>>) {
+    let mut nombres = Vec::new();
+    let mut datos = Vec::new();
+    let conjuntos: Vec<&String> = algoritmos.first().map_or(Vec::new(), |a| a.1.iter().map(|c| &c.0).collect());
+    for conjunto in conjuntos {
+        let resultados: Option<Vec<&ResultadosCV>> = algoritmos.iter()
+            .map(|a| a.1.iter().find(|c| &c.0 == conjunto).map(|c| &c.1)).collect();
+        let resultados = match resultados {
+            Some(r) => r,
+            None => continue,
+        };
+
+        if por_particion {
+            let n_tests = resultados.iter().map(|r| r.tests.len()).min().unwrap_or(0);
+            for i in 0..n_tests {
+                nombres.push(format!("{} {}", conjunto, resultados[0].nombre_test(&resultados[0].tests[i])));
+                datos.push(resultados.iter().map(|r| columna.valor(&r.tests[i])).collect());
+            }
+        } else {
+            nombres.push(conjunto.clone());
+            datos.push(resultados.iter().map(|r| {
+                r.tests.iter().map(|t| columna.valor(t)).sum::<f64>() / r.tests.len() as f64
+            }).collect());
+        }
+    }
+    (nombres, datos)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resultado() -> ResultadoTest {
+        ResultadoTest {
+            repeticion: 1, particion: 3, ejecucion: 0,
+            tasa_clas: 87.5, tasa_red: 40.0, agregado: 63.75, tiempo_ms: 12,
+            evaluaciones: 15000, aciertos_cache: 7,
+            semilla: u64::MAX, semilla_test: 123456789012345,
+            pesos: vec![0.0, 0.25, 1.0],
+            matriz: MatrizConfusion(vec![vec![3, 1], vec![0, 4]]),
+        }
+    }
+
+    #[test]
+    fn campos_entrecomillados() {
+        assert_eq!(dividir_csv("a,\"b,c\",d"), vec!["a", "b,c", "d"]);
+        assert_eq!(dividir_csv("\"dice \"\"hola\"\"\",x"), vec!["dice \"hola\"", "x"]);
+        assert_eq!(dividir_csv("\"\"\"\""), vec!["\""]);
+        assert_eq!(dividir_csv(",,"), vec!["", "", ""]);
+        assert_eq!(dividir_csv("uno,dos\r"), vec!["uno", "dos"]);
+        assert_eq!(dividir_csv(""), vec![""]);
+    }
+
+    #[test]
+    fn campos_escritos_se_leen_igual() {
+        let campos = ["simple", "con,coma", "con \"comillas\"", "", "\"", "a,\"b\",c", "ñandú"];
+        let linea = campos.iter().map(|c| campo_csv(c)).collect::<Vec<_>>().join(",");
+        assert_eq!(dividir_csv(&linea), campos);
+        assert_eq!(campo_csv("simple"), "simple");
+        assert_eq!(campo_csv("a\"b"), "\"a\"\"b\"");
+    }
+
+    #[test]
+    fn registro_json_ida_y_vuelta() {
+        let r = resultado();
+        let leido = registro_de_json(&json::analizar(&registro_json("BL, \"1-NN\"", "datos.arff", &r).to_string()).unwrap()).unwrap();
+        assert_eq!((leido.algoritmo.as_str(), leido.conjunto.as_str()), ("BL, \"1-NN\"", "datos.arff"));
+        let t = leido.test;
+        assert_eq!((t.repeticion, t.particion, t.ejecucion), (1, 3, 0));
+        assert_eq!((t.tasa_clas, t.tasa_red, t.agregado, t.tiempo_ms), (87.5, 40.0, 63.75, 12));
+        assert_eq!((t.evaluaciones, t.aciertos_cache, t.semilla, t.semilla_test), (15000, 7, u64::MAX, 123456789012345));
+        assert_eq!(t.pesos, r.pesos);
+        assert_eq!(t.matriz, r.matriz);
+    }
+
+    #[test]
+    fn registro_csv_ida_y_vuelta() {
+        let r = resultado();
+        let cabecera = dividir_csv(COLUMNAS_CSV);
+        let leido = registro_de_csv(&cabecera, &dividir_csv(&registro_csv("BL, \"1-NN\"", "datos.arff", &r))).unwrap();
+        assert_eq!((leido.algoritmo.as_str(), leido.conjunto.as_str()), ("BL, \"1-NN\"", "datos.arff"));
+        let t = leido.test;
+        assert_eq!((t.repeticion, t.particion, t.tasa_clas, t.semilla), (1, 3, 87.5, u64::MAX));
+        assert_eq!(t.pesos, r.pesos);
+        assert!(t.matriz.0.is_empty());
+        assert!(registro_de_csv(&cabecera, &dividir_csv("a,b,1,1,1,x,0,0,0,0,0,0,0,")).is_err());
+    }
+}
@@ -0,0 +1,409 @@
+// Generación de las tablas de resultados de la memoria a partir de los resultados guardados
+// Sustituye a resultados2tablas.js, que dependía del texto que imprimían los programas
+// Se generan en LaTeX (entorno tabular), Markdown o HTML
+
+use evaluacion_pesos::{ResultadoTest, ResultadosCV};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+
+// Formatos de tabla admitidos
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FormatoTabla {
+    Latex,
+    Markdown,
+    Html,
+}
+
+impl FromStr for FormatoTabla {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<FormatoTabla, String> {
+        match s.trim().to_lowercase().as_str() {
+            "latex" | "tex" => Ok(FormatoTabla::Latex),
+            "markdown" | "md" => Ok(FormatoTabla::Markdown),
+            "html" => Ok(FormatoTabla::Html),
+            _ => Err(format!("formato de tabla desconocido: {} (se admiten latex, markdown y html)", s)),
+        }
+    }
+}
+
+impl FormatoTabla {
+    // Extensión de los archivos en los que se guardan las tablas de este formato
+    pub fn extension(&self) -> &'static str {
+        match self {
+            FormatoTabla::Latex => "tex",
+            FormatoTabla::Markdown => "md",
+            FormatoTabla::Html => "html",
+        }
+    }
+}
+
+// Opciones de presentación de las tablas
+#[derive(Clone, Copy, Debug)]
+pub struct OpcionesTabla {
+    pub formato: FormatoTabla,
+    pub precision: usize,  // Decimales de las tasas y el agregado. Los tiempos se redondean a milisegundos
+    pub resaltar: bool,    // Si se resalta el mejor valor de cada columna en la tabla global
+}
+
+impl Default for OpcionesTabla {
+    fn default() -> OpcionesTabla {
+        OpcionesTabla { formato: FormatoTabla::Latex, precision: 2, resaltar: true }
+    }
+}
+
+
+// Columnas que se muestran de cada conjunto de datos: nombre en LaTeX, nombre en texto plano
+//   y si son mejores los valores más altos
+const COLUMNAS: [(&str, &str, bool); 4] = [
+    ("\\texttt{\\%\\_clas}", "%_clas", true),
+    ("\\texttt{\\%\\_red}", "%_red", true),
+    ("Agr.", "Agr.", true),
+    ("T (ms)", "T (ms)", false),
+];
+
+// Tabla con un grupo de columnas por conjunto de datos y una fila por test o algoritmo
+// Las celdas vacías corresponden a resultados que no se tienen
+struct Tabla {
+    grupos: Vec<String>,
+    filas: Vec<(String, Vec<Option<f64>>)>,
+    fila_media: Option<Vec<Option<f64>>>,  // Fila de medias, que se separa del resto
+    resaltar: bool,
+}
+
+fn valores(r: &ResultadoTest) -> [f64; 4] {
+    [r.tasa_clas, r.tasa_red, r.agregado, r.tiempo_ms as f64]
+}
+
+fn valores_medios(resultados: &ResultadosCV) -> [f64; 4] {
+    let m = resultados.medias();
+    [m.0, m.1, m.2, m.3]
+}
+
+// Nombre con el que se muestra un conjunto de datos: el de su archivo sin directorio ni extensión
+pub fn nombre_conjunto(ruta: &str) -> String {
+    Path::new(ruta).file_stem().and_then(|s| s.to_str()).unwrap_or(ruta).to_string()
+}
+
+
+// Tabla de un algoritmo: resultados de cada test en cada conjunto de datos y media de todos ellos
+pub fn tabla_algoritmo(conjuntos: &[(String, ResultadosCV)], opciones: &OpcionesTabla) -> String {
+    let n_filas = conjuntos.iter().map(|c| c.1.tests.len()).max().unwrap_or(0);
+    let filas = (0..n_filas).map(|i| {
+        // Los tests se nombran según el primer conjunto que tenga ese test
+        let nombre = conjuntos.iter().filter_map(|c| c.1.tests.get(i).map(|r| c.1.nombre_test(r))).next().unwrap();
+        let celdas = conjuntos.iter().flat_map(|c| match c.1.tests.get(i) {
+            Some(r) => valores(r).iter().map(|x| Some(*x)).collect::<Vec<_>>(),
+            None => vec![None; COLUMNAS.len()],
+        }).collect();
+        (format!("Partición {}", nombre), celdas)
+    }).collect();
+    let media = conjuntos.iter().flat_map(|c| valores_medios(&c.1).to_vec()).map(Some).collect();
+
+    let tabla = Tabla {
+        grupos: conjuntos.iter().map(|c| nombre_conjunto(&c.0)).collect(),
+        filas,
+        fila_media: Some(media),
+        resaltar: false,
+    };
+    escribir(&tabla, opciones)
+}
+
+// Tabla global: resultados medios de cada algoritmo en cada conjunto de datos
+// Se resalta el mejor valor de cada columna si así se indica en las opciones
+pub fn tabla_global(algoritmos: &[(String, Vec<(String, ResultadosCV)>)], opciones: &OpcionesTabla) -> String {
+    let mut grupos: Vec<String> = Vec::new();
+    for c in algoritmos.iter().flat_map(|a| a.1.iter()) {
+        if !grupos.contains(&c.0) {
+            grupos.push(c.0.clone());
+        }
+    }
+
+    let filas = algoritmos.iter().map(|a| {
+        let celdas = grupos.iter().flat_map(|g| match a.1.iter().find(|c| &c.0 == g) {
+            Some(c) => valores_medios(&c.1).iter().map(|x| Some(*x)).collect::<Vec<_>>(),
+            None => vec![None; COLUMNAS.len()],
+        }).collect();
+        (a.0.clone(), celdas)
+    }).collect();
+
+    let tabla = Tabla {
+        grupos: grupos.iter().map(|g| nombre_conjunto(g)).collect(),
+        filas,
+        fila_media: None,
+        resaltar: opciones.resaltar,
+    };
+    escribir(&tabla, opciones)
+}
+
+// Imprime la tabla de cada algoritmo y la tabla global, cada una precedida por un título
+pub fn imprimir_tablas(algoritmos: &[(String, Vec<(String, ResultadosCV)>)], opciones: &OpcionesTabla) {
+    for (algoritmo, conjuntos) in algoritmos {
+        println!("{}\n{}", titulo(algoritmo, opciones.formato), tabla_algoritmo(conjuntos, opciones));
+    }
+    println!("{}\n{}", titulo("Tabla global", opciones.formato), tabla_global(algoritmos, opciones));
+}
+
+// Guarda la tabla de cada algoritmo y la tabla global en archivos del directorio indicado,
+//   con la misma disposición que generaba resultados2tablas.js
+pub fn guardar_tablas(algoritmos: &[(String, Vec<(String, ResultadosCV)>)], opciones: &OpcionesTabla, directorio: &str) {
+    let directorio = Path::new(directorio);
+    let extension = opciones.formato.extension();
+    for (algoritmo, conjuntos) in algoritmos {
+        let ruta = directorio.join(format!("{}.{}", nombre_archivo(algoritmo), extension));
+        guardar(&ruta, &tabla_algoritmo(conjuntos, opciones));
+    }
+    guardar(&directorio.join(format!("tabla-global.{}", extension)), &tabla_global(algoritmos, opciones));
+}
+
+// Título que precede a una tabla cuando se imprimen varias seguidas
+fn titulo(nombre: &str, formato: FormatoTabla) -> String {
+    match formato {
+        FormatoTabla::Latex => format!("% {}", nombre),
+        FormatoTabla::Markdown => format!("### {}", nombre),
+        FormatoTabla::Html => format!("<h3>{}</h3>", escapar_html(nombre)),
+    }
+}
+
+
+
+// Funciones auxiliares para escribir las tablas
+
+
+// Nombre de archivo para la tabla de un algoritmo: se sustituyen los caracteres que no pueden aparecer en él
+fn nombre_archivo(algoritmo: &str) -> String {
+    algoritmo.chars().map(|c| if c.is_alphanumeric() || "-_.,()+ ".contains(c) { c } else { '_' }).collect()
+}
+
+fn guardar(ruta: &Path, tabla: &str) {
+    if let Err(e) = File::create(ruta).and_then(|mut f| f.write_all(tabla.as_bytes())) {
+        println!("No se pudo escribir {}: {}", ruta.display(), e);
+    }
+}
+
+// Mejor valor de cada columna entre todas las filas (sin contar la de medias)
+fn mejores(tabla: &Tabla) -> Vec<Option<f64>> {
+    let n_columnas = tabla.grupos.len() * COLUMNAS.len();
+    (0..n_columnas).map(|j| {
+        let maximizar = COLUMNAS[j % COLUMNAS.len()].2;
+        tabla.filas.iter().filter_map(|f| f.1[j]).filter(|x| !x.is_nan()).fold(None, |mejor, x| match mejor {
+            Some(m) if (maximizar && m >= x) || (!maximizar && m <= x) => Some(m),
+            _ => Some(x),
+        })
+    }).collect()
+}
+
+// Escribe un valor con la precisión pedida, comparándolo con el mejor de su columna para resaltarlo
+// Se compara el valor ya redondeado para que se resalten todos los que se muestran iguales al mejor
+fn formatear(x: Option<f64>, j: usize, mejores: &[Option<f64>], opciones: &OpcionesTabla) -> (String, bool) {
+    let precision = if j % COLUMNAS.len() == COLUMNAS.len()-1 { 0 } else { opciones.precision };
+    match x {
+        None => (String::new(), false),
+        Some(x) => {
+            let texto = format!("{:.*}", precision, x);
+            let mejor = mejores[j].is_some_and(|m| format!("{:.*}", precision, m) == texto);
+            (texto, mejor)
+        },
+    }
+}
+
+fn escribir(tabla: &Tabla, opciones: &OpcionesTabla) -> String {
+    let mejores = if tabla.resaltar { mejores(tabla) } else { vec![None; tabla.grupos.len() * COLUMNAS.len()] };
+    let celdas = |fila: &[Option<f64>]| -> Vec<(String, bool)> {
+        fila.iter().enumerate().map(|(j, x)| formatear(*x, j, &mejores, opciones)).collect()
+    };
+    let mut filas: Vec<(String, Vec<(String, bool)>)> = tabla.filas.iter().map(|f| (f.0.clone(), celdas(&f.1))).collect();
+    let media = tabla.fila_media.as_ref().map(|m| ("Media".to_string(), celdas(m)));
+
+    match opciones.formato {
+        FormatoTabla::Latex => escribir_latex(&tabla.grupos, &filas, media.as_ref()),
+        FormatoTabla::Markdown => {
+            filas.extend(media.map(|(n, c)| (format!("**{}**", n), c)));
+            escribir_markdown(&tabla.grupos, &filas)
+        },
+        FormatoTabla::Html => escribir_html(&tabla.grupos, &filas, media.as_ref()),
+    }
+}
+
+// Mismo formato que generaba resultados2tablas.js
+fn escribir_latex(grupos: &[String], filas: &[(String, Vec<(String, bool)>)], media: Option<&(String, Vec<(String, bool)>)>) -> String {
+    let n_columnas = 1 + grupos.len() * COLUMNAS.len();
+    let cabeceras: Vec<String> = grupos.iter().enumerate().map(|(i, g)| {
+        let borde = if i+1 < grupos.len() { "|c||" } else { "|c|" };
+        format!("\\multicolumn{{{}}}{{ {} }}{{{}}}", COLUMNAS.len(), borde, escapar_latex(g))
+    }).collect();
+    let subcabeceras: Vec<&str> = grupos.iter().flat_map(|_| COLUMNAS.iter().map(|c| c.0)).collect();
+    let fila = |nombre: &str, celdas: &[(String, bool)]| -> String {
+        let celdas: Vec<String> = celdas.iter().map(|(x, mejor)| {
+            if x.is_empty() { String::new() } else if *mejor { format!("$\\mathbf{{{}}}$", x) } else { format!("${}$", x) }
+        }).collect();
+        format!("\\multicolumn{{1}}{{ |c|  }}{{{}}} & {}", nombre, celdas.join(" & "))
+    };
+
+    let mut texto = format!("\\begin{{tabular}}{{c{}}}\n", "|r|r|r|r|".repeat(grupos.len()));
+    texto += &format!("\\cline{{2-{}}}\n & {}\\\\ \\cline{{2-{}}} \n", n_columnas, cabeceras.join(" & "), n_columnas);
+    texto += &format!(" & {}", subcabeceras.join(" & "));
+    for (nombre, celdas) in filas {
+        texto += &format!("\\\\ \\hline \n{}", fila(&escapar_latex(nombre), celdas));
+    }
+    if let Some((nombre, celdas)) = media {
+        texto += &format!("\\\\ \\hline\n\\hline{}", fila(&format!("\\large{{{}}}", nombre), celdas));
+    }
+    texto + "\\\\ \\hline \n\\end{tabular}\n"
+}
+
+fn escribir_markdown(grupos: &[String], filas: &[(String, Vec<(String, bool)>)]) -> String {
+    let cabeceras: Vec<String> = grupos.iter().flat_map(|g| COLUMNAS.iter().map(move |c| format!("{} {}", g, c.1))).collect();
+    let mut texto = format!("| | {} |\n", cabeceras.join(" | "));
+    texto += &format!("|---|{}\n", "---:|".repeat(cabeceras.len()));
+    for (nombre, celdas) in filas {
+        let celdas: Vec<String> = celdas.iter().map(|(x, mejor)| if *mejor { format!("**{}**", x) } else { x.clone() }).collect();
+        texto += &format!("| {} | {} |\n", nombre.replace('|', "\\|"), celdas.join(" | "));
+    }
+    texto
+}
+
+fn escribir_html(grupos: &[String], filas: &[(String, Vec<(String, bool)>)], media: Option<&(String, Vec<(String, bool)>)>) -> String {
+    let fila = |nombre: &str, celdas: &[(String, bool)]| -> String {
+        let celdas: String = celdas.iter().map(|(x, mejor)| {
+            if *mejor { format!("<td><b>{}</b></td>", x) } else { format!("<td>{}</td>", x) }
+        }).collect();
+        format!("<tr><th>{}</th>{}</tr>\n", escapar_html(nombre), celdas)
+    };
+
+    let mut texto = String::from("<table>\n<thead>\n<tr><th></th>");
+    for g in grupos {
+        texto += &format!("<th colspan=\"{}\">{}</th>", COLUMNAS.len(), escapar_html(g));
+    }
+    texto += "</tr>\n<tr><th></th>";
+    for _ in grupos {
+        for c in &COLUMNAS {
+            texto += &format!("<th>{}</th>", escapar_html(c.1));
+        }
+    }
+    texto += "</tr>\n</thead>\n<tbody>\n";
+    for (nombre, celdas) in filas {
+        texto += &fila(nombre, celdas);
+    }
+    texto += "</tbody>\n";
+    if let Some((nombre, celdas)) = media {
+        texto += &format!("<tfoot>\n{}</tfoot>\n", fila(nombre, celdas));
+    }
+    texto + "</table>\n"
+}
+
+fn escapar_latex(s: &str) -> String {
+    s.chars().map(|c| match c {
+        '\\' => "\\textbackslash{}".to_string(),
+        '&' | '%' | '$' | '#' | '_' | '{' | '}' => format!("\\{}", c),
+        c => c.to_string(),
+    }).collect()
+}
+
+fn escapar_html(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use evaluacion_pesos::MatrizConfusion;
+
+    fn test(particion: usize, tasa_clas: f64, tasa_red: f64, tiempo_ms: i64) -> ResultadoTest {
+        ResultadoTest {
+            repeticion: 0, particion, ejecucion: 0,
+            tasa_clas, tasa_red, agregado: (tasa_clas + tasa_red) / 2.0, tiempo_ms,
+            evaluaciones: 0, aciertos_cache: 0, semilla: 0, semilla_test: 0,
+            pesos: Vec::new(), matriz: MatrizConfusion(Vec::new()),
+        }
+    }
+
+    fn resultados(tests: Vec<ResultadoTest>) -> ResultadosCV {
+        ResultadosCV { tests }
+    }
+
+    // Dos algoritmos sobre dos conjuntos; el segundo algoritmo no tiene resultados del segundo conjunto
+    fn algoritmos() -> Vec<(String, Vec<(String, ResultadosCV)>)> {
+        vec![
+            ("1-NN".to_string(), vec![
+                ("datos/a.arff".to_string(), resultados(vec![test(0, 80.0, 0.0, 10), test(1, 90.0, 0.0, 20)])),
+                ("datos/b_c.arff".to_string(), resultados(vec![test(0, 70.0, 0.0, 4)])),
+            ]),
+            ("RELIEF".to_string(), vec![
+                ("datos/a.arff".to_string(), resultados(vec![test(0, 85.0, 50.0, 30), test(1, 85.0, 30.0, 40)])),
+            ]),
+        ]
+    }
+
+    fn opciones(formato: FormatoTabla) -> OpcionesTabla {
+        OpcionesTabla { formato, ..OpcionesTabla::default() }
+    }
+
+    #[test]
+    fn formato_desde_texto() {
+        assert_eq!("LaTeX".parse(), Ok(FormatoTabla::Latex));
+        assert_eq!(" md ".parse(), Ok(FormatoTabla::Markdown));
+        assert_eq!("html".parse::<FormatoTabla>().map(|f| f.extension()), Ok("html"));
+        assert!("csv".parse::<FormatoTabla>().is_err());
+    }
+
+    #[test]
+    fn tabla_de_un_algoritmo_con_medias() {
+        let tabla = tabla_algoritmo(&algoritmos()[0].1, &opciones(FormatoTabla::Markdown));
+        let lineas: Vec<&str> = tabla.lines().collect();
+        assert_eq!(lineas[0], "| | a %_clas | a %_red | a Agr. | a T (ms) | b_c %_clas | b_c %_red | b_c Agr. | b_c T (ms) |");
+        assert_eq!(lineas[2], "| Partición 1 | 80.00 | 0.00 | 40.00 | 10 | 70.00 | 0.00 | 35.00 | 4 |");
+        // El segundo conjunto no tiene segunda partición: sus celdas quedan vacías
+        assert_eq!(lineas[3], "| Partición 2 | 90.00 | 0.00 | 45.00 | 20 |  |  |  |  |");
+        assert_eq!(lineas[4], "| **Media** | 85.00 | 0.00 | 42.50 | 15 | 70.00 | 0.00 | 35.00 | 4 |");
+        assert_eq!(lineas.len(), 5);
+    }
+
+    #[test]
+    fn tabla_global_resalta_el_mejor_de_cada_columna() {
+        let tabla = tabla_global(&algoritmos(), &opciones(FormatoTabla::Markdown));
+        let lineas: Vec<&str> = tabla.lines().collect();
+        // Empatan en %_clas; en tiempo es mejor el menor
+        assert_eq!(lineas[2], "| 1-NN | **85.00** | 0.00 | 42.50 | **15** | **70.00** | **0.00** | **35.00** | **4** |");
+        assert_eq!(lineas[3], "| RELIEF | **85.00** | **40.00** | **62.50** | 35 |  |  |  |  |");
+
+        let sin_resaltar = OpcionesTabla { resaltar: false, precision: 1, ..opciones(FormatoTabla::Markdown) };
+        let tabla = tabla_global(&algoritmos(), &sin_resaltar);
+        assert!(!tabla.contains("**"));
+        assert!(tabla.contains("| RELIEF | 85.0 | 40.0 | 62.5 | 35 |"));
+    }
+
+    #[test]
+    fn tabla_latex() {
+        let tabla = tabla_global(&algoritmos(), &opciones(FormatoTabla::Latex));
+        assert!(tabla.starts_with("\\begin{tabular}{c|r|r|r|r||r|r|r|r|}\n"));
+        assert!(tabla.contains("\\multicolumn{4}{ |c|| }{a} & \\multicolumn{4}{ |c| }{b\\_c}"));
+        assert!(tabla.contains("{RELIEF} & $\\mathbf{85.00}$ & $\\mathbf{40.00}$ & $\\mathbf{62.50}$ & $35$ &  &  &  & "));
+        assert!(tabla.ends_with("\\end{tabular}\n"));
+
+        let tabla = tabla_algoritmo(&algoritmos()[1].1, &opciones(FormatoTabla::Latex));
+        assert!(tabla.contains("\\hline\\multicolumn{1}{ |c|  }{\\large{Media}} & $85.00$ & $40.00$ & $62.50$ & $35$"));
+        assert!(!tabla.contains("mathbf"));
+    }
+
+    #[test]
+    fn tabla_html() {
+        let tabla = tabla_algoritmo(&algoritmos()[1].1, &opciones(FormatoTabla::Html));
+        assert!(tabla.contains("<th colspan=\"4\">a</th>"));
+        assert!(tabla.contains("<tr><th>Partición 2</th><td>85.00</td><td>30.00</td><td>57.50</td><td>40</td></tr>"));
+        assert!(tabla.contains("<tfoot>\n<tr><th>Media</th><td>85.00</td><td>40.00</td><td>62.50</td><td>35</td></tr>\n</tfoot>"));
+        assert_eq!(titulo("a<b", FormatoTabla::Html), "<h3>a&lt;b</h3>");
+    }
+
+    #[test]
+    fn escapes_y_nombres() {
+        assert_eq!(escapar_latex("a_b & 50% {x} \\"), "a\\_b \\& 50\\% \\{x\\} \\textbackslash{}");
+        assert_eq!(escapar_html("<a href=\"x\">&</a>"), "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;");
+        assert_eq!(nombre_archivo("BL+ES (k=3)/x:y"), "BL+ES (k_3)_x_y");
+        assert_eq!(nombre_conjunto("instancias/ozone-320.arff"), "ozone-320");
+    }
+}
@@ -0,0 +1,316 @@
+// Trazas de convergencia de las metaheurísticas
+// Los algoritmos informan de su progreso (evaluaciones hechas, puntuación de la solución actual, de la mejor
+//   y otros valores como la temperatura o la diversidad de la población) a un observador que se guarda por
+//   hilo, como los parámetros, para no cambiar su interfaz. Si no hay observador no se hace nada
+// Cuando un algoritmo usa otro (la búsqueda local de ILS o de los meméticos), solo informa el exterior
+
+use evaluacion_pesos::ResultadoTest;
+use presupuesto::Evaluador;
+use problema::Problema;
+use resultados::campo_csv;
+use std::cell::{Cell, RefCell};
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::rc::Rc;
+
+
+// Estado de un algoritmo en un momento de su ejecución
+#[derive(Clone, Debug)]
+pub struct PuntoTraza {
+    pub evaluaciones: usize,        // Evaluaciones de la función objetivo hechas en el test hasta ahora
+    pub actual: f64,                // Puntuación de la solución actual (en la búsqueda local, del último vecino) o media de la población
+    pub mejor: f64,                 // Puntuación de la mejor solución encontrada
+    pub temperatura: Option<f64>,   // Temperatura, en el enfriamiento simulado
+    pub diversidad: Option<Diversidad>,  // Diversidad de la población, en los algoritmos poblacionales
+}
+
+// Función a la que se informa de cada punto de la traza
+pub type Observador = Box<dyn FnMut(&PuntoTraza)>;
+
+// Observador al que se informa del progreso de los algoritmos del hilo actual, y profundidad de
+//   anidamiento de los algoritmos que se están ejecutando
+thread_local! {
+    static OBSERVADOR: RefCell<Option<Observador>> = RefCell::new(None);
+    static PROFUNDIDAD: Cell<usize> = const { Cell::new(0) };
+}
+
+// Cambia el observador al que informan los algoritmos del hilo actual
+pub fn fijar_observador(observador: Option<Observador>) {
+    OBSERVADOR.with(|o| *o.borrow_mut() = observador);
+}
+
+// Indica si hay un observador, para que los algoritmos no calculen valores que nadie va a usar
+pub fn activa() -> bool {
+    OBSERVADOR.with(|o| o.borrow().is_some())
+}
+
+// Ámbito de la ejecución de un algoritmo: mientras exista, los algoritmos que use no informan
+pub struct Ambito;
+
+// Marca el comienzo de un algoritmo que informa de su progreso
+pub fn entrar() -> Ambito {
+    PROFUNDIDAD.with(|p| p.set(p.get() + 1));
+    Ambito
+}
+
+impl Drop for Ambito {
+    fn drop(&mut self) {
+        PROFUNDIDAD.with(|p| p.set(p.get() - 1));
+    }
+}
+
+// Informa al observador del estado de un algoritmo, si no lo está usando otro algoritmo
+// Las evaluaciones son las que ha contado el evaluador del algoritmo
+pub fn registrar(evaluador: &Evaluador, actual: f64, mejor: f64, temperatura: Option<f64>, diversidad: Option<Diversidad>) {
+    if PROFUNDIDAD.with(|p| p.get()) != 1 { return }
+    OBSERVADOR.with(|o| if let Some(observador) = o.borrow_mut().as_mut() {
+        observador(&PuntoTraza { evaluaciones: evaluador.usadas(), actual, mejor, temperatura, diversidad });
+    });
+}
+
+// Ejecuta una función guardando la traza de los algoritmos que ejecute, si se pide, y la devuelve junto a su resultado
+pub fn grabar<T, F: FnOnce() -> T>(trazar: bool, f: F) -> (T, Vec<PuntoTraza>) {
+    if !trazar { return (f(), Vec::new()) }
+    let puntos = Rc::new(RefCell::new(Vec::new()));
+    let destino = puntos.clone();
+    fijar_observador(Some(Box::new(move |p: &PuntoTraza| destino.borrow_mut().push(p.clone()))));
+    let resultado = f();
+    fijar_observador(None);
+    let puntos = puntos.replace(Vec::new());
+    (resultado, puntos)
+}
+
+// Medidas de la diversidad de una población
+#[derive(Clone, Copy, Debug)]
+pub struct Diversidad {
+    pub distancia_media: f64,   // Media de la distancia euclídea entre cada par de cromosomas normalizados, dividida por la raíz del número de genes
+    pub entropia: f64,          // Media de la entropía de cada gen normalizado y discretizado en INTERVALOS_ENTROPIA intervalos, entre 0 y 1
+    pub varianza: f64,          // Varianza de las puntuaciones de los cromosomas
+}
+
+// Número de intervalos en los que se divide [0, 1] para calcular la entropía de cada gen
+const INTERVALOS_ENTROPIA: usize = 10;
+
+// Calcula la diversidad de una población de un problema, dada por la puntuación y la solución de cada cromosoma
+// Cada gen se lleva a [0, 1] según los límites del problema, de forma que la distancia media y la entropía
+//   están entre 0 y 1 en cualquier problema, y valen 0 si todos los cromosomas son iguales
+pub fn diversidad(problema: &dyn Problema, poblacion: &[(f64, &[f64])]) -> Diversidad {
+    let n = poblacion.len();
+    let n_genes = poblacion.first().map_or(0, |c| c.1.len());
+    if n == 0 || n_genes == 0 {
+        return Diversidad { distancia_media: 0.0, entropia: 0.0, varianza: 0.0 };
+    }
+    let limites: Vec<(f64, f64)> = (0..n_genes).map(|i| problema.limites(i)).collect();
+    let normalizados: Vec<Vec<f64>> = poblacion.iter().map(|c| c.1.iter().zip(&limites).map(|(x, (a, b))| {
+        if b > a { (x - a) / (b - a) } else { 0.0 }
+    }).collect()).collect();
+
+    let mut suma_distancias = 0.0;
+    for i in 0..n {
+        for j in i+1..n {
+            suma_distancias += normalizados[i].iter().zip(&normalizados[j]).map(|(a, b)| (a - b).powi(2)).sum::<f64>().sqrt();
+        }
+    }
+    let pares = n*(n-1)/2;
+    let distancia_media = if pares == 0 { 0.0 } else { suma_distancias / pares as f64 / (n_genes as f64).sqrt() };
+
+    let entropia = (0..n_genes).map(|g| {
+        let mut frecuencias = [0usize; INTERVALOS_ENTROPIA];
+        for c in &normalizados {
+            let intervalo = (c[g].max(0.0) * INTERVALOS_ENTROPIA as f64) as usize;
+            frecuencias[intervalo.min(INTERVALOS_ENTROPIA - 1)] += 1;
+        }
+        -frecuencias.iter().filter(|f| **f > 0).map(|f| {
+            let p = *f as f64 / n as f64;
+            p * p.ln()
+        }).sum::<f64>() / (INTERVALOS_ENTROPIA as f64).ln()
+    }).sum::<f64>() / n_genes as f64;
+
+    let media = poblacion.iter().map(|c| c.0).sum::<f64>() / n as f64;
+    let varianza = poblacion.iter().map(|c| (c.0 - media).powi(2)).sum::<f64>() / n as f64;
+
+    Diversidad { distancia_media, entropia, varianza }
+}
+
+
+
+// Columnas de los archivos de trazas
+const COLUMNAS_CSV: &str = "algoritmo,conjunto,repeticion,particion,ejecucion,evaluaciones,actual,mejor,temperatura,distancia_media,entropia,varianza";
+
+// Archivo CSV en el que se escriben las trazas de todos los tests, una fila por cada punto
+pub struct ArchivoTraza {
+    archivo: File,
+}
+
+impl ArchivoTraza {
+    // Crea (o vacía) el archivo indicado y escribe la cabecera
+    pub fn crear(ruta: &str) -> Result<ArchivoTraza, io::Error> {
+        let mut archivo = File::create(ruta)?;
+        writeln!(archivo, "{}", COLUMNAS_CSV)?;
+        Ok(ArchivoTraza { archivo })
+    }
+
+    // Escribe la traza de un test de un algoritmo sobre un conjunto de datos
+    pub fn escribir(&mut self, algoritmo: &str, conjunto: &str, test: &ResultadoTest, puntos: &[PuntoTraza]) -> Result<(), io::Error> {
+        let opcional = |x: Option<f64>| x.map_or(String::new(), |x| x.to_string());
+        for p in puntos {
+            writeln!(self.archivo, "{},{},{},{},{},{},{},{},{},{},{},{}", campo_csv(algoritmo), campo_csv(conjunto),
+                     test.repeticion + 1, test.particion + 1, test.ejecucion + 1, p.evaluaciones, p.actual, p.mejor,
+                     opcional(p.temperatura), opcional(p.diversidad.map(|d| d.distancia_media)),
+                     opcional(p.diversidad.map(|d| d.entropia)), opcional(p.diversidad.map(|d| d.varianza)))?;
+        }
+        self.archivo.flush()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aprendiz::Aprendiz;
+    use catalogo;
+    use evaluacion_pesos;
+    use evaluacion_pesos::MatrizConfusion;
+    use knn::Dato;
+    use presupuesto::Presupuesto;
+    use problema::pruebas::Parabola;
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    // Problema con todas las componentes en los mismos límites, de cuya función objetivo no depende la diversidad
+    fn problema_en(inferior: f64, superior: f64) -> Parabola {
+        Parabola::nueva(2, (inferior, superior))
+    }
+
+    fn punto(evaluaciones: usize, mejor: f64) -> PuntoTraza {
+        PuntoTraza { evaluaciones, actual: mejor / 2.0, mejor, temperatura: None, diversidad: None }
+    }
+
+    // Ejecuta un algoritmo del catálogo en unos datos pequeños con pocas evaluaciones y devuelve su traza
+    fn traza_de(clave: &str) -> Vec<PuntoTraza> {
+        let datos: Vec<Dato> = (0..20).map(|i| {
+            Dato::new(&[(i % 4) as f64 / 4.0, (i * 7 % 5) as f64 / 5.0, (i % 2) as f64], &[], i % 2)
+        }).collect();
+        let aprendiz = catalogo::buscar(clave).unwrap().aprendiz();
+        let presupuesto = Presupuesto { evaluaciones: Some(300), ..Default::default() };
+        let (_, traza) = grabar(true, || {
+            evaluacion_pesos::evaluar_test(&|d: &[Dato], rng: &mut _| aprendiz.aprender(d, presupuesto, rng), &datos[..16], &datos[16..], 0, 0, 0, 1)
+        });
+        traza
+    }
+
+    #[test]
+    fn solo_informa_el_algoritmo_exterior() {
+        let (resultado, puntos) = grabar(true, || {
+            assert!(activa());
+            let evaluador = Evaluador::nuevo(Presupuesto::default());
+            let _exterior = entrar();
+            registrar(&evaluador, 1.0, 2.0, None, None);
+            {
+                let _interior = entrar();
+                registrar(&evaluador, 10.0, 20.0, None, None);
+            }
+            registrar(&evaluador, 3.0, 4.0, Some(0.5), None);
+            5
+        });
+        assert_eq!(resultado, 5);
+        assert_eq!(puntos.iter().map(|p| (p.actual, p.mejor, p.temperatura)).collect::<Vec<_>>(),
+                   vec![(1.0, 2.0, None), (3.0, 4.0, Some(0.5))]);
+        assert!(!activa());
+
+        // Sin grabar no hay observador, y los algoritmos no informan de nada
+        let (resultado, puntos) = grabar(false, || {
+            let _ambito = entrar();
+            registrar(&Evaluador::nuevo(Presupuesto::default()), 1.0, 2.0, None, None);
+            activa()
+        });
+        assert!(!resultado && puntos.is_empty());
+    }
+
+    #[test]
+    fn diversidad_de_poblaciones() {
+        let iguales = [0.3, 0.7];
+        let d = diversidad(&problema_en(0.0, 1.0), &[(1.0, &iguales), (1.0, &iguales), (1.0, &iguales)]);
+        assert_eq!((d.distancia_media, d.entropia, d.varianza), (0.0, 0.0, 0.0));
+
+        let (a, b) = ([0.0, 0.0], [1.0, 1.0]);
+        let d = diversidad(&problema_en(0.0, 1.0), &[(0.0, &a), (1.0, &b)]);
+        assert!((d.distancia_media - 1.0).abs() < 1e-12);
+        assert!((d.entropia - 2f64.ln() / 10f64.ln()).abs() < 1e-12);
+        assert!((d.varianza - 0.25).abs() < 1e-12);
+
+        // Con un cromosoma en cada intervalo la entropía es la máxima
+        let genes: Vec<[f64; 1]> = (0..INTERVALOS_ENTROPIA).map(|i| [(i as f64 + 0.5) / INTERVALOS_ENTROPIA as f64]).collect();
+        let poblacion: Vec<(f64, &[f64])> = genes.iter().map(|g| (0.0, &g[..])).collect();
+        assert!((diversidad(&problema_en(0.0, 1.0), &poblacion).entropia - 1.0).abs() < 1e-12);
+
+        let d = diversidad(&problema_en(0.0, 1.0), &[]);
+        assert_eq!((d.distancia_media, d.entropia, d.varianza), (0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn diversidad_en_otros_limites() {
+        // La misma población en [-5, 5] tiene la misma diversidad que en [0, 1], y no es mayor que 1
+        let en_unidad = [[0.0, 0.1], [0.5, 0.5], [1.0, 0.95]];
+        let en_caja: Vec<Vec<f64>> = en_unidad.iter().map(|c| c.iter().map(|x| x*10.0 - 5.0).collect()).collect();
+        let unidad = diversidad(&problema_en(0.0, 1.0), &en_unidad.iter().map(|c| (0.0, &c[..])).collect::<Vec<_>>());
+        let caja = diversidad(&problema_en(-5.0, 5.0), &en_caja.iter().map(|c| (0.0, c.as_slice())).collect::<Vec<_>>());
+        assert!((unidad.distancia_media - caja.distancia_media).abs() < 1e-12);
+        assert!((unidad.entropia - caja.entropia).abs() < 1e-12);
+        assert!(caja.distancia_media <= 1.0 && caja.entropia <= 1.0);
+
+        // Los extremos opuestos de la caja están a distancia 1, y cada uno en un intervalo distinto de la entropía
+        let (a, b) = ([-5.0, -5.0], [5.0, 5.0]);
+        let d = diversidad(&problema_en(-5.0, 5.0), &[(0.0, &a), (0.0, &b)]);
+        assert!((d.distancia_media - 1.0).abs() < 1e-12);
+        assert!((d.entropia - 2f64.ln() / 10f64.ln()).abs() < 1e-12);
+
+        // Si todos los valores negativos fuesen el mismo intervalo, como sin normalizar, la entropía sería menor
+        let negativos = [[-4.5, 0.0], [-3.5, 0.0], [-2.5, 0.0]];
+        let d = diversidad(&problema_en(-5.0, 5.0), &negativos.iter().map(|c| (0.0, &c[..])).collect::<Vec<_>>());
+        assert!((d.entropia - 3f64.ln() / 10f64.ln() / 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn trazas_de_los_algoritmos() {
+        for clave in &["busqueda_local", "es", "agg_blx", "de_rand_1"] {
+            let traza = traza_de(clave);
+            assert!(!traza.is_empty(), "{} no informa de su progreso", clave);
+            assert!(traza.windows(2).all(|p| p[0].evaluaciones <= p[1].evaluaciones && p[0].mejor <= p[1].mejor),
+                    "la traza de {} no es monótona", clave);
+            assert!(traza.iter().all(|p| p.evaluaciones <= 300 && p.actual <= p.mejor + 1e-9));
+        }
+        assert!(traza_de("es").iter().all(|p| p.temperatura.is_some_and(|t| t > 0.0)));
+        assert!(traza_de("agg_blx").iter().all(|p| p.diversidad.is_some()));
+        assert!(traza_de("busqueda_local").iter().all(|p| p.temperatura.is_none() && p.diversidad.is_none()));
+    }
+
+    #[test]
+    fn archivo_de_trazas() {
+        let ruta = env::temp_dir().join(format!("traza-{}.csv", process::id()));
+        let test = ResultadoTest {
+            repeticion: 0, particion: 1, ejecucion: 2,
+            tasa_clas: 80.0, tasa_red: 50.0, agregado: 65.0, tiempo_ms: 1,
+            evaluaciones: 20, aciertos_cache: 0,
+            semilla: 1, semilla_test: 2,
+            pesos: vec![1.0],
+            matriz: MatrizConfusion(Vec::new()),
+        };
+        let mut con_diversidad = punto(20, 0.75);
+        con_diversidad.temperatura = Some(2.5);
+        con_diversidad.diversidad = Some(Diversidad { distancia_media: 0.5, entropia: 0.25, varianza: 0.125 });
+        {
+            let mut archivo = ArchivoTraza::crear(ruta.to_str().unwrap()).unwrap();
+            archivo.escribir("AGG, BLX", "iris", &test, &[punto(10, 0.5), con_diversidad]).unwrap();
+        }
+        let contenido = fs::read_to_string(&ruta).unwrap();
+        assert_eq!(contenido.lines().collect::<Vec<_>>(), vec![
+            COLUMNAS_CSV,
+            "\"AGG, BLX\",iris,1,2,3,10,0.25,0.5,,,,",
+            "\"AGG, BLX\",iris,1,2,3,20,0.375,0.75,2.5,0.5,0.25,0.125",
+        ]);
+        fs::remove_file(&ruta).unwrap();
+    }
+}
@@ -0,0 +1,127 @@
+// Uso de la biblioteca desde otro crate, como en los ejemplos de examples/
+// Cada test hace lo mismo que uno de los ejemplos con datos y presupuestos pequeños, y comprueba el resultado
+
+#[macro_use] extern crate aprendizaje_pesos;
+extern crate rand;
+
+use aprendizaje_pesos::aprendiz::{Aprendiz, Tuberia};
+use aprendizaje_pesos::evaluacion_pesos;
+use aprendizaje_pesos::evaluacion_pesos::Validacion;
+use aprendizaje_pesos::funciones_practica1::{busqueda_local_desde, relief};
+use aprendizaje_pesos::funciones_practica2::agg_blx;
+use aprendizaje_pesos::funciones_practica3::{differential_evolution_general, op_rand_1};
+use aprendizaje_pesos::json::Valor;
+use aprendizaje_pesos::knn::{Dato, Normalizacion};
+use aprendizaje_pesos::parametros::Parametros;
+use aprendizaje_pesos::planificador;
+use aprendizaje_pesos::planificador::{Datos, Planificacion};
+use aprendizaje_pesos::problema;
+use aprendizaje_pesos::presupuesto::{Evaluador, Presupuesto};
+use aprendizaje_pesos::problema::Problema;
+use rand::{Isaac64Rng, Rng, SeedableRng};
+
+
+// Datos con 4 atributos en [0, 10] en los que la clase solo depende del primero, normalizados a [0, 1]
+fn datos(n: usize) -> Vec<Dato> {
+    let mut rng = evaluacion_pesos::rng_semilla(2018);
+    let mut datos: Vec<Dato> = (0..n).map(|_| {
+        let atributos: Vec<f64> = (0..4).map(|_| rng.gen_range(0.0, 10.0)).collect();
+        let clase = if atributos[0] > 5.0 { 1 } else { 0 };
+        Dato::new(&atributos, &[], clase)
+    }).collect();
+    Normalizacion::ajustar(&datos).aplicar(&mut datos);
+    datos
+}
+
+fn parametros() -> Parametros {
+    Parametros { max_evaluaciones: 300, ..Parametros::default() }
+}
+
+#[test]
+fn aprender_pesos_con_datos_propios() {
+    let datos = datos(60);
+    let aprendices: Vec<Box<dyn Aprendiz>> = vec![
+        Box::new(aprendiz!(relief, "RELIEF")),
+        Box::new(aprendiz!(agg_blx, "AGG_BLX").con_parametros(parametros())),
+        Box::new(Tuberia::desde(aprendiz!(relief, "RELIEF")).mejora("busqueda_local_desde", busqueda_local_desde)
+                     .parametros(parametros()).construir()),
+    ];
+    for aprendiz in &aprendices {
+        let aprendizaje = aprendiz.aprender(&datos, Presupuesto::default(), &mut evaluacion_pesos::rng_semilla(1));
+        assert_eq!(aprendizaje.pesos.len(), 4);
+        assert!(aprendizaje.pesos.iter().all(|w| (0.0..=1.0).contains(w)));
+        assert!(aprendizaje.evaluaciones <= 300, "{} se pasa del presupuesto", aprendiz.nombre());
+        // El atributo del que depende la clase es el que más pesa
+        assert!(aprendizaje.pesos[1..].iter().all(|w| *w <= aprendizaje.pesos[0]), "{}: {:?}", aprendiz.nombre(), aprendizaje.pesos);
+
+        let resultados = evaluacion_pesos::ffcv(&|d: &[Dato], r: &mut Isaac64Rng| aprendiz.aprender(d, Presupuesto::default(), r), &datos, 42, Validacion::KFold(3, 1), 1).unwrap();
+        assert_eq!(resultados.tests.len(), 3);
+    }
+    assert_eq!(aprendices[2].a_json().campo("mejoras").map(|m| m.to_string()), Some(r#"["busqueda_local_desde"]"#.to_string()));
+}
+
+// Búsqueda aleatoria definida fuera de la biblioteca, como en el ejemplo aprendiz_propio
+struct BusquedaAleatoria {
+    intentos: usize,
+}
+
+impl Aprendiz for BusquedaAleatoria {
+    fn nombre(&self) -> &str { "Búsqueda aleatoria" }
+
+    fn parametros(&self) -> Option<Parametros> { None }
+
+    fn pesos(&self, entrenamiento: &[Dato], evaluador: &Evaluador, rng: &mut Isaac64Rng) -> Vec<f64> {
+        let n = entrenamiento[0].num_atributos();
+        (0..self.intentos).map(|_| {
+            let w: Vec<f64> = (0..n).map(|_| rng.gen_range(0.0, 1.0)).collect();
+            (evaluacion_pesos::evaluar(evaluador, entrenamiento, &w), w)
+        }).fold((-1.0, Vec::new()), |mejor, s| if s.0 > mejor.0 { s } else { mejor }).1
+    }
+
+    fn a_json(&self) -> Valor {
+        objeto_json!{ "nombre" => self.nombre(), "intentos" => self.intentos }
+    }
+}
+
+#[test]
+fn aprendiz_propio_en_el_planificador() {
+    let aleatoria = BusquedaAleatoria { intentos: 20 };
+    let relief = aprendiz!(relief, "RELIEF");
+    let aprendices: Vec<&dyn Aprendiz> = vec![&relief, &aleatoria];
+    let conjuntos = vec![("propios", Datos::Archivo(datos(40)))];
+    let plan = Planificacion { semilla: 42, validacion: Validacion::KFold(4, 1), ejecuciones: 1, repetir: None, hilos: 2, presupuesto: Presupuesto::default() };
+
+    let mut terminados = Vec::new();
+    let vacios = planificador::ejecutar(&aprendices, &conjuntos, &plan, None, None, |c, a, resultados| {
+        assert!(resultados.tests.iter().all(|t| t.pesos.len() == 4));
+        terminados.push((c, a, resultados.tests.len(), resultados.tests.iter().map(|t| t.evaluaciones).sum::<usize>()));
+    });
+    assert!(vacios.is_empty());
+    assert_eq!(terminados, vec![(0, 0, 4, 0), (0, 1, 4, 4*20)]);
+}
+
+// Función de Rosenbrock cambiada de signo en [-2, 2]^n, como en el ejemplo problema_propio
+struct Rosenbrock;
+
+impl Problema for Rosenbrock {
+    fn dimension(&self) -> usize { 3 }
+
+    fn limites(&self, _i: usize) -> (f64, f64) { (-2.0, 2.0) }
+
+    fn funcion_objetivo(&self, x: &[f64]) -> f64 {
+        -x.windows(2).map(|p| 100.0*(p[1] - p[0]*p[0]).powi(2) + (1.0 - p[0]).powi(2)).sum::<f64>()
+    }
+}
+
+#[test]
+fn problema_propio() {
+    let mut rng = Isaac64Rng::from_seed(&[42]);
+    let evaluador = Evaluador::nuevo(Presupuesto::default());
+    let inicial = problema::solucion_aleatoria(&Rosenbrock, &mut rng);
+    let x = differential_evolution_general(&Rosenbrock, &problema::solucion_aleatoria, &op_rand_1, &evaluador, &mut rng);
+    assert_eq!(x.len(), 3);
+    assert!(x.iter().all(|xi| (-2.0..=2.0).contains(xi)));
+    assert_eq!(evaluador.usadas(), Parametros::default().max_evaluaciones);
+    assert!(Rosenbrock.funcion_objetivo(&x) > Rosenbrock.funcion_objetivo(&inicial));
+    assert!(Rosenbrock.funcion_objetivo(&x) > -0.1);
+}